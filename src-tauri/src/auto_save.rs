@@ -0,0 +1,383 @@
+// JustSnap - Auto-Save Module
+// Resolves filename templates like `{date:%Y-%m-%d}/{app_name}_{time}_{n}.{ext}` and writes captures atomically
+
+use crate::capture_store::CaptureSource;
+use crate::encoding::{encode_image, OutputFormat};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use image::RgbaImage;
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Template used when the settings do not name one
+pub const DEFAULT_TEMPLATE: &str = "JustSnap_{date}_{time}.{ext}";
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H-%M-%S";
+
+/// Stand-in for metadata the capture does not have (e.g. no source window)
+const UNKNOWN: &str = "unknown";
+
+/// Give up looking for a free file name after this many attempts
+const MAX_COLLISIONS: u32 = 10_000;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Placeholder {
+    /// `{date}` / `{time}` with their chrono format
+    Timestamp(String),
+    AppName,
+    WindowTitle,
+    Monitor,
+    /// `{n}` / `{n:<width>}`: collision counter, zero padded to width
+    Counter(usize),
+    Ext,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// A parsed filename template
+///
+/// Placeholders: `{date}`, `{time}` (both accept a chrono format, e.g. `{date:%Y%m%d}`),
+/// `{app_name}`, `{title}`, `{monitor}`, `{n}` (collision counter, `{n:3}` pads to 001) and
+/// `{ext}`. `/` in the template creates sub-directories; filled-in values never do.
+#[derive(Clone, Debug)]
+pub struct SaveTemplate {
+    segments: Vec<Segment>,
+}
+
+impl SaveTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find(&['{', '}'][..]) {
+            if rest[start..].starts_with('}') {
+                return Err(format!("Unmatched '}}' in template '{}'", template));
+            }
+            literal.push_str(&rest[..start]);
+
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| format!("Unclosed '{{' in template '{}'", template))?;
+
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Placeholder(parse_placeholder(
+                &rest[start + 1..end],
+            )?));
+            rest = &rest[end + 1..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        let template = Self { segments };
+        template.check_literal_path()?;
+        Ok(template)
+    }
+
+    fn has_counter(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Placeholder(Placeholder::Counter(_))))
+    }
+
+    /// The template must stay inside the save folder
+    fn check_literal_path(&self) -> Result<(), String> {
+        let literal: String = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(text) => text.as_str(),
+                Segment::Placeholder(_) => "x",
+            })
+            .collect();
+        let path = Path::new(&literal);
+
+        if literal.is_empty() || literal.ends_with(&['/', '\\'][..]) {
+            return Err("Template does not name a file".to_string());
+        }
+        if path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(format!(
+                "Template must be a relative path inside the save folder: '{}'",
+                literal
+            ));
+        }
+        Ok(())
+    }
+
+    /// Relative path for a capture. `counter` fills `{n}`; without `{n}` in the template a
+    /// counter is appended to the file name instead (`name_2.png`).
+    pub fn render(
+        &self,
+        captured_at: &DateTime<Local>,
+        source: &CaptureSource,
+        ext: &str,
+        counter: Option<u32>,
+    ) -> PathBuf {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Placeholder(placeholder) => {
+                    let value = match placeholder {
+                        Placeholder::Timestamp(format) => captured_at.format(format).to_string(),
+                        Placeholder::AppName => source_value(&source.app_name),
+                        Placeholder::WindowTitle => source_value(&source.window_title),
+                        Placeholder::Monitor => source_value(&source.monitor_name),
+                        Placeholder::Counter(width) => {
+                            format!("{:0width$}", counter.unwrap_or(1), width = *width)
+                        }
+                        Placeholder::Ext => ext.to_string(),
+                    };
+                    rendered.push_str(&sanitize_component(&value));
+                }
+            }
+        }
+
+        let mut path: PathBuf = rendered
+            .split(&['/', '\\'][..])
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect();
+
+        if let (Some(counter), false) = (counter, self.has_counter()) {
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let file_name = match path.extension() {
+                Some(ext) => format!("{}_{}.{}", stem, counter, ext.to_string_lossy()),
+                None => format!("{}_{}", stem, counter),
+            };
+            path.set_file_name(file_name);
+        }
+
+        path
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Placeholder, String> {
+    let (name, arg) = match placeholder.split_once(':') {
+        Some((name, arg)) => (name.trim(), Some(arg)),
+        None => (placeholder.trim(), None),
+    };
+
+    let time_format = |default: &str| -> Result<String, String> {
+        let format = arg.unwrap_or(default);
+        if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+            return Err(format!("Invalid date/time format '{}'", format));
+        }
+        Ok(format.to_string())
+    };
+
+    let placeholder = match name {
+        "date" => Placeholder::Timestamp(time_format(DEFAULT_DATE_FORMAT)?),
+        "time" => Placeholder::Timestamp(time_format(DEFAULT_TIME_FORMAT)?),
+        "app_name" | "app" => Placeholder::AppName,
+        "title" | "window_title" => Placeholder::WindowTitle,
+        "monitor" => Placeholder::Monitor,
+        "n" => Placeholder::Counter(match arg {
+            Some(width) => width
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid counter width '{}'", width))?,
+            None => 0,
+        }),
+        "ext" => Placeholder::Ext,
+        _ => return Err(format!("Unknown placeholder '{{{}}}'", placeholder)),
+    };
+
+    if arg.is_some() && !matches!(name, "date" | "time" | "n") {
+        return Err(format!("'{{{}}}' does not take a format", name));
+    }
+    Ok(placeholder)
+}
+
+fn source_value(value: &Option<String>) -> String {
+    match value.as_deref().map(str::trim) {
+        Some(value) if !value.is_empty() => value.to_string(),
+        _ => UNKNOWN.to_string(),
+    }
+}
+
+/// Make a filled-in value safe as (part of) a file name on every platform
+fn sanitize_component(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    // Windows drops trailing dots and spaces
+    cleaned.trim_end_matches(&['.', ' '][..]).to_string()
+}
+
+/// Write `bytes` to `path` via a temporary file in the same directory, so a partly written
+/// file never appears under the final name
+pub fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid file name: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+
+    result.map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        format!("Failed to write {}: {}", path.display(), e)
+    })
+}
+
+/// Encode a capture and save it under `directory` using `template`, returning the final path.
+/// Missing directories are created; an existing file is never overwritten.
+pub fn save_with_template(
+    image: &RgbaImage,
+    captured_at: &DateTime<Local>,
+    source: &CaptureSource,
+    directory: &Path,
+    template: &SaveTemplate,
+    format: &OutputFormat,
+) -> Result<PathBuf, String> {
+    let bytes = encode_image(image, format)?;
+    let ext = format.extension();
+
+    for attempt in 0..MAX_COLLISIONS {
+        let counter = (template.has_counter() || attempt > 0).then_some(attempt + 1);
+        let path = directory.join(template.render(captured_at, source, ext, counter));
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+
+        // Reserve the name first so concurrent saves never pick the same file
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to create {}: {}", path.display(), e)),
+        }
+
+        if let Err(e) = write_atomically(&path, &bytes) {
+            let _ = std::fs::remove_file(&path);
+            return Err(e);
+        }
+
+        if cfg!(debug_assertions) {
+            println!("[AutoSave] Saved capture to {}", path.display());
+        }
+        return Ok(path);
+    }
+
+    Err(format!(
+        "No free file name for template after {} attempts",
+        MAX_COLLISIONS
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn captured_at() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 7).unwrap()
+    }
+
+    fn source() -> CaptureSource {
+        CaptureSource {
+            monitor_name: Some("DELL U2720Q".to_string()),
+            window_title: Some("notes.txt - Editor".to_string()),
+            app_name: Some("Code".to_string()),
+            scale_factor: None,
+        }
+    }
+
+    #[test]
+    fn fills_placeholders() {
+        let template =
+            SaveTemplate::parse("{date:%Y-%m}/{app_name}_{title}_{monitor}_{time}_{n:3}.{ext}")
+                .unwrap();
+
+        let path = template.render(&captured_at(), &source(), "png", Some(2));
+
+        assert_eq!(
+            path,
+            Path::new("2024-03").join("Code_notes.txt - Editor_DELL U2720Q_14-05-07_002.png")
+        );
+    }
+
+    #[test]
+    fn filled_values_cannot_escape_the_folder() {
+        let source = CaptureSource {
+            window_title: Some("../../etc/passwd".to_string()),
+            app_name: None,
+            ..source()
+        };
+        let template = SaveTemplate::parse("{app_name}/{title}.{ext}").unwrap();
+
+        let path = template.render(&captured_at(), &source, "png", None);
+
+        assert_eq!(path, Path::new("unknown").join(".._.._etc_passwd.png"));
+        assert!(SaveTemplate::parse("../{time}.{ext}").is_err());
+        assert!(SaveTemplate::parse("/tmp/{time}.{ext}").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(SaveTemplate::parse("{date").is_err());
+        assert!(SaveTemplate::parse("shot}.png").is_err());
+        assert!(SaveTemplate::parse("{user}.png").is_err());
+        assert!(SaveTemplate::parse("{date:%Q}.png").is_err());
+        assert!(SaveTemplate::parse("{ext:png}").is_err());
+        assert!(SaveTemplate::parse("{date}/").is_err());
+    }
+
+    #[test]
+    fn counts_up_on_collisions() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+        let template = SaveTemplate::parse("{date}/shot.{ext}").unwrap();
+        let format = OutputFormat::Png { level: None };
+
+        let image = RgbaImage::new(2, 2);
+        let save =
+            || save_with_template(&image, &captured_at(), &source(), &dir, &template, &format);
+
+        let first = save().unwrap();
+        let second = save().unwrap();
+
+        assert_eq!(first, dir.join("2024-03-09").join("shot.png"));
+        assert_eq!(second, dir.join("2024-03-09").join("shot_2.png"));
+        let saved = image::open(&second).unwrap();
+        assert_eq!((saved.width(), saved.height()), (2, 2));
+        // No temporary files are left behind
+        assert_eq!(
+            std::fs::read_dir(first.parent().unwrap()).unwrap().count(),
+            2
+        );
+    }
+}
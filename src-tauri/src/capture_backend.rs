@@ -0,0 +1,144 @@
+// JustSnap - Capture Backend Module
+// Abstracts monitor enumeration and pixel grabbing so capture logic can run without a display
+
+use crate::screen_capture::MonitorInfo;
+use image::{Rgba, RgbaImage};
+use std::sync::Arc;
+
+/// A source of monitors and their framebuffers
+pub trait CaptureBackend: Send + Sync {
+    /// Enumerate all connected monitors (coordinates in virtual desktop space)
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, String>;
+
+    /// Grab the full framebuffer of the monitor with the given id
+    fn capture_monitor(&self, monitor_id: u32) -> Result<RgbaImage, String>;
+}
+
+/// Managed state holding the active capture backend
+#[derive(Clone)]
+pub struct CaptureBackendState(pub Arc<dyn CaptureBackend>);
+
+impl CaptureBackendState {
+    /// Select the backend from `JUSTSNAP_CAPTURE_BACKEND` ("memory" for headless runs),
+    /// falling back to xcap
+    pub fn from_env() -> Self {
+        match std::env::var("JUSTSNAP_CAPTURE_BACKEND").as_deref() {
+            Ok("memory") => {
+                if cfg!(debug_assertions) {
+                    println!("[Capture] Using in-memory capture backend");
+                }
+                Self(Arc::new(MemoryBackend::single_display()))
+            }
+            _ => Self(Arc::new(XcapBackend)),
+        }
+    }
+
+    pub fn backend(&self) -> &dyn CaptureBackend {
+        self.0.as_ref()
+    }
+}
+
+// ============================================
+// xcap Backend (default)
+// ============================================
+
+/// Captures real displays through xcap
+pub struct XcapBackend;
+
+impl CaptureBackend for XcapBackend {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, String> {
+        let monitors =
+            xcap::Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+
+        Ok(monitors
+            .iter()
+            .map(|monitor| MonitorInfo {
+                id: monitor.id().unwrap_or(0),
+                name: monitor.name().unwrap_or_default(),
+                x: monitor.x().unwrap_or(0),
+                y: monitor.y().unwrap_or(0),
+                width: monitor.width().unwrap_or(0),
+                height: monitor.height().unwrap_or(0),
+                scale_factor: monitor.scale_factor().unwrap_or(1.0) as f64,
+                is_primary: monitor.is_primary().unwrap_or(false),
+            })
+            .collect())
+    }
+
+    fn capture_monitor(&self, monitor_id: u32) -> Result<RgbaImage, String> {
+        let monitors =
+            xcap::Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+
+        let monitor = monitors
+            .into_iter()
+            .find(|m| m.id().unwrap_or(0) == monitor_id)
+            .ok_or_else(|| format!("Monitor {} not found", monitor_id))?;
+
+        monitor
+            .capture_image()
+            .map_err(|e| format!("Failed to capture monitor: {}", e))
+    }
+}
+
+// ============================================
+// In-Memory Backend (headless / tests)
+// ============================================
+
+/// Serves synthetic framebuffers from memory
+pub struct MemoryBackend {
+    frames: Vec<(MonitorInfo, RgbaImage)>,
+}
+
+impl MemoryBackend {
+    /// Create a backend where every monitor is filled with a solid colour derived from its id
+    pub fn from_monitors(monitors: Vec<MonitorInfo>) -> Self {
+        let frames = monitors
+            .into_iter()
+            .map(|info| {
+                let image = RgbaImage::from_pixel(info.width, info.height, Self::fill_for(info.id));
+                (info, image)
+            })
+            .collect();
+
+        Self { frames }
+    }
+
+    /// A single 1920x1080 primary display
+    pub fn single_display() -> Self {
+        Self::from_monitors(vec![MonitorInfo {
+            id: 1,
+            name: "Virtual-1".to_string(),
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+            scale_factor: 1.0,
+            is_primary: true,
+        }])
+    }
+
+    /// Deterministic fill colour for a synthetic monitor
+    pub fn fill_for(monitor_id: u32) -> Rgba<u8> {
+        let seed = monitor_id.wrapping_mul(2654435761);
+        Rgba([
+            (seed >> 24) as u8,
+            (seed >> 16) as u8,
+            (seed >> 8) as u8,
+            255,
+        ])
+    }
+}
+
+impl CaptureBackend for MemoryBackend {
+    fn monitors(&self) -> Result<Vec<MonitorInfo>, String> {
+        Ok(self.frames.iter().map(|(info, _)| info.clone()).collect())
+    }
+
+    fn capture_monitor(&self, monitor_id: u32) -> Result<RgbaImage, String> {
+        self.frames
+            .iter()
+            .find(|(info, _)| info.id == monitor_id)
+            .map(|(_, image)| image.clone())
+            .ok_or_else(|| format!("Monitor {} not found", monitor_id))
+    }
+}
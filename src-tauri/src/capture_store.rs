@@ -0,0 +1,272 @@
+// JustSnap - Capture Store Module
+// Keeps recent captures in memory under generated ids, evicting the least recently used
+// ones once a memory cap is reached
+
+use crate::screen_capture::MonitorInfo;
+use image::RgbaImage;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Default memory budget for stored captures (enough for several 4K captures)
+pub const DEFAULT_MEMORY_LIMIT: usize = 512 * 1024 * 1024;
+
+/// URI scheme serving stored captures to the webview (`justsnap://localhost/<id>`)
+pub const PROTOCOL_SCHEME: &str = "justsnap";
+
+/// Where a capture was taken from (used e.g. to name auto-saved files)
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct CaptureSource {
+    pub monitor_name: Option<String>,
+    pub window_title: Option<String>,
+    pub app_name: Option<String>,
+    pub scale_factor: Option<f64>,
+}
+
+/// A capture held in memory
+pub struct StoredCapture {
+    pub image: RgbaImage,
+    pub captured_at: chrono::DateTime<chrono::Local>,
+    pub source: CaptureSource,
+}
+
+impl StoredCapture {
+    pub fn new(image: RgbaImage) -> Self {
+        Self {
+            image,
+            captured_at: chrono::Local::now(),
+            source: CaptureSource::default(),
+        }
+    }
+
+    /// A capture of a whole monitor
+    pub fn from_monitor(image: RgbaImage, monitor: &MonitorInfo) -> Self {
+        Self::new(image).with_source(CaptureSource {
+            monitor_name: Some(monitor.name.clone()),
+            scale_factor: Some(monitor.scale_factor),
+            ..CaptureSource::default()
+        })
+    }
+
+    pub fn with_source(mut self, source: CaptureSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    fn byte_size(&self) -> usize {
+        self.image.as_raw().len()
+    }
+}
+
+#[derive(Default)]
+struct StoreInner {
+    entries: HashMap<String, Arc<StoredCapture>>,
+    /// Capture ids from least to most recently used
+    lru: VecDeque<String>,
+    used_bytes: usize,
+    counter: u64,
+}
+
+impl StoreInner {
+    fn touch(&mut self, id: &str) {
+        if let Some(pos) = self.lru.iter().position(|entry| entry == id) {
+            if let Some(entry) = self.lru.remove(pos) {
+                self.lru.push_back(entry);
+            }
+        }
+    }
+
+    fn remove(&mut self, id: &str) -> bool {
+        match self.entries.remove(id) {
+            Some(capture) => {
+                self.used_bytes -= capture.byte_size();
+                self.lru.retain(|entry| entry != id);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Managed in-memory store of recent captures
+pub struct CaptureStore {
+    limit_bytes: usize,
+    inner: Mutex<StoreInner>,
+}
+
+impl Default for CaptureStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_MEMORY_LIMIT)
+    }
+}
+
+impl CaptureStore {
+    pub fn new(limit_bytes: usize) -> Self {
+        Self {
+            limit_bytes,
+            inner: Mutex::new(StoreInner::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, StoreInner> {
+        // A panic while holding the lock cannot leave the maps inconsistent, so recover
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Store a capture and return its generated id.
+    /// Older captures are evicted until the store fits its memory cap again
+    /// (the new capture itself is always kept).
+    pub fn insert(&self, capture: StoredCapture) -> String {
+        self.insert_shared(Arc::new(capture))
+    }
+
+    /// Store another handle to an existing capture for a second owner (e.g. a sticky pinned
+    /// from the editor) that releases it on its own. Each handle counts against the cap.
+    pub fn share(&self, id: &str) -> Result<String, String> {
+        let capture = self.get(id)?;
+        Ok(self.insert_shared(capture))
+    }
+
+    fn insert_shared(&self, capture: Arc<StoredCapture>) -> String {
+        let mut inner = self.lock();
+
+        inner.counter += 1;
+        let id = format!(
+            "cap-{}-{}",
+            chrono::Utc::now().timestamp_millis(),
+            inner.counter
+        );
+
+        inner.used_bytes += capture.byte_size();
+        inner.entries.insert(id.clone(), capture);
+        inner.lru.push_back(id.clone());
+
+        while inner.used_bytes > self.limit_bytes && inner.lru.len() > 1 {
+            if let Some(oldest) = inner.lru.front().cloned() {
+                if cfg!(debug_assertions) {
+                    println!("[CaptureStore] Evicting {}", oldest);
+                }
+                inner.remove(&oldest);
+            }
+        }
+
+        id
+    }
+
+    /// Look up a capture, marking it as recently used
+    pub fn get(&self, id: &str) -> Result<Arc<StoredCapture>, String> {
+        let mut inner = self.lock();
+
+        let capture = inner
+            .entries
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("Capture '{}' not found (it may have been evicted)", id))?;
+        inner.touch(id);
+
+        Ok(capture)
+    }
+
+    /// Drop a capture, returning whether it existed
+    pub fn remove(&self, id: &str) -> bool {
+        self.lock().remove(id)
+    }
+}
+
+/// URL under which the webview can load a stored capture
+pub fn capture_url(id: &str) -> String {
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://{}.localhost/{}", PROTOCOL_SCHEME, id)
+    } else {
+        format!("{}://localhost/{}", PROTOCOL_SCHEME, id)
+    }
+}
+
+/// Serve `justsnap://localhost/<id>` requests from the capture store
+pub fn protocol_response(
+    store: &CaptureStore,
+    request: &tauri::http::Request<Vec<u8>>,
+) -> tauri::http::Response<Vec<u8>> {
+    use crate::encoding::{encode_image, OutputFormat};
+    use tauri::http::{header, Response, StatusCode};
+
+    let id = request.uri().path().trim_start_matches('/');
+
+    let encoded = store
+        .get(id)
+        .and_then(|capture| encode_image(&capture.image, &OutputFormat::Bmp));
+
+    match encoded {
+        Ok(bytes) => Response::builder()
+            .header(header::CONTENT_TYPE, "image/bmp")
+            .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+            .body(bytes),
+        Err(e) => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(e.into_bytes()),
+    }
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(width: u32, height: u32) -> StoredCapture {
+        StoredCapture::new(RgbaImage::new(width, height))
+    }
+
+    #[test]
+    fn insert_generates_unique_ids() {
+        let store = CaptureStore::default();
+
+        let first = store.insert(capture(1, 1));
+        let second = store.insert(capture(1, 1));
+
+        assert_ne!(first, second);
+        assert!(store.get(&first).is_ok());
+        assert!(store.get(&second).is_ok());
+    }
+
+    #[test]
+    fn evicts_least_recently_used_over_limit() {
+        // Each 10x10 capture is 400 bytes; room for two
+        let store = CaptureStore::new(800);
+
+        let a = store.insert(capture(10, 10));
+        let b = store.insert(capture(10, 10));
+        store.get(&a).unwrap();
+        let c = store.insert(capture(10, 10));
+
+        assert!(store.get(&a).is_ok());
+        assert!(store.get(&b).is_err());
+        assert!(store.get(&c).is_ok());
+    }
+
+    #[test]
+    fn oversized_capture_is_still_kept() {
+        let store = CaptureStore::new(100);
+
+        let old = store.insert(capture(2, 2));
+        let big = store.insert(capture(10, 10));
+
+        assert!(store.get(&old).is_err());
+        assert!(store.get(&big).is_ok());
+        assert!(store.remove(&big));
+        assert!(!store.remove(&big));
+    }
+
+    #[test]
+    fn shared_handles_are_released_separately() {
+        let store = CaptureStore::default();
+
+        let original = store.insert(capture(2, 2));
+        let shared = store.share(&original).unwrap();
+        assert_ne!(original, shared);
+
+        assert!(store.remove(&original));
+        assert_eq!(store.get(&shared).unwrap().image.dimensions(), (2, 2));
+        assert!(store.share(&original).is_err());
+    }
+}
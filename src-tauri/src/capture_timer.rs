@@ -0,0 +1,183 @@
+// JustSnap - Capture Timer Module
+// Countdown before a delayed capture, so menus and tooltips can be opened first
+
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event emitted once per countdown step
+pub const COUNTDOWN_EVENT: &str = "capture-countdown";
+
+/// Payload of `capture-countdown`: `remaining` reaches 0 right before the capture runs
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct CountdownTick {
+    pub remaining: u32,
+    pub total: u32,
+    pub cancelled: bool,
+}
+
+#[derive(Default)]
+struct TimerState {
+    generation: u64,
+    /// Generation of the countdown currently running
+    active: Option<u64>,
+}
+
+/// Managed state tracking the running countdown (at most one at a time)
+pub struct CaptureTimer {
+    interval: Duration,
+    state: Mutex<TimerState>,
+    changed: Condvar,
+}
+
+impl Default for CaptureTimer {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1))
+    }
+}
+
+impl CaptureTimer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            state: Mutex::new(TimerState::default()),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TimerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Block for `seconds` steps, reporting each one through `tick`.
+    /// Starting a new countdown cancels the one already running.
+    /// Returns an error if the countdown was cancelled.
+    pub fn countdown(
+        &self,
+        seconds: u32,
+        mut tick: impl FnMut(CountdownTick),
+    ) -> Result<(), String> {
+        let id = {
+            let mut state = self.lock();
+            state.generation += 1;
+            state.active = Some(state.generation);
+            self.changed.notify_all();
+            state.generation
+        };
+
+        for remaining in (1..=seconds).rev() {
+            tick(CountdownTick {
+                remaining,
+                total: seconds,
+                cancelled: false,
+            });
+
+            let state = self.lock();
+            let (state, _) = self
+                .changed
+                .wait_timeout_while(state, self.interval, |state| state.active == Some(id))
+                .unwrap_or_else(|e| e.into_inner());
+
+            if state.active != Some(id) {
+                // Only report explicit cancels; a superseding countdown reports its own ticks
+                let superseded = state.active.is_some();
+                drop(state);
+                if !superseded {
+                    tick(CountdownTick {
+                        remaining,
+                        total: seconds,
+                        cancelled: true,
+                    });
+                }
+                return Err("Delayed capture cancelled".to_string());
+            }
+        }
+
+        let mut state = self.lock();
+        if state.active != Some(id) {
+            return Err("Delayed capture cancelled".to_string());
+        }
+        state.active = None;
+        drop(state);
+
+        tick(CountdownTick {
+            remaining: 0,
+            total: seconds,
+            cancelled: false,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel the running countdown, returning whether there was one
+    pub fn cancel(&self) -> bool {
+        let mut state = self.lock();
+        let was_running = state.active.take().is_some();
+        self.changed.notify_all();
+        was_running
+    }
+}
+
+/// Run the countdown on the managed timer, emitting `capture-countdown` events to the frontend
+pub fn run_countdown(app: &AppHandle, seconds: u32) -> Result<(), String> {
+    if cfg!(debug_assertions) {
+        println!("[Timer] Delayed capture in {}s", seconds);
+    }
+
+    let timer = app.state::<CaptureTimer>();
+    timer.countdown(seconds, |tick| {
+        if let Err(e) = app.emit(COUNTDOWN_EVENT, tick) {
+            eprintln!("[Error] Failed to emit countdown event: {}", e);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn countdown_ticks_down_to_zero() {
+        let timer = CaptureTimer::new(Duration::from_millis(1));
+        let mut ticks = Vec::new();
+
+        timer
+            .countdown(3, |tick| ticks.push(tick.remaining))
+            .unwrap();
+
+        assert_eq!(ticks, vec![3, 2, 1, 0]);
+        assert!(!timer.cancel());
+    }
+
+    #[test]
+    fn cancel_stops_running_countdown() {
+        let timer = Arc::new(CaptureTimer::new(Duration::from_secs(30)));
+
+        let worker = {
+            let timer = Arc::clone(&timer);
+            std::thread::spawn(move || {
+                let mut last = None;
+                let result = timer.countdown(5, |tick| last = Some(tick));
+                (result, last)
+            })
+        };
+
+        // Wait for the countdown to start before cancelling it
+        while timer.lock().active.is_none() {
+            std::thread::yield_now();
+        }
+        assert!(timer.cancel());
+
+        let (result, last) = worker.join().unwrap();
+        assert!(result.is_err());
+        assert_eq!(
+            last,
+            Some(CountdownTick {
+                remaining: 5,
+                total: 5,
+                cancelled: true
+            })
+        );
+    }
+}
@@ -0,0 +1,528 @@
+// JustSnap - Command Line Module
+// Headless captures for scripts (`justsnap capture ...`, `justsnap monitors`) without any window
+
+use crate::capture_backend::{CaptureBackend, CaptureBackendState};
+use crate::capture_store::CaptureSource;
+use crate::export::{export_images, ExportFormat, ExportMetadata};
+use crate::screen_capture::{CaptureRegion, MonitorInfo};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage:
+  justsnap capture [--region x,y,w,h] [--monitor N] [--format FORMAT] --out FILE
+  justsnap capture --window TITLE-REGEX [--format FORMAT] --out FILE
+  justsnap monitors [--json]
+  justsnap help
+
+capture
+  --region x,y,w,h   Region in pixels, relative to --monitor when given (else the virtual desktop)
+  --monitor N        Monitor index from `justsnap monitors` (the whole monitor without --region)
+  --window REGEX     Topmost visible window whose title matches the regular expression
+  --format FORMAT    png, jpg, webp, tiff, bmp, qoi, raw or pdf
+                     (default: from the --out extension, else png)
+  --out FILE         Where to write the image, `-` for standard output
+  Without --region, --monitor or --window the primary monitor is captured.
+
+Exit codes
+  0  success
+  1  the capture or writing the file failed
+  2  invalid arguments
+  3  no monitor or window matched";
+
+// ============================================
+// Arguments
+// ============================================
+
+/// A command-line invocation
+#[derive(Debug, PartialEq)]
+pub enum CliCommand {
+    Capture(CaptureArgs),
+    Monitors { json: bool },
+    Help,
+}
+
+/// What `justsnap capture` captures and where it writes it
+#[derive(Debug, Default, PartialEq)]
+pub struct CaptureArgs {
+    pub region: Option<CaptureRegion>,
+    pub monitor: Option<usize>,
+    pub window: Option<String>,
+    pub format: Option<ExportFormat>,
+    /// None for standard output
+    pub out: Option<PathBuf>,
+}
+
+/// Why a command failed, mapped to the process exit code
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    /// Invalid arguments (2)
+    Usage(String),
+    /// No monitor or window matched (3)
+    NotFound(String),
+    /// Capturing, encoding or writing failed (1)
+    Failed(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Failed(_) => 1,
+            Self::Usage(_) => 2,
+            Self::NotFound(_) => 3,
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            Self::Usage(message) | Self::NotFound(message) | Self::Failed(message) => message,
+        }
+    }
+}
+
+/// Parse the process arguments (program name first).
+/// Returns None when they are meant for the app itself (no arguments, `--silence`, image files).
+pub fn parse(args: &[String]) -> Option<Result<CliCommand, CliError>> {
+    let (command, options) = args.get(1..)?.split_first()?;
+
+    match command.as_str() {
+        "capture" => Some(parse_capture(options).map(CliCommand::Capture)),
+        "monitors" => Some(match options {
+            [] => Ok(CliCommand::Monitors { json: false }),
+            [flag] if flag == "--json" => Ok(CliCommand::Monitors { json: true }),
+            [other, ..] => Err(CliError::Usage(format!("Unknown option '{}'", other))),
+        }),
+        "help" | "--help" | "-h" => Some(Ok(CliCommand::Help)),
+        _ => None,
+    }
+}
+
+fn parse_capture(options: &[String]) -> Result<CaptureArgs, CliError> {
+    let mut args = CaptureArgs::default();
+    let mut out = None;
+    let mut options = options.iter();
+
+    while let Some(option) = options.next() {
+        let mut value = || {
+            options
+                .next()
+                .ok_or_else(|| CliError::Usage(format!("{} needs a value", option)))
+        };
+
+        match option.as_str() {
+            "--region" => args.region = Some(parse_region(value()?)?),
+            "--monitor" => {
+                let index = value()?;
+                let index = index
+                    .parse()
+                    .map_err(|_| CliError::Usage(format!("Invalid monitor index '{}'", index)))?;
+                args.monitor = Some(index);
+            }
+            "--window" => args.window = Some(value()?.clone()),
+            "--format" => {
+                let format = ExportFormat::from_extension(value()?).map_err(CliError::Usage)?;
+                args.format = Some(format);
+            }
+            "--out" => out = Some(value()?.clone()),
+            other => return Err(CliError::Usage(format!("Unknown option '{}'", other))),
+        }
+    }
+
+    if args.window.is_some() && (args.region.is_some() || args.monitor.is_some()) {
+        return Err(CliError::Usage(
+            "--window cannot be combined with --region or --monitor".to_string(),
+        ));
+    }
+
+    args.out = match out.as_deref() {
+        Some("-") => None,
+        Some(path) => Some(PathBuf::from(path)),
+        None => return Err(CliError::Usage("--out is required".to_string())),
+    };
+    Ok(args)
+}
+
+/// Parse `x,y,w,h`
+fn parse_region(value: &str) -> Result<CaptureRegion, CliError> {
+    let invalid = || CliError::Usage(format!("Invalid region '{}' (expected x,y,w,h)", value));
+
+    let numbers = value
+        .split(',')
+        .map(|part| part.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+
+    match numbers[..] {
+        [x, y, width, height] if width > 0 && height > 0 => Ok(CaptureRegion {
+            x,
+            y,
+            width,
+            height,
+        }),
+        _ => Err(invalid()),
+    }
+}
+
+// ============================================
+// Running Commands
+// ============================================
+
+/// Run a command-line invocation without starting the app.
+/// Returns None when the arguments are for the app itself.
+pub fn run(args: &[String]) -> Option<ExitCode> {
+    let command = parse(args)?;
+
+    // Release builds on Windows have no console of their own; write to the one we were started from
+    #[cfg(windows)]
+    unsafe {
+        use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+
+    let backend = CaptureBackendState::from_env();
+    let result = command.and_then(|command| {
+        execute(
+            &command,
+            backend.backend(),
+            &xcap_windows,
+            &mut std::io::stdout().lock(),
+        )
+    });
+
+    Some(match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("justsnap: {}", e.message());
+            if let CliError::Usage(_) = e {
+                eprintln!("\n{}", USAGE);
+            }
+            ExitCode::from(e.exit_code())
+        }
+    })
+}
+
+/// A top-level window that `--window` can match
+pub struct CliWindow {
+    pub title: String,
+    pub app_name: String,
+    pub region: CaptureRegion,
+}
+
+/// Visible windows from front to back
+fn xcap_windows() -> Result<Vec<CliWindow>, String> {
+    use crate::window_list::{list_windows, WindowFilter};
+
+    Ok(list_windows(&WindowFilter::default())?
+        .into_iter()
+        .filter(|window| window.is_visible)
+        .map(|window| CliWindow {
+            region: CaptureRegion {
+                x: window.x,
+                y: window.y,
+                width: window.width as i32,
+                height: window.height as i32,
+            },
+            title: window.title,
+            app_name: window.app_name,
+        })
+        .collect())
+}
+
+fn execute(
+    command: &CliCommand,
+    backend: &dyn CaptureBackend,
+    windows: &dyn Fn() -> Result<Vec<CliWindow>, String>,
+    stdout: &mut dyn Write,
+) -> Result<(), CliError> {
+    match command {
+        CliCommand::Help => {
+            writeln!(stdout, "{}", USAGE).map_err(|e| CliError::Failed(e.to_string()))
+        }
+        CliCommand::Monitors { json } => {
+            let monitors = backend.monitors().map_err(CliError::Failed)?;
+            print_monitors(&monitors, *json, stdout).map_err(|e| CliError::Failed(e.to_string()))
+        }
+        CliCommand::Capture(args) => capture(args, backend, windows, stdout),
+    }
+}
+
+/// Monitor with its index, as printed by `justsnap monitors --json`
+#[derive(serde::Serialize)]
+struct IndexedMonitor<'a> {
+    index: usize,
+    #[serde(flatten)]
+    monitor: &'a MonitorInfo,
+}
+
+fn print_monitors(
+    monitors: &[MonitorInfo],
+    json: bool,
+    stdout: &mut dyn Write,
+) -> std::io::Result<()> {
+    if json {
+        let monitors = monitors
+            .iter()
+            .enumerate()
+            .map(|(index, monitor)| IndexedMonitor { index, monitor })
+            .collect::<Vec<_>>();
+        serde_json::to_writer_pretty(&mut *stdout, &monitors)?;
+        return writeln!(stdout);
+    }
+
+    for (index, monitor) in monitors.iter().enumerate() {
+        writeln!(
+            stdout,
+            "{}  {}  {}x{} at ({}, {})  scale {}{}",
+            index,
+            monitor.name,
+            monitor.width,
+            monitor.height,
+            monitor.x,
+            monitor.y,
+            monitor.scale_factor,
+            if monitor.is_primary { "  primary" } else { "" }
+        )?;
+    }
+    Ok(())
+}
+
+fn capture(
+    args: &CaptureArgs,
+    backend: &dyn CaptureBackend,
+    windows: &dyn Fn() -> Result<Vec<CliWindow>, String>,
+    stdout: &mut dyn Write,
+) -> Result<(), CliError> {
+    let monitors = backend.monitors().map_err(CliError::Failed)?;
+    let mut source = CaptureSource::default();
+
+    let region = match (&args.window, args.monitor) {
+        (Some(pattern), _) => {
+            let window = find_window(pattern, windows().map_err(CliError::Failed)?)?;
+            source.window_title = Some(window.title);
+            source.app_name = Some(window.app_name);
+            window.region
+        }
+        (None, Some(index)) => {
+            let monitor = monitors.get(index).ok_or_else(|| {
+                CliError::NotFound(format!(
+                    "No monitor {} ({} connected)",
+                    index,
+                    monitors.len()
+                ))
+            })?;
+            match args.region {
+                Some(region) => CaptureRegion {
+                    x: monitor.x + region.x,
+                    y: monitor.y + region.y,
+                    ..region
+                },
+                None => monitor_region(monitor),
+            }
+        }
+        (None, None) => match args.region {
+            Some(region) => region,
+            None => monitors
+                .iter()
+                .find(|monitor| monitor.is_primary)
+                .or(monitors.first())
+                .map(monitor_region)
+                .ok_or_else(|| CliError::NotFound("No monitors found".to_string()))?,
+        },
+    };
+
+    let monitor = crate::screen_capture::monitor_at_point(
+        &monitors,
+        region.x + region.width / 2,
+        region.y + region.height / 2,
+    );
+    source.monitor_name = monitor.map(|m| m.name.clone());
+    source.scale_factor = monitor.map(|m| m.scale_factor);
+
+    let image = crate::screen_capture::capture_region_raw(backend, region, &Default::default())
+        .map_err(|e| match monitor {
+            None => CliError::NotFound(e),
+            Some(_) => CliError::Failed(e),
+        })?;
+
+    let format = match (args.format, &args.out) {
+        (Some(format), _) => format,
+        (None, Some(path)) => ExportFormat::from_path(path).map_err(CliError::Usage)?,
+        (None, None) => ExportFormat::Png,
+    };
+    let metadata = ExportMetadata::from_capture(&chrono::Local::now(), &source);
+    let bytes = export_images(&[&image], format, Some(&metadata)).map_err(CliError::Failed)?;
+
+    match args.out {
+        Some(ref path) => {
+            crate::auto_save::write_atomically(path, &bytes).map_err(CliError::Failed)
+        }
+        None => stdout
+            .write_all(&bytes)
+            .and_then(|_| stdout.flush())
+            .map_err(|e| CliError::Failed(e.to_string())),
+    }
+}
+
+fn monitor_region(monitor: &MonitorInfo) -> CaptureRegion {
+    CaptureRegion {
+        x: monitor.x,
+        y: monitor.y,
+        width: monitor.width as i32,
+        height: monitor.height as i32,
+    }
+}
+
+/// The frontmost window whose title matches `pattern`
+fn find_window(pattern: &str, windows: Vec<CliWindow>) -> Result<CliWindow, CliError> {
+    let regex = regex::Regex::new(pattern)
+        .map_err(|e| CliError::Usage(format!("Invalid window pattern: {}", e)))?;
+
+    windows
+        .into_iter()
+        .find(|window| regex.is_match(&window.title))
+        .ok_or_else(|| CliError::NotFound(format!("No window title matches '{}'", pattern)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_backend::MemoryBackend;
+
+    fn args(line: &str) -> Vec<String> {
+        std::iter::once("justsnap")
+            .chain(line.split_whitespace())
+            .map(String::from)
+            .collect()
+    }
+
+    fn no_windows() -> Result<Vec<CliWindow>, String> {
+        Ok(Vec::new())
+    }
+
+    fn two_monitors() -> MemoryBackend {
+        MemoryBackend::from_monitors(
+            [(1, 0, true), (2, 1920, false)]
+                .into_iter()
+                .map(|(id, x, is_primary)| MonitorInfo {
+                    id,
+                    name: format!("Display-{}", id),
+                    x,
+                    y: 0,
+                    width: 1920,
+                    height: 1080,
+                    scale_factor: 1.0,
+                    is_primary,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn parses_commands_and_leaves_app_arguments_alone() {
+        assert_eq!(parse(&args("")), None);
+        assert_eq!(parse(&args("--silence")), None);
+        assert_eq!(parse(&args("screenshot.png")), None);
+
+        let Some(Ok(CliCommand::Capture(capture))) = parse(&args(
+            "capture --region 10,20,300,200 --monitor 1 --format JPG --out -",
+        )) else {
+            panic!("capture was not parsed");
+        };
+        assert_eq!(
+            capture,
+            CaptureArgs {
+                region: Some(CaptureRegion {
+                    x: 10,
+                    y: 20,
+                    width: 300,
+                    height: 200
+                }),
+                monitor: Some(1),
+                format: Some(ExportFormat::Jpeg),
+                ..CaptureArgs::default()
+            }
+        );
+
+        for invalid in [
+            "capture --region 1,2,3 --out a.png",
+            "capture --region 0,0,0,10 --out a.png",
+            "capture --monitor --out a.png",
+            "capture --format gif --out a.gif",
+            "capture --window Editor --monitor 0 --out a.png",
+            "capture --region 0,0,10,10",
+            "monitors --yaml",
+        ] {
+            let error = parse(&args(invalid)).unwrap().unwrap_err();
+            assert_eq!(error.exit_code(), 2, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn captures_a_region_of_a_monitor() {
+        let backend = two_monitors();
+        let mut stdout = Vec::new();
+
+        let command = parse(&args("capture --monitor 1 --region 5,5,40,30 --out -"))
+            .unwrap()
+            .unwrap();
+        execute(&command, &backend, &no_windows, &mut stdout).unwrap();
+
+        let image = image::load_from_memory(&stdout).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (40, 30));
+        assert_eq!(image.get_pixel(0, 0), &MemoryBackend::fill_for(2));
+
+        let missing = parse(&args("capture --monitor 2 --out -"))
+            .unwrap()
+            .unwrap();
+        let error = execute(&missing, &backend, &no_windows, &mut Vec::new()).unwrap_err();
+        assert_eq!(error.exit_code(), 3);
+    }
+
+    #[test]
+    fn captures_the_frontmost_matching_window() {
+        let backend = two_monitors();
+        let windows = || {
+            Ok(["Terminal", "notes.txt - Editor", "main.rs - Editor"]
+                .into_iter()
+                .enumerate()
+                .map(|(i, title)| CliWindow {
+                    title: title.to_string(),
+                    app_name: "app".to_string(),
+                    region: CaptureRegion {
+                        x: 1900 + i as i32,
+                        y: 10,
+                        width: 100 + i as i32,
+                        height: 50,
+                    },
+                })
+                .collect())
+        };
+        let mut stdout = Vec::new();
+
+        let command = parse(&args("capture --window Editor$ --out -"))
+            .unwrap()
+            .unwrap();
+        execute(&command, &backend, &windows, &mut stdout).unwrap();
+        let image = image::load_from_memory(&stdout).unwrap();
+        assert_eq!((image.width(), image.height()), (101, 50));
+
+        let missing = parse(&args("capture --window ^Browser --out -"))
+            .unwrap()
+            .unwrap();
+        let error = execute(&missing, &backend, &windows, &mut Vec::new()).unwrap_err();
+        assert_eq!(error.exit_code(), 3);
+    }
+
+    #[test]
+    fn lists_monitors_as_json() {
+        let mut stdout = Vec::new();
+        let command = parse(&args("monitors --json")).unwrap().unwrap();
+        execute(&command, &two_monitors(), &no_windows, &mut stdout).unwrap();
+
+        let monitors: serde_json::Value = serde_json::from_slice(&stdout).unwrap();
+        assert_eq!(monitors[1]["index"], 1);
+        assert_eq!(monitors[1]["name"], "Display-2");
+        assert_eq!(monitors[1]["x"], 1920);
+    }
+}
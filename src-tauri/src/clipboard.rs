@@ -0,0 +1,455 @@
+// JustSnap - Clipboard Module
+// Puts captures on the system clipboard (optionally with text, HTML and file flavours)
+// and reads images back from it
+
+use image::RgbaImage;
+use std::path::PathBuf;
+
+/// Put an RGBA image on the clipboard
+pub fn set_image(img: &RgbaImage) -> Result<(), String> {
+    use arboard::{Clipboard, ImageData};
+
+    // Create clipboard image data
+    let img_data = ImageData {
+        width: img.width() as usize,
+        height: img.height() as usize,
+        bytes: img.as_raw().as_slice().into(),
+    };
+
+    // Copy to clipboard
+    let mut clipboard =
+        Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
+    clipboard
+        .set_image(img_data)
+        .map_err(|e| format!("Failed to copy image to clipboard: {}", e))?;
+
+    Ok(())
+}
+
+// ============================================
+// Reading
+// ============================================
+
+/// An image read from the clipboard, with the file it came from when a path was copied
+pub struct PastedImage {
+    pub image: RgbaImage,
+    pub path: Option<PathBuf>,
+}
+
+/// Read an image from the clipboard: image data first, then a copied image file
+/// (file list, or a path / `file://` URI copied as text)
+pub fn get_image() -> Result<PastedImage, String> {
+    use arboard::Clipboard;
+
+    let mut clipboard =
+        Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
+
+    if let Ok(data) = clipboard.get_image() {
+        let width = data.width as u32;
+        let height = data.height as u32;
+        let image = RgbaImage::from_raw(width, height, data.bytes.into_owned())
+            .ok_or_else(|| "Clipboard image has an unexpected size".to_string())?;
+        return Ok(PastedImage { image, path: None });
+    }
+
+    let mut candidates = clipboard.get().file_list().unwrap_or_default();
+    if let Ok(text) = clipboard.get_text() {
+        candidates.extend(paths_from_text(&text));
+    }
+
+    candidates
+        .into_iter()
+        .filter(|path| path.is_file())
+        .find_map(|path| {
+            let image = image::open(&path).ok()?.to_rgba8();
+            Some(PastedImage {
+                image,
+                path: Some(path),
+            })
+        })
+        .ok_or_else(|| "The clipboard does not contain an image".to_string())
+}
+
+/// File paths in copied text: one per line, optionally quoted or as `file://` URIs
+fn paths_from_text(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(|line| line.trim().trim_matches('"').trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.strip_prefix("file://") {
+            // file:///home/me/a%20b.png, or file://localhost/...
+            Some(uri) => {
+                let path = uri.strip_prefix("localhost").unwrap_or(uri);
+                let decoded = percent_encoding::percent_decode_str(path).decode_utf8_lossy();
+                // file:///C:/Users/... -> C:/Users/...
+                match decoded.strip_prefix('/') {
+                    Some(rest) if cfg!(windows) && rest.get(1..2) == Some(":") => {
+                        PathBuf::from(rest)
+                    }
+                    _ => PathBuf::from(decoded.as_ref()),
+                }
+            }
+            None => PathBuf::from(line),
+        })
+        .collect()
+}
+
+// ============================================
+// Multiple Flavours
+// ============================================
+
+/// A representation placed on the clipboard
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardFlavour {
+    Image,
+    Text,
+    Html,
+    FileList,
+}
+
+/// Everything to put on the clipboard in one go
+#[derive(Default)]
+pub struct ClipboardContent<'a> {
+    pub image: Option<&'a RgbaImage>,
+    pub text: Option<String>,
+    /// Alt text of the HTML `<img>` (e.g. OCR text); HTML is only offered with an image
+    pub html_alt: Option<String>,
+    pub files: Vec<PathBuf>,
+}
+
+/// HTML fragment embedding a PNG as a data URI
+pub fn image_html(png: &[u8], alt: &str) -> String {
+    use base64::Engine;
+
+    format!(
+        "<img src=\"data:image/png;base64,{}\" alt=\"{}\">",
+        base64::engine::general_purpose::STANDARD.encode(png),
+        escape_html(alt)
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Put several flavours on the clipboard so paste targets can pick the richest one they understand.
+/// Returns the flavours that were set.
+///
+/// Windows gets every flavour at once (bitmap + PNG, Unicode text, HTML Format, file drop list).
+/// Elsewhere the clipboard library replaces the contents on every write, so only the richest
+/// single offer is made: the image, else HTML with its plain-text alternative, else text, else files.
+/// Callers that need the text next to an image must check the returned flavours (the editor
+/// then offers image, text and HTML together through the webview clipboard, which cannot carry
+/// the file list).
+pub fn set_contents(content: &ClipboardContent) -> Result<Vec<ClipboardFlavour>, String> {
+    let files: Vec<PathBuf> = content
+        .files
+        .iter()
+        .filter(|path| path.exists())
+        .cloned()
+        .collect();
+
+    let html = match (content.image, &content.html_alt) {
+        (Some(image), Some(alt)) => {
+            let png = crate::encoding::encode_image(
+                image,
+                &crate::encoding::OutputFormat::Png { level: None },
+            )?;
+            Some(image_html(&png, alt))
+        }
+        _ => None,
+    };
+
+    let flavours = set_platform_contents(content, html.as_deref(), &files)?;
+
+    if cfg!(debug_assertions) {
+        println!("[Clipboard] Set {:?}", flavours);
+    }
+    Ok(flavours)
+}
+
+#[cfg(windows)]
+fn set_platform_contents(
+    content: &ClipboardContent,
+    html: Option<&str>,
+    files: &[PathBuf],
+) -> Result<Vec<ClipboardFlavour>, String> {
+    use windows::core::w;
+    use windows::Win32::System::DataExchange::RegisterClipboardFormatW;
+
+    let mut formats: Vec<(u32, Vec<u8>)> = Vec::new();
+    let mut flavours = Vec::new();
+
+    if let Some(image) = content.image {
+        formats.push((win32::CF_DIB, win32::dib(image)));
+        // PNG keeps transparency for apps that read it (Office, browsers, GIMP)
+        let png = crate::encoding::encode_image(
+            image,
+            &crate::encoding::OutputFormat::Png { level: None },
+        )?;
+        formats.push((unsafe { RegisterClipboardFormatW(w!("PNG")) }, png));
+        flavours.push(ClipboardFlavour::Image);
+    }
+    if let Some(ref text) = content.text {
+        formats.push((win32::CF_UNICODETEXT, win32::unicode_text(text)));
+        flavours.push(ClipboardFlavour::Text);
+    }
+    if let Some(html) = html {
+        let format = unsafe { RegisterClipboardFormatW(w!("HTML Format")) };
+        formats.push((format, win32::cf_html(html)));
+        flavours.push(ClipboardFlavour::Html);
+    }
+    if !files.is_empty() {
+        formats.push((win32::CF_HDROP, win32::drop_files(files)));
+        flavours.push(ClipboardFlavour::FileList);
+    }
+
+    win32::set_clipboard_formats(&formats)?;
+    Ok(flavours)
+}
+
+#[cfg(not(windows))]
+fn set_platform_contents(
+    content: &ClipboardContent,
+    html: Option<&str>,
+    files: &[PathBuf],
+) -> Result<Vec<ClipboardFlavour>, String> {
+    use arboard::Clipboard;
+
+    if let Some(image) = content.image {
+        set_image(image)?;
+        if cfg!(debug_assertions) && (content.text.is_some() || !files.is_empty()) {
+            println!("[Clipboard] Only the image was set, the text and files were left out");
+        }
+        return Ok(vec![ClipboardFlavour::Image]);
+    }
+
+    let mut clipboard =
+        Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
+    let set = match (html, &content.text) {
+        (Some(html), text) => clipboard
+            .set_html(html, text.as_deref())
+            .map(|_| vec![ClipboardFlavour::Html, ClipboardFlavour::Text]),
+        (None, Some(text)) => clipboard
+            .set_text(text.as_str())
+            .map(|_| vec![ClipboardFlavour::Text]),
+        (None, None) if !files.is_empty() => clipboard
+            .set()
+            .file_list(files)
+            .map(|_| vec![ClipboardFlavour::FileList]),
+        (None, None) => Ok(Vec::new()),
+    };
+
+    set.map_err(|e| format!("Failed to copy to clipboard: {}", e))
+}
+
+/// Clipboard data layouts used by Windows
+#[cfg(any(windows, test))]
+mod win32 {
+    use image::RgbaImage;
+    use std::path::PathBuf;
+
+    #[cfg(windows)]
+    pub const CF_DIB: u32 = 8;
+    #[cfg(windows)]
+    pub const CF_UNICODETEXT: u32 = 13;
+    #[cfg(windows)]
+    pub const CF_HDROP: u32 = 15;
+
+    /// BITMAPINFOHEADER + bottom-up 32-bit BGRA rows
+    pub fn dib(image: &RgbaImage) -> Vec<u8> {
+        let (width, height) = image.dimensions();
+        let pixel_bytes = width as usize * height as usize * 4;
+
+        let mut data = Vec::with_capacity(40 + pixel_bytes);
+        data.extend_from_slice(&40u32.to_le_bytes()); // biSize
+        data.extend_from_slice(&(width as i32).to_le_bytes());
+        data.extend_from_slice(&(height as i32).to_le_bytes()); // positive = bottom-up
+        data.extend_from_slice(&1u16.to_le_bytes()); // biPlanes
+        data.extend_from_slice(&32u16.to_le_bytes()); // biBitCount
+        data.extend_from_slice(&0u32.to_le_bytes()); // BI_RGB
+        data.extend_from_slice(&(pixel_bytes as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 16]); // resolution and palette
+
+        for row in image.rows().rev() {
+            for pixel in row {
+                let [r, g, b, a] = pixel.0;
+                data.extend_from_slice(&[b, g, r, a]);
+            }
+        }
+        data
+    }
+
+    /// NUL-terminated UTF-16
+    pub fn unicode_text(text: &str) -> Vec<u8> {
+        text.encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    /// "HTML Format": a header with byte offsets of the document and fragment
+    pub fn cf_html(fragment: &str) -> Vec<u8> {
+        const PREFIX: &str = "<html><body>\r\n<!--StartFragment-->";
+        const SUFFIX: &str = "<!--EndFragment-->\r\n</body></html>";
+
+        let header =
+            |start_html: usize, end_html: usize, start_fragment: usize, end_fragment: usize| {
+                format!(
+                    "Version:0.9\r\nStartHTML:{:010}\r\nEndHTML:{:010}\r\n\
+                 StartFragment:{:010}\r\nEndFragment:{:010}\r\n",
+                    start_html, end_html, start_fragment, end_fragment
+                )
+            };
+
+        // Offsets are zero-padded, so the header length does not depend on them
+        let start_html = header(0, 0, 0, 0).len();
+        let start_fragment = start_html + PREFIX.len();
+        let end_fragment = start_fragment + fragment.len();
+        let end_html = end_fragment + SUFFIX.len();
+
+        let mut data = header(start_html, end_html, start_fragment, end_fragment).into_bytes();
+        data.extend_from_slice(PREFIX.as_bytes());
+        data.extend_from_slice(fragment.as_bytes());
+        data.extend_from_slice(SUFFIX.as_bytes());
+        data.push(0);
+        data
+    }
+
+    /// DROPFILES header followed by NUL-separated UTF-16 paths and a final NUL
+    pub fn drop_files(files: &[PathBuf]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&20u32.to_le_bytes()); // pFiles: offset of the path list
+        data.extend_from_slice(&[0; 8]); // pt
+        data.extend_from_slice(&0u32.to_le_bytes()); // fNC
+        data.extend_from_slice(&1u32.to_le_bytes()); // fWide
+
+        for file in files {
+            data.extend(unicode_text(&file.to_string_lossy()));
+        }
+        data.extend_from_slice(&[0, 0]);
+        data
+    }
+
+    /// Replace the clipboard contents with all formats in one clipboard session
+    #[cfg(windows)]
+    pub fn set_clipboard_formats(formats: &[(u32, Vec<u8>)]) -> Result<(), String> {
+        use windows::Win32::Foundation::{HANDLE, HWND};
+        use windows::Win32::System::DataExchange::{
+            CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+        };
+        use windows::Win32::System::Memory::{
+            GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE,
+        };
+
+        unsafe {
+            // Another application may be holding the clipboard for a moment
+            let mut attempts = 0;
+            while OpenClipboard(HWND::default()).is_err() {
+                attempts += 1;
+                if attempts == 10 {
+                    return Err("Failed to access clipboard".to_string());
+                }
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+
+            let result = (|| {
+                EmptyClipboard().map_err(|e| format!("Failed to clear clipboard: {}", e))?;
+
+                for (format, data) in formats {
+                    let memory = GlobalAlloc(GMEM_MOVEABLE, data.len())
+                        .map_err(|e| format!("Failed to copy to clipboard: {}", e))?;
+                    let pointer = GlobalLock(memory) as *mut u8;
+                    if pointer.is_null() {
+                        let _ = GlobalFree(memory);
+                        return Err("Failed to copy to clipboard".to_string());
+                    }
+                    std::ptr::copy_nonoverlapping(data.as_ptr(), pointer, data.len());
+                    let _ = GlobalUnlock(memory);
+
+                    // The clipboard owns the memory once it is set
+                    if let Err(e) = SetClipboardData(*format, HANDLE(memory.0)) {
+                        let _ = GlobalFree(memory);
+                        return Err(format!("Failed to copy to clipboard: {}", e));
+                    }
+                }
+                Ok(())
+            })();
+
+            let _ = CloseClipboard();
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_paths_in_copied_text() {
+        let text =
+            "\"/home/me/shot one.png\"\r\nfile:///tmp/a%20b.png\n\nfile://localhost/srv/c.jpg";
+
+        assert_eq!(
+            paths_from_text(text),
+            [
+                PathBuf::from("/home/me/shot one.png"),
+                PathBuf::from("/tmp/a b.png"),
+                PathBuf::from("/srv/c.jpg"),
+            ]
+        );
+    }
+
+    #[test]
+    fn html_embeds_png_with_escaped_alt_text() {
+        let html = image_html(&[0x89, b'P', b'N', b'G'], "a < b & \"c\"\nd");
+
+        assert_eq!(
+            html,
+            "<img src=\"data:image/png;base64,iVBORw==\" alt=\"a &lt; b &amp; &quot;c&quot;&#10;d\">"
+        );
+    }
+
+    #[test]
+    fn cf_html_offsets_point_at_fragment() {
+        let fragment = "<img alt=\"ü\">";
+        let data = win32::cf_html(fragment);
+        let text = std::str::from_utf8(&data[..data.len() - 1]).unwrap();
+
+        let offset = |name: &str| -> usize {
+            let start = text.find(name).unwrap() + name.len() + 1;
+            text[start..start + 10].parse().unwrap()
+        };
+        assert_eq!(
+            &text[offset("StartFragment")..offset("EndFragment")],
+            fragment
+        );
+        assert!(text[offset("StartHTML")..].starts_with("<html>"));
+        assert_eq!(offset("EndHTML"), text.len());
+    }
+
+    #[test]
+    fn windows_layouts() {
+        let image = RgbaImage::from_fn(2, 2, |x, y| image::Rgba([x as u8, y as u8, 9, 255]));
+        let dib = win32::dib(&image);
+        assert_eq!(dib.len(), 40 + 16);
+        // Bottom row first, BGRA
+        assert_eq!(&dib[40..44], &[9, 1, 0, 255]);
+
+        let files = win32::drop_files(&[PathBuf::from("a"), PathBuf::from("b")]);
+        assert_eq!(&files[16..20], &1u32.to_le_bytes());
+        assert_eq!(&files[20..], &[b'a', 0, 0, 0, b'b', 0, 0, 0, 0, 0]);
+    }
+}
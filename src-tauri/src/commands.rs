@@ -1,16 +1,79 @@
 // JustSnap - Tauri Commands
 // All IPC commands that can be invoked from the frontend
 
+use crate::capture_backend::CaptureBackendState;
+use crate::capture_store::{CaptureSource, CaptureStore, StoredCapture};
+use crate::clipboard::ClipboardFlavour;
+use crate::encoding::OutputFormat;
+use crate::history::{
+    record_capture, CaptureHistory, HistoryDetails, HistoryEntry, HistoryQuery, HistoryUpdate,
+    HISTORY_CHANGED_EVENT,
+};
+use crate::hotkey_validation::{HotkeyError, NormalizedHotkey};
+use crate::hotkeys::HotkeyAction;
+use crate::image_input::{OpenTarget, OpenedImage, StartupFiles, StickyWindow};
+use crate::recent_regions::{RecentRegion, RecentRegions, RepeatedCapture};
+use crate::settings::{Settings, SettingsStore, SETTINGS_CHANGED_EVENT};
+use crate::window_list::{WindowFilter, WindowInfo, WindowLookupError};
+use std::collections::HashMap;
 use tauri::command;
+use tauri::ipc::{InvokeBody, Request, Response};
+
+// ============================================
+// Binary IPC Helpers
+// ============================================
+
+/// Header carrying the (percent-encoded) destination path of a binary save request
+const PATH_HEADER: &str = "x-justsnap-path";
+
+/// Header carrying (percent-encoded) JSON provenance metadata of a binary save request
+const METADATA_HEADER: &str = "x-justsnap-metadata";
+
+/// Header carrying (percent-encoded) JSON `ClipboardExtras` of a binary clipboard request
+const CLIPBOARD_HEADER: &str = "x-justsnap-clipboard";
+
+/// Header naming the stored capture an image sent to `store_capture` was made from
+const BASE_CAPTURE_HEADER: &str = "x-justsnap-base-capture";
+
+/// Raw bytes of a binary IPC request body
+fn request_bytes<'a>(request: &'a Request<'_>) -> Result<&'a [u8], String> {
+    match request.body() {
+        InvokeBody::Raw(bytes) => Ok(bytes),
+        InvokeBody::Json(_) => Err("Expected a binary request body".to_string()),
+    }
+}
+
+/// Read and percent-decode a header of a binary IPC request
+fn request_header(request: &Request<'_>, name: &str) -> Result<String, String> {
+    let value = request
+        .headers()
+        .get(name)
+        .ok_or_else(|| format!("Missing '{}' header", name))?
+        .to_str()
+        .map_err(|e| format!("Invalid '{}' header: {}", name, e))?;
+
+    percent_encoding::percent_decode_str(value)
+        .decode_utf8()
+        .map(|v| v.into_owned())
+        .map_err(|e| format!("Invalid '{}' header: {}", name, e))
+}
 
 // ============================================
 // Screen Capture Commands
 // ============================================
 
 #[command]
-pub async fn capture_screen(x: i32, y: i32, width: i32, height: i32) -> Result<String, String> {
+pub async fn capture_screen(
+    backend: tauri::State<'_, CaptureBackendState>,
+    recent: tauri::State<'_, RecentRegions>,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    options: Option<crate::screen_capture::StitchOptions>,
+    format: Option<OutputFormat>,
+) -> Result<Response, String> {
     use crate::screen_capture::{capture_region, CaptureRegion};
-    use base64::{engine::general_purpose, Engine as _};
 
     let region = CaptureRegion {
         x,
@@ -19,16 +82,119 @@ pub async fn capture_screen(x: i32, y: i32, width: i32, height: i32) -> Result<S
         height,
     };
 
-    let bytes = capture_region(region).await?;
+    // Regions spanning several monitors are stitched together
+    let options = options.unwrap_or_default();
+    let format = format.unwrap_or_default();
+    let bytes = capture_region(backend.backend(), region, &options, &format).await?;
+
+    // Remember the region for "repeat last region"
+    let monitors = crate::screen_capture::get_all_monitors(backend.backend())?;
+    recent.remember(region, &monitors, chrono::Utc::now().timestamp_millis());
+
+    // Send raw bytes to avoid Base64 / JSON array overhead
+    Ok(Response::new(bytes))
+}
 
-    // Convert to Base64 to avoid JSON serialization overhead of byte arrays
-    Ok(general_purpose::STANDARD.encode(&bytes))
+/// A selection made in the overlay: a region, or the window picked with Smart Select
+#[derive(serde::Deserialize)]
+pub struct CaptureSelection {
+    /// Virtual desktop pixels
+    #[serde(flatten)]
+    pub region: crate::screen_capture::CaptureRegion,
+    /// Window the selection was made over
+    pub window: Option<WindowInfo>,
+    /// Grab `window` on its own, so windows covering it do not show up
+    #[serde(default)]
+    pub whole_window: bool,
+    #[serde(default)]
+    pub options: crate::screen_capture::StitchOptions,
+}
+
+/// A selection kept in the capture store
+#[derive(serde::Serialize)]
+pub struct SelectionCapture {
+    pub capture_id: String,
+    pub width: u32,
+    pub height: u32,
+    /// Unix time in milliseconds
+    pub captured_at: i64,
+}
+
+/// Capture an overlay selection straight into the capture store, so the editor can save,
+/// copy and pin it by id. The capture remembers its monitor and window (for auto-save names).
+#[command]
+pub async fn capture_selection(
+    backend: tauri::State<'_, CaptureBackendState>,
+    recent: tauri::State<'_, RecentRegions>,
+    store: tauri::State<'_, CaptureStore>,
+    selection: CaptureSelection,
+) -> Result<SelectionCapture, String> {
+    let region = selection.region;
+    let image = match (&selection.window, selection.whole_window) {
+        (Some(window), true) => crate::window_capture::capture_window(
+            window.id,
+            &crate::window_capture::WindowCaptureOptions::default(),
+        )?,
+        _ => crate::screen_capture::capture_region_raw(
+            backend.backend(),
+            region,
+            &selection.options,
+        )?,
+    };
+
+    let monitors = crate::screen_capture::get_all_monitors(backend.backend())?;
+    let monitor = crate::screen_capture::monitor_at_point(
+        &monitors,
+        region.x + region.width / 2,
+        region.y + region.height / 2,
+    );
+    let capture = StoredCapture::new(image).with_source(CaptureSource {
+        monitor_name: monitor.map(|m| m.name.clone()),
+        window_title: selection.window.as_ref().map(|w| w.title.clone()),
+        app_name: selection.window.as_ref().map(|w| w.app_name.clone()),
+        scale_factor: monitor.map(|m| m.scale_factor),
+    });
+    let captured_at = capture.captured_at.timestamp_millis();
+    let (width, height) = capture.image.dimensions();
+
+    // Remember the region for "repeat last region", like `capture_screen`
+    if !selection.whole_window {
+        recent.remember(region, &monitors, captured_at);
+    }
+
+    Ok(SelectionCapture {
+        capture_id: store.insert(capture),
+        width,
+        height,
+        captured_at,
+    })
+}
+
+/// Capture the most recent `capture_screen` region again, without the overlay,
+/// straight to the auto-save folder (when auto-save is on) or the clipboard
+#[command]
+pub async fn repeat_last_region(app: tauri::AppHandle) -> Result<RepeatedCapture, String> {
+    crate::recent_regions::repeat_last_region(&app)
+}
+
+/// Regions captured with `capture_screen`, newest first
+#[command]
+pub async fn get_recent_regions(
+    recent: tauri::State<'_, RecentRegions>,
+) -> Result<Vec<RecentRegion>, String> {
+    Ok(recent.list())
 }
 
+/// Capture the full screen. With `delay_secs`, a countdown (`capture-countdown` events) runs
+/// first and the monitor under the cursor is captured like the capture hotkey does.
 #[command]
-pub async fn capture_full_screen(app: tauri::AppHandle) -> Result<String, String> {
+pub async fn capture_full_screen(
+    app: tauri::AppHandle,
+    backend: tauri::State<'_, CaptureBackendState>,
+    format: Option<OutputFormat>,
+    delay_secs: Option<u32>,
+) -> Result<Response, String> {
     use crate::screen_capture::capture_full_screen;
-    use base64::{engine::general_purpose, Engine as _};
     use tauri::Manager;
 
     let window = app.get_webview_window("main");
@@ -39,10 +205,48 @@ pub async fn capture_full_screen(app: tauri::AppHandle) -> Result<String, String
         let _ = win.set_ignore_cursor_events(false);
     }
 
+    // Timed capture: count down (emitting `capture-countdown`) off the async runtime
+    if let Some(seconds) = delay_secs.filter(|seconds| *seconds > 0) {
+        let app_handle = app.clone();
+        let countdown = tauri::async_runtime::spawn_blocking(move || {
+            crate::capture_timer::run_countdown(&app_handle, seconds)
+        })
+        .await
+        .map_err(|e| format!("Countdown failed: {}", e))
+        .and_then(|result| result);
+
+        if let Err(e) = countdown {
+            if let Some(ref win) = window {
+                let _ = win.show();
+            }
+            return Err(e);
+        }
+
+        // Then the same monitor-at-cursor capture as the capture hotkey, which brings the
+        // overlay up on that monitor
+        let app_handle = app.clone();
+        let capture_id = tauri::async_runtime::spawn_blocking(move || {
+            crate::hotkeys::capture_at_cursor(&app_handle, crate::hotkeys::HotkeyAction::Capture)
+        })
+        .await
+        .map_err(|e| format!("Capture failed: {}", e))?;
+        let Some(capture_id) = capture_id else {
+            if let Some(ref win) = window {
+                let _ = win.show();
+            }
+            return Err("Failed to capture the monitor under the cursor".to_string());
+        };
+
+        let capture = app.state::<CaptureStore>().get(&capture_id)?;
+        let bytes = crate::encoding::encode_image(&capture.image, &format.unwrap_or_default())?;
+        return Ok(Response::new(bytes));
+    }
+
     // Slight delay to allow window to hide
     std::thread::sleep(std::time::Duration::from_millis(100));
 
-    let capture_result = capture_full_screen().await;
+    let format = format.unwrap_or_default();
+    let capture_result = capture_full_screen(backend.backend(), &format).await;
 
     // Show the window again immediately
     if let Some(ref win) = window {
@@ -51,37 +255,198 @@ pub async fn capture_full_screen(app: tauri::AppHandle) -> Result<String, String
     }
 
     let bytes = capture_result?;
-    Ok(general_purpose::STANDARD.encode(&bytes))
+    Ok(Response::new(bytes))
 }
 
+/// Capture the whole virtual desktop. The response leads with the layout manifest mapping
+/// monitors into the image (see `screen_capture::capture_virtual_desktop`).
 #[command]
-pub async fn get_monitors() -> Result<Vec<crate::screen_capture::MonitorInfo>, String> {
-    crate::screen_capture::get_all_monitors()
+pub async fn capture_all_monitors(
+    app: tauri::AppHandle,
+    backend: tauri::State<'_, CaptureBackendState>,
+    fill: Option<[u8; 4]>,
+    format: Option<OutputFormat>,
+) -> Result<Response, String> {
+    use crate::screen_capture::capture_virtual_desktop;
+    use tauri::Manager;
+
+    let window = app.get_webview_window("main");
+
+    // Hide the overlay so it does not end up in the capture
+    if let Some(ref win) = window {
+        let _ = win.hide();
+    }
+
+    // Slight delay to allow window to hide
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    let format = format.unwrap_or_default();
+    let capture_result =
+        capture_virtual_desktop(backend.backend(), fill.unwrap_or([0, 0, 0, 0]), &format).await;
+
+    if let Some(ref win) = window {
+        let _ = win.show();
+        let _ = win.set_focus();
+    }
+
+    let bytes = capture_result?;
+    Ok(Response::new(bytes))
+}
+
+#[command]
+pub async fn get_monitors(
+    backend: tauri::State<'_, CaptureBackendState>,
+) -> Result<Vec<crate::screen_capture::MonitorInfo>, String> {
+    crate::screen_capture::get_all_monitors(backend.backend())
 }
 
 #[derive(serde::Deserialize)]
 pub struct HotkeyConfig {
     pub key: String,
     pub modifiers: Vec<String>,
+    #[serde(default)]
+    pub delay_secs: Option<u32>,
 }
 
+impl From<HotkeyConfig> for crate::hotkeys::Hotkey {
+    fn from(config: HotkeyConfig) -> Self {
+        Self {
+            key: config.key,
+            modifiers: config.modifiers,
+            delay_secs: config.delay_secs,
+        }
+    }
+}
+
+/// Bind a hotkey to an action (region capture when no action is given)
 #[command]
-pub async fn register_hotkey(app: tauri::AppHandle, config: HotkeyConfig) -> Result<(), String> {
-    use crate::hotkeys::{register_global_hotkey, Hotkey};
+pub async fn register_hotkey(
+    app: tauri::AppHandle,
+    config: HotkeyConfig,
+    action: Option<HotkeyAction>,
+) -> Result<(), HotkeyError> {
+    use crate::hotkeys::register_action_hotkey;
 
-    let hotkey = Hotkey {
-        key: config.key,
-        modifiers: config.modifiers,
-    };
+    register_action_hotkey(&app, action.unwrap_or(HotkeyAction::Capture), config.into())
+}
+
+/// Bind hotkeys for several actions, reporting the outcome per action
+#[command]
+pub async fn register_hotkeys(
+    app: tauri::AppHandle,
+    hotkeys: HashMap<HotkeyAction, HotkeyConfig>,
+) -> Result<Vec<crate::hotkeys::HotkeyRegistration>, String> {
+    use crate::hotkeys::register_action_hotkeys;
+
+    let hotkeys = hotkeys
+        .into_iter()
+        .map(|(action, config)| (action, config.into()))
+        .collect();
 
-    register_global_hotkey(&app, hotkey)
+    Ok(register_action_hotkeys(&app, hotkeys))
 }
 
+/// Unbind one action's hotkey, or every hotkey when no action is given
 #[command]
-pub async fn unregister_hotkey(app: tauri::AppHandle) -> Result<(), String> {
-    use crate::hotkeys::unregister_global_hotkey;
+pub async fn unregister_hotkey(
+    app: tauri::AppHandle,
+    action: Option<HotkeyAction>,
+) -> Result<(), String> {
+    use crate::hotkeys::{unregister_action_hotkey, unregister_global_hotkey};
 
-    unregister_global_hotkey(&app)
+    match action {
+        Some(action) => unregister_action_hotkey(&app, action).map(|_| ()),
+        None => unregister_global_hotkey(&app),
+    }
+}
+
+/// Check a hotkey without binding it: normalises modifier names, rejects modifier-only or
+/// reserved combinations and shortcuts already bound to another action
+#[command]
+pub async fn validate_hotkey(
+    registry: tauri::State<'_, crate::hotkeys::HotkeyRegistry>,
+    config: HotkeyConfig,
+    action: Option<HotkeyAction>,
+) -> Result<NormalizedHotkey, HotkeyError> {
+    use crate::hotkeys::validate_action_hotkey;
+
+    validate_action_hotkey(&registry, action, &config.into())
+}
+
+#[command]
+pub async fn get_hotkeys(
+    registry: tauri::State<'_, crate::hotkeys::HotkeyRegistry>,
+) -> Result<Vec<crate::hotkeys::HotkeyBinding>, String> {
+    Ok(registry.bindings())
+}
+
+/// Run an action (region capture by default) after a countdown, like a timed hotkey press.
+/// Results arrive through the usual `screen-capture-ready` / `hotkey-action` events.
+#[command]
+pub async fn start_delayed_capture(
+    app: tauri::AppHandle,
+    delay_secs: u32,
+    action: Option<HotkeyAction>,
+) -> Result<(), String> {
+    crate::hotkeys::start_delayed_action(&app, action.unwrap_or(HotkeyAction::Capture), delay_secs);
+    Ok(())
+}
+
+/// Cancel a running timed capture, returning whether one was running
+#[command]
+pub async fn cancel_delayed_capture(
+    timer: tauri::State<'_, crate::capture_timer::CaptureTimer>,
+) -> Result<bool, String> {
+    Ok(timer.cancel())
+}
+
+// ============================================
+// Settings Commands
+// ============================================
+
+#[command]
+pub async fn get_settings(store: tauri::State<'_, SettingsStore>) -> Result<Settings, String> {
+    Ok(store.get())
+}
+
+/// Save a partial settings update (snake_case keys), apply what changed (hotkeys, autostart)
+/// and broadcast the new settings to every window
+#[command]
+pub async fn update_settings(
+    app: tauri::AppHandle,
+    store: tauri::State<'_, SettingsStore>,
+    patch: serde_json::Value,
+) -> Result<Settings, String> {
+    let (previous, settings) = store.update(patch)?;
+    apply_changed_settings(&app, &previous, &settings);
+    Ok(settings)
+}
+
+/// One-time import of the settings the webview stored before the backend owned them.
+/// Ignored once a settings file exists; returns the current settings either way.
+#[command]
+pub async fn import_legacy_settings(
+    app: tauri::AppHandle,
+    store: tauri::State<'_, SettingsStore>,
+    state: serde_json::Value,
+) -> Result<Settings, String> {
+    match store.import_legacy(state)? {
+        Some((previous, settings)) => {
+            apply_changed_settings(&app, &previous, &settings);
+            Ok(settings)
+        }
+        None => Ok(store.get()),
+    }
+}
+
+fn apply_changed_settings(app: &tauri::AppHandle, previous: &Settings, settings: &Settings) {
+    use tauri::Emitter;
+
+    crate::settings::apply_settings(app, settings, Some(previous));
+
+    if let Err(e) = app.emit(SETTINGS_CHANGED_EVENT, settings) {
+        eprintln!("[Error] Failed to emit settings change: {}", e);
+    }
 }
 
 // ============================================
@@ -114,6 +479,9 @@ pub async fn hide_overlay(app: tauri::AppHandle) -> Result<(), String> {
         let _ = window.set_ignore_cursor_events(false);
     }
 
+    // The next overlay looks elements up afresh
+    app.state::<crate::ui_elements::ElementLookup>().reset();
+
     Ok(())
 }
 
@@ -121,15 +489,30 @@ pub async fn hide_overlay(app: tauri::AppHandle) -> Result<(), String> {
 // File System Commands
 // ============================================
 
+/// Save encoded image bytes (request body) to the path given in the `x-justsnap-path` header,
+/// converting them to the format of the path's extension
+/// (png, jpg, webp, tiff, bmp, qoi, raw, pdf).
+/// An optional `x-justsnap-metadata` header (JSON `ExportMetadata`) is embedded in the file.
 #[command]
-pub async fn save_image(path: String, image_data: Vec<u8>) -> Result<(), String> {
-    use std::fs::File;
-    use std::io::Write;
+pub async fn save_image(request: Request<'_>) -> Result<(), String> {
+    use crate::export::{export_to_file, ExportMetadata};
+
+    let path = request_header(&request, PATH_HEADER)?;
+    let metadata = match request.headers().get(METADATA_HEADER) {
+        Some(_) => {
+            let json = request_header(&request, METADATA_HEADER)?;
+            let metadata: ExportMetadata = serde_json::from_str(&json)
+                .map_err(|e| format!("Invalid '{}' header: {}", METADATA_HEADER, e))?;
+            Some(metadata)
+        }
+        None => None,
+    };
 
-    let mut file = File::create(&path).map_err(|e| e.to_string())?;
-    file.write_all(&image_data).map_err(|e| e.to_string())?;
+    let image = image::load_from_memory(request_bytes(&request)?)
+        .map_err(|e| format!("Failed to decode image: {}", e))?
+        .to_rgba8();
 
-    Ok(())
+    export_to_file(&[&image], std::path::Path::new(&path), metadata.as_ref())
 }
 
 // Note: save_text is currently unused but kept for future text export features
@@ -139,14 +522,28 @@ pub async fn save_image(path: String, image_data: Vec<u8>) -> Result<(), String>
 // }
 
 #[command]
-pub async fn open_save_dialog(app: tauri::AppHandle) -> Result<Option<String>, String> {
+pub async fn open_save_dialog(
+    app: tauri::AppHandle,
+    default_name: Option<String>,
+) -> Result<Option<String>, String> {
     use tauri_plugin_dialog::DialogExt;
 
-    let file_path = app
+    let mut dialog = app
         .dialog()
         .file()
-        .add_filter("Image", &["png"])
-        .blocking_save_file();
+        .add_filter("PNG Image", &["png"])
+        .add_filter("JPEG Image", &["jpg", "jpeg"])
+        .add_filter("WebP Image", &["webp"])
+        .add_filter("TIFF Image", &["tif", "tiff"])
+        .add_filter("Bitmap", &["bmp"])
+        .add_filter("QOI Image", &["qoi"])
+        .add_filter("Raw RGBA", &["raw", "rgba"])
+        .add_filter("PDF Document", &["pdf"]);
+    if let Some(name) = default_name {
+        dialog = dialog.set_file_name(name);
+    }
+
+    let file_path = dialog.blocking_save_file();
 
     Ok(file_path.map(|p| p.to_string()))
 }
@@ -155,34 +552,73 @@ pub async fn open_save_dialog(app: tauri::AppHandle) -> Result<Option<String>, S
 // Clipboard Commands
 // ============================================
 
+/// Copy encoded image bytes (request body) to the clipboard
 #[command]
-pub async fn copy_image_to_clipboard(image_data: Vec<u8>) -> Result<(), String> {
-    use arboard::{Clipboard, ImageData};
+pub async fn copy_image_to_clipboard(request: Request<'_>) -> Result<(), String> {
+    let image_data = request_bytes(&request)?;
 
     // Decode bytes to raw RGBA (auto-detect format, handles BMP/PNG)
-    let img = image::load_from_memory(&image_data)
+    let img = image::load_from_memory(image_data)
         .map_err(|e| format!("Failed to decode image: {}", e))?
         .to_rgba8();
 
-    let width = img.width() as usize;
-    let height = img.height() as usize;
-    let rgba_data = img.into_raw();
+    crate::clipboard::set_image(&img)
+}
+
+/// Extra clipboard flavours sent along with an image
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+pub struct ClipboardExtras {
+    /// Plain text (e.g. OCR text); also the alt text of the HTML flavour
+    pub text: Option<String>,
+    /// Offer the image as HTML (`<img>` with a data URI); on by default
+    pub html: Option<bool>,
+    /// Files for the file-list flavour (e.g. where the capture was saved)
+    pub files: Vec<String>,
+}
 
-    // Create clipboard image data
-    let img_data = ImageData {
-        width,
-        height,
-        bytes: rgba_data.into(),
+/// Put an image on the clipboard together with its text, HTML and file-list flavours
+fn copy_with_extras(
+    image: Option<&image::RgbaImage>,
+    extras: ClipboardExtras,
+) -> Result<Vec<ClipboardFlavour>, String> {
+    use crate::clipboard::{set_contents, ClipboardContent};
+
+    let html_alt = match extras.html {
+        Some(false) => None,
+        _ => Some(extras.text.clone().unwrap_or_default()),
     };
 
-    // Copy to clipboard
-    let mut clipboard =
-        Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
-    clipboard
-        .set_image(img_data)
-        .map_err(|e| format!("Failed to copy image to clipboard: {}", e))?;
+    set_contents(&ClipboardContent {
+        image,
+        text: extras.text,
+        html_alt,
+        files: extras.files.into_iter().map(Into::into).collect(),
+    })
+}
 
-    Ok(())
+/// Copy encoded image bytes (request body, may be empty) with the extra flavours given as JSON
+/// `ClipboardExtras` in the `x-justsnap-clipboard` header. Returns the flavours that were set.
+#[command]
+pub async fn copy_to_clipboard(request: Request<'_>) -> Result<Vec<ClipboardFlavour>, String> {
+    let extras: ClipboardExtras = match request.headers().get(CLIPBOARD_HEADER) {
+        Some(_) => serde_json::from_str(&request_header(&request, CLIPBOARD_HEADER)?)
+            .map_err(|e| format!("Invalid '{}' header: {}", CLIPBOARD_HEADER, e))?,
+        None => ClipboardExtras::default(),
+    };
+
+    let bytes = request_bytes(&request)?;
+    let image = if bytes.is_empty() {
+        None
+    } else {
+        Some(
+            image::load_from_memory(bytes)
+                .map_err(|e| format!("Failed to decode image: {}", e))?
+                .to_rgba8(),
+        )
+    };
+
+    copy_with_extras(image.as_ref(), extras)
 }
 
 #[command]
@@ -198,9 +634,189 @@ pub async fn copy_text_to_clipboard(text: String) -> Result<(), String> {
     Ok(())
 }
 
+// ============================================
+// Capture Store Commands
+// ============================================
+
+/// Encoded bytes of a stored capture
+#[command]
+pub async fn get_capture(
+    store: tauri::State<'_, CaptureStore>,
+    capture_id: String,
+    format: Option<OutputFormat>,
+) -> Result<Response, String> {
+    let capture = store.get(&capture_id)?;
+    let bytes = crate::encoding::encode_image(&capture.image, &format.unwrap_or_default())?;
+    Ok(Response::new(bytes))
+}
+
+/// Store encoded image bytes (request body), e.g. an annotated export, and return its capture id.
+/// With an `x-justsnap-base-capture` header the image keeps that capture's time and source
+/// (for auto-save names and embedded metadata).
+#[command]
+pub async fn store_capture(
+    store: tauri::State<'_, CaptureStore>,
+    request: Request<'_>,
+) -> Result<String, String> {
+    let image = image::load_from_memory(request_bytes(&request)?)
+        .map_err(|e| format!("Failed to decode image: {}", e))?
+        .to_rgba8();
+
+    let capture = match request.headers().get(BASE_CAPTURE_HEADER) {
+        Some(_) => {
+            let base = store.get(&request_header(&request, BASE_CAPTURE_HEADER)?)?;
+            StoredCapture {
+                image,
+                captured_at: base.captured_at,
+                source: base.source.clone(),
+            }
+        }
+        None => StoredCapture::new(image),
+    };
+    Ok(store.insert(capture))
+}
+
+/// Crop a stored capture (image pixel coordinates) into a new capture, returning its id
+#[command]
+pub async fn crop_capture(
+    store: tauri::State<'_, CaptureStore>,
+    capture_id: String,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+) -> Result<String, String> {
+    use crate::screen_capture::{crop_image, CaptureRegion};
+
+    let capture = store.get(&capture_id)?;
+    let region = CaptureRegion {
+        x,
+        y,
+        width,
+        height,
+    };
+    let cropped = crop_image(&capture.image, region)?;
+
+    // The crop keeps the original capture time and source (for auto-save file names)
+    Ok(store.insert(StoredCapture {
+        image: cropped,
+        captured_at: capture.captured_at,
+        source: capture.source.clone(),
+    }))
+}
+
+/// Save a stored capture in the format of the path's extension (PNG by default).
+/// Unless `include_metadata` is false, capture time, monitor, window title and scale factor are embedded.
+#[command]
+pub async fn save_capture(
+    store: tauri::State<'_, CaptureStore>,
+    capture_id: String,
+    path: String,
+    include_metadata: Option<bool>,
+) -> Result<(), String> {
+    export_captures(store, vec![capture_id], path, include_metadata).await
+}
+
+/// Save stored captures to one file; PDF gets one page per capture, other formats take a single capture
+#[command]
+pub async fn export_captures(
+    store: tauri::State<'_, CaptureStore>,
+    capture_ids: Vec<String>,
+    path: String,
+    include_metadata: Option<bool>,
+) -> Result<(), String> {
+    use crate::export::{export_to_file, ExportMetadata};
+
+    let captures = capture_ids
+        .iter()
+        .map(|id| store.get(id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let images: Vec<&image::RgbaImage> = captures.iter().map(|capture| &capture.image).collect();
+
+    // PDF document info describes the first capture
+    let metadata = match (include_metadata.unwrap_or(true), captures.first()) {
+        (true, Some(capture)) => Some(ExportMetadata::from_capture(
+            &capture.captured_at,
+            &capture.source,
+        )),
+        _ => None,
+    };
+
+    export_to_file(&images, std::path::Path::new(&path), metadata.as_ref())
+}
+
+/// Save a stored capture into the save folder under a name built from a template
+/// (`{date}`, `{time}`, `{app_name}`, `{title}`, `{monitor}`, `{n}`, `{ext}`), returning the final path.
+/// Template and folder default to the `auto_save_template` and `save_path` settings.
+#[command]
+pub async fn auto_save_capture(
+    store: tauri::State<'_, CaptureStore>,
+    settings: tauri::State<'_, SettingsStore>,
+    capture_id: String,
+    window: Option<WindowInfo>,
+    template: Option<String>,
+    directory: Option<String>,
+    format: Option<OutputFormat>,
+) -> Result<String, String> {
+    use crate::auto_save::{save_with_template, SaveTemplate};
+
+    let settings = settings.get();
+    let directory = directory
+        .or(settings.save_path)
+        .ok_or_else(|| "No save folder set".to_string())?;
+    let template = SaveTemplate::parse(&template.unwrap_or(settings.auto_save_template))?;
+
+    let capture = store.get(&capture_id)?;
+    // The window the capture was taken from (e.g. picked with smart select)
+    let source = match window {
+        Some(window) => CaptureSource {
+            window_title: Some(window.title),
+            app_name: Some(window.app_name),
+            ..capture.source.clone()
+        },
+        None => capture.source.clone(),
+    };
+
+    let path = save_with_template(
+        &capture.image,
+        &capture.captured_at,
+        &source,
+        std::path::Path::new(&directory),
+        &template,
+        &format.unwrap_or(OutputFormat::Png { level: None }),
+    )?;
+
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Copy a stored capture; with `extras` its text, HTML and file-list flavours are added.
+/// Returns the flavours that were set.
 #[command]
-pub async fn save_temp_image(image_data: Vec<u8>) -> Result<String, String> {
+pub async fn copy_capture_to_clipboard(
+    store: tauri::State<'_, CaptureStore>,
+    capture_id: String,
+    extras: Option<ClipboardExtras>,
+) -> Result<Vec<ClipboardFlavour>, String> {
+    let capture = store.get(&capture_id)?;
+    match extras {
+        Some(extras) => copy_with_extras(Some(&capture.image), extras),
+        None => crate::clipboard::set_image(&capture.image).map(|_| vec![ClipboardFlavour::Image]),
+    }
+}
+
+/// Free a stored capture once the webview no longer needs it
+#[command]
+pub async fn release_capture(
+    store: tauri::State<'_, CaptureStore>,
+    capture_id: String,
+) -> Result<bool, String> {
+    Ok(store.remove(&capture_id))
+}
+
+#[command]
+pub async fn save_temp_image(request: Request<'_>) -> Result<String, String> {
     use std::io::Write;
+    let image_data = request_bytes(&request)?;
     let mut temp_path = std::env::temp_dir();
     let file_name = format!(
         "justsnap_sticky_{}.png",
@@ -210,7 +826,7 @@ pub async fn save_temp_image(image_data: Vec<u8>) -> Result<String, String> {
 
     let path_str = temp_path.to_string_lossy().to_string();
     let mut file = std::fs::File::create(&temp_path).map_err(|e| e.to_string())?;
-    file.write_all(&image_data).map_err(|e| e.to_string())?;
+    file.write_all(image_data).map_err(|e| e.to_string())?;
 
     Ok(path_str)
 }
@@ -218,45 +834,33 @@ pub async fn save_temp_image(image_data: Vec<u8>) -> Result<String, String> {
 #[command]
 pub async fn create_sticky_window(
     app: tauri::AppHandle,
-    image_src: String,        // Expects full data URL or src
-    annotations_json: String, // JSON string of annotations
+    image_src: String,          // Expects full data URL or src
+    capture_id: Option<String>, // Stored capture to show instead of image_src
+    annotations_json: String,   // JSON string of annotations
     x: f64,
     y: f64,
     width: f64,
     height: f64,
     native_width: f64,  // New: Physical Width of the original image
     native_height: f64, // New: Physical Height of the original image
+    store: tauri::State<'_, CaptureStore>,
 ) -> Result<(), String> {
-    use tauri::{WebviewUrl, WebviewWindowBuilder};
-
-    let label = format!("sticky_{}", chrono::Utc::now().timestamp_micros());
-
-    // Inject window type and image src, AND native dimensions
-    let init_script = format!(
-        "window.__WINDOW_TYPE__ = 'sticky'; 
-         window.__STICKY_IMAGE_SRC__ = {:?}; 
-         window.__STICKY_ANNOTATIONS__ = {:?};
-         window.__STICKY_NATIVE_WIDTH__ = {};
-         window.__STICKY_NATIVE_HEIGHT__ = {};",
-        image_src, annotations_json, native_width, native_height
-    );
-
-    let _win = WebviewWindowBuilder::new(&app, &label, WebviewUrl::App("index.html".into()))
-        .title("JustSnap Sticky")
-        .decorations(false)
-        .resizable(true)
-        .always_on_top(true)
-        .skip_taskbar(true);
-
-    let _win = _win
-        .shadow(true)
-        .inner_size(width, height)
-        .position(x, y)
-        .initialization_script(&init_script)
-        .build()
-        .map_err(|e: tauri::Error| e.to_string())?;
-
-    Ok(())
+    // The sticky gets its own handle so the caller can release theirs
+    let capture_id = capture_id.map(|id| store.share(&id)).transpose()?;
+    crate::image_input::create_sticky_window(
+        &app,
+        StickyWindow {
+            image_src,
+            capture_id,
+            annotations_json,
+            x,
+            y,
+            width,
+            height,
+            native_width,
+            native_height,
+        },
+    )
 }
 
 #[command]
@@ -343,236 +947,364 @@ pub async fn close_window(window: tauri::Window) -> Result<(), String> {
 }
 
 // ============================================
-// Window Enumeration for Smart Select
+// Capture History Commands
 // ============================================
 
-#[derive(serde::Serialize)]
-pub struct WindowInfo {
-    pub id: u32,
-    pub title: String,
-    pub app_name: String,
-    pub x: i32,
-    pub y: i32,
-    pub width: u32,
-    pub height: u32,
-    pub z_order: i32, // Lower number = closer to user (top-most)
+/// Header carrying (percent-encoded) JSON details of a capture recorded in the history
+const HISTORY_HEADER: &str = "x-justsnap-history";
+
+/// Details sent along with a capture to record in the history
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+struct HistoryRecordRequest {
+    /// Unix time in milliseconds (now when omitted)
+    captured_at: Option<i64>,
+    #[serde(flatten)]
+    details: HistoryDetails,
+    /// Window the capture was taken from (e.g. picked with smart select)
+    window: Option<WindowInfo>,
 }
 
-#[cfg(windows)]
-fn get_window_z_order_map() -> std::collections::HashMap<u32, i32> {
-    use std::collections::HashMap;
-    use windows::Win32::Foundation::LPARAM;
-    use windows::Win32::UI::WindowsAndMessaging::EnumWindows;
-
-    let mut z_order_map: HashMap<u32, i32> = HashMap::new();
-
-    // Create a tuple on the stack that will be passed to the callback
-    // The callback will increment z_index as it enumerates windows
-    let mut data = (0i32, &mut z_order_map);
-
-    unsafe {
-        // Enumerate all top-level windows in Z-order (top to bottom)
-        let _ = EnumWindows(
-            Some(enum_windows_proc),
-            LPARAM(&mut data as *mut _ as isize),
-        );
-    }
+/// Record encoded image bytes (request body) in the capture history.
+/// An optional `x-justsnap-history` header carries the region (virtual desktop pixels),
+/// source window, saved path and OCR text.
+#[command]
+pub async fn record_capture_history(
+    app: tauri::AppHandle,
+    backend: tauri::State<'_, CaptureBackendState>,
+    request: Request<'_>,
+) -> Result<HistoryEntry, String> {
+    let record: HistoryRecordRequest = match request.headers().get(HISTORY_HEADER) {
+        Some(_) => serde_json::from_str(&request_header(&request, HISTORY_HEADER)?)
+            .map_err(|e| format!("Invalid '{}' header: {}", HISTORY_HEADER, e))?,
+        None => HistoryRecordRequest::default(),
+    };
 
-    z_order_map
+    let image = image::load_from_memory(request_bytes(&request)?)
+        .map_err(|e| format!("Failed to decode image: {}", e))?
+        .to_rgba8();
+
+    let details = record.details;
+    let captured_at = record
+        .captured_at
+        .unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+    record_history(&app, &backend, &image, captured_at, details, record.window)
 }
 
-#[cfg(windows)]
-unsafe extern "system" fn enum_windows_proc(
-    hwnd: windows::Win32::Foundation::HWND,
-    lparam: windows::Win32::Foundation::LPARAM,
-) -> windows::Win32::Foundation::BOOL {
-    use std::collections::HashMap;
-
-    let data = lparam.0 as *mut (i32, *mut HashMap<u32, i32>);
-    let (z_index, map_ptr) = &mut *data;
-    let map = &mut **map_ptr;
-
-    // Store the Z-order for this window handle
-    let window_id = hwnd.0 as u32;
-    map.insert(window_id, *z_index);
-    *z_index += 1;
-
-    windows::Win32::Foundation::BOOL::from(true) // Continue enumeration
-}
-
-#[cfg(not(windows))]
-#[allow(dead_code)]
-fn get_window_z_order_map() -> std::collections::HashMap<u32, i32> {
-    // On non-Windows platforms, return empty map
-    // The z_order will default to the xcap enumeration order
-    std::collections::HashMap::new()
-}
-
-// Helper function to check if two rectangles intersect
-#[allow(dead_code)]
-fn rectangles_intersect(
-    rect1: (i32, i32, i32, i32), // (x, y, width, height)
-    rect2: (i32, i32, i32, i32),
-) -> bool {
-    let (x1, y1, w1, h1) = rect1;
-    let (x2, y2, w2, h2) = rect2;
-
-    // Calculate boundaries
-    let left1 = x1;
-    let right1 = x1 + w1;
-    let top1 = y1;
-    let bottom1 = y1 + h1;
-
-    let left2 = x2;
-    let right2 = x2 + w2;
-    let top2 = y2;
-    let bottom2 = y2 + h2;
-
-    // Check if rectangles do NOT intersect, then negate
-    !(right1 <= left2 || right2 <= left1 || bottom1 <= top2 || bottom2 <= top1)
-}
-
-// get_windows and get_cursor_position removed as they were unused by frontend
-
-// Get window at specific screen coordinates (WYSIWYG - what you see is what you get)
-#[command]
-pub async fn get_window_at_point(x: i32, y: i32) -> Result<Option<WindowInfo>, String> {
-    #[cfg(windows)]
-    {
-        use windows::Win32::Foundation::POINT;
-        use windows::Win32::UI::WindowsAndMessaging::{
-            GetAncestor, IsIconic, IsWindowVisible, WindowFromPoint, GA_ROOT,
-        };
-        use xcap::Window;
+/// Record a stored capture in the capture history, with its capture time, monitor and window.
+/// `details` adds the region (virtual desktop pixels), saved path and OCR text.
+#[command]
+pub async fn record_stored_capture_history(
+    app: tauri::AppHandle,
+    backend: tauri::State<'_, CaptureBackendState>,
+    store: tauri::State<'_, CaptureStore>,
+    capture_id: String,
+    details: Option<HistoryDetails>,
+) -> Result<HistoryEntry, String> {
+    let capture = store.get(&capture_id)?;
+    let mut details = details.unwrap_or_default();
+    details.window_title = details
+        .window_title
+        .or_else(|| capture.source.window_title.clone());
+    details.app_name = details.app_name.or_else(|| capture.source.app_name.clone());
+    details.monitor_name = details
+        .monitor_name
+        .or_else(|| capture.source.monitor_name.clone());
+
+    let captured_at = capture.captured_at.timestamp_millis();
+    record_history(&app, &backend, &capture.image, captured_at, details, None)
+}
 
-        unsafe {
-            let point = POINT { x, y };
+/// Add a capture to the history, naming its window and the monitor the region is on
+fn record_history(
+    app: &tauri::AppHandle,
+    backend: &CaptureBackendState,
+    image: &image::RgbaImage,
+    captured_at: i64,
+    mut details: HistoryDetails,
+    window: Option<WindowInfo>,
+) -> Result<HistoryEntry, String> {
+    if let Some(window) = window {
+        details.window_title = Some(window.title);
+        details.app_name = Some(window.app_name);
+    }
+    // Name the monitor the middle of the region is on
+    if let (None, Some(region)) = (&details.monitor_name, details.region) {
+        let monitors = crate::screen_capture::get_all_monitors(backend.backend())?;
+        details.monitor_name = crate::screen_capture::monitor_at_point(
+            &monitors,
+            region.x + (region.width / 2) as i32,
+            region.y + (region.height / 2) as i32,
+        )
+        .map(|monitor| monitor.name.clone());
+    }
 
-            // Get the top-most visible window at this point
-            let mut hwnd = WindowFromPoint(point);
+    record_capture(app, image, captured_at, details)
+}
 
-            if hwnd.is_invalid() {
-                return Ok(None);
-            }
+/// Captures in the history, newest first, filtered by text and date
+#[command]
+pub async fn list_history(
+    history: tauri::State<'_, CaptureHistory>,
+    query: Option<HistoryQuery>,
+) -> Result<Vec<HistoryEntry>, String> {
+    history.list(&query.unwrap_or_default())
+}
 
-            // Get the root window (not a child control)
-            hwnd = GetAncestor(hwnd, GA_ROOT);
+/// JPEG thumbnail of a history entry
+#[command]
+pub async fn get_history_thumbnail(
+    history: tauri::State<'_, CaptureHistory>,
+    id: i64,
+) -> Result<Response, String> {
+    Ok(Response::new(history.thumbnail(id)?))
+}
 
-            if hwnd.is_invalid() {
-                return Ok(None);
-            }
+/// Fill in the saved path or OCR text of a history entry
+#[command]
+pub async fn update_history_entry(
+    app: tauri::AppHandle,
+    history: tauri::State<'_, CaptureHistory>,
+    id: i64,
+    update: HistoryUpdate,
+) -> Result<HistoryEntry, String> {
+    use tauri::Emitter;
+
+    let entry = history.update(id, update)?;
+    let _ = app.emit(HISTORY_CHANGED_EVENT, ());
+    Ok(entry)
+}
 
-            // Skip if not visible or minimized
-            if !IsWindowVisible(hwnd).as_bool() || IsIconic(hwnd).as_bool() {
-                return Ok(None);
-            }
+/// Delete history entries, returning how many were removed
+#[command]
+pub async fn delete_history(
+    app: tauri::AppHandle,
+    history: tauri::State<'_, CaptureHistory>,
+    ids: Vec<i64>,
+) -> Result<usize, String> {
+    use tauri::Emitter;
+
+    let removed = history.delete(&ids)?;
+    let _ = app.emit(HISTORY_CHANGED_EVENT, ());
+    Ok(removed)
+}
 
-            let window_id = hwnd.0 as u32;
-
-            // Try to find this window in xcap's window list to get full details
-            let all_windows = Window::all().map_err(|e| e.to_string())?;
-
-            // Get Z-order map to check if this window is covered by others
-            let z_order_map = get_window_z_order_map();
-
-            for window in &all_windows {
-                if window.id().unwrap_or(0) == window_id {
-                    // Skip minimized windows
-                    if window.is_minimized().unwrap_or(false) {
-                        return Ok(None);
-                    }
-
-                    // Skip windows with invalid dimensions
-                    if window.width().unwrap_or(0) == 0 || window.height().unwrap_or(0) == 0 {
-                        return Ok(None);
-                    }
-
-                    let title = window.title().unwrap_or_default();
-                    let app_name = window.app_name().unwrap_or_default();
-
-                    // Filter out system/overlay windows
-                    let title_lower = title.to_lowercase();
-                    let app_lower = app_name.to_lowercase();
-
-                    if title_lower == "program manager"
-                        || title_lower == "default ime"
-                        || title_lower.contains("overlay")
-                        || app_lower.contains("justsnap")
-                        || title_lower.contains("justsnap")
-                    {
-                        return Ok(None);
-                    }
-
-                    // STRICT RULE: Check if this window is covered by any other window
-                    // If any part is covered, reject it entirely
-                    let current_z = z_order_map.get(&window_id).copied().unwrap_or(i32::MAX);
-                    let current_rect = (
-                        window.x().unwrap_or(0),
-                        window.y().unwrap_or(0),
-                        window.width().unwrap_or(0) as i32,
-                        window.height().unwrap_or(0) as i32,
-                    );
-
-                    // Check all other windows
-                    for other_window in &all_windows {
-                        if other_window.id().unwrap_or(0) == window_id {
-                            continue; // Skip self
-                        }
-
-                        // Skip minimized/invalid windows
-                        if other_window.is_minimized().unwrap_or(false)
-                            || other_window.width().unwrap_or(0) == 0
-                            || other_window.height().unwrap_or(0) == 0
-                        {
-                            continue;
-                        }
-
-                        // Get other window's Z-order
-                        let other_z = z_order_map
-                            .get(&other_window.id().unwrap_or(0))
-                            .copied()
-                            .unwrap_or(i32::MAX);
-
-                        // If other window is above us (lower z-order = higher in stack)
-                        if other_z < current_z {
-                            // Check if rectangles overlap
-                            let other_rect = (
-                                other_window.x().unwrap_or(0),
-                                other_window.y().unwrap_or(0),
-                                other_window.width().unwrap_or(0) as i32,
-                                other_window.height().unwrap_or(0) as i32,
-                            );
-
-                            if rectangles_intersect(current_rect, other_rect) {
-                                // This window is partially covered - reject it!
-                                return Ok(None);
-                            }
-                        }
-                    }
-
-                    // Window is not covered by any other window - allow selection
-                    return Ok(Some(WindowInfo {
-                        id: window_id,
-                        title: title.to_string(),
-                        app_name: app_name.to_string(),
-                        x: window.x().unwrap_or(0),
-                        y: window.y().unwrap_or(0),
-                        width: window.width().unwrap_or(0),
-                        height: window.height().unwrap_or(0),
-                        z_order: current_z,
-                    }));
-                }
-            }
+/// Load a history entry into the capture store and open it in the editor
+/// on the monitor under the cursor (`open-in-editor` event). Returns the capture id.
+#[command]
+pub async fn reopen_history(
+    app: tauri::AppHandle,
+    history: tauri::State<'_, CaptureHistory>,
+    store: tauri::State<'_, CaptureStore>,
+    id: i64,
+) -> Result<String, String> {
+    use chrono::TimeZone;
+
+    let entry = history.get(id)?;
+    let image = history.load_image(id)?;
+
+    let captured_at = chrono::Local
+        .timestamp_millis_opt(entry.captured_at)
+        .single()
+        .unwrap_or_else(chrono::Local::now);
+    let capture_id = store.insert(StoredCapture {
+        image,
+        captured_at,
+        source: CaptureSource {
+            monitor_name: entry.monitor_name,
+            window_title: entry.window_title,
+            app_name: entry.app_name,
+            scale_factor: None,
+        },
+    });
+
+    crate::image_input::open_in_editor(&app, &capture_id, Some(id))?;
+    Ok(capture_id)
+}
+
+// ============================================
+// Image Input Commands
+// ============================================
+
+/// Read an image from the clipboard (image data, or a copied image file / path),
+/// store it like a capture and open it in the editor (`target` "none" only stores it)
+#[command]
+pub async fn paste_image_from_clipboard(
+    app: tauri::AppHandle,
+    target: Option<OpenTarget>,
+) -> Result<OpenedImage, String> {
+    crate::image_input::paste_from_clipboard(&app, target.unwrap_or_default())
+}
 
-            // Window not found in xcap list
-            Ok(None)
+/// Open image files (GIFs give their first frame) in the editor or as stickies.
+/// Without `paths` the open dialog asks for them; an empty list means it was cancelled.
+#[command]
+pub async fn open_image(
+    app: tauri::AppHandle,
+    paths: Option<Vec<String>>,
+    target: Option<OpenTarget>,
+) -> Result<Vec<OpenedImage>, String> {
+    let target = target.unwrap_or_default();
+    match paths {
+        Some(paths) => {
+            let paths = paths
+                .into_iter()
+                .map(std::path::PathBuf::from)
+                .collect::<Vec<_>>();
+            crate::image_input::open_files(&app, &paths, target)
         }
+        None => crate::image_input::open_with_dialog(&app, target),
     }
+}
 
-    #[cfg(not(windows))]
-    {
-        let _ = x;
-        let _ = y;
-        Ok(None)
+/// Open the image files given on the command line (called by the editor once it listens)
+#[command]
+pub async fn open_startup_files(
+    app: tauri::AppHandle,
+    startup: tauri::State<'_, StartupFiles>,
+) -> Result<Vec<OpenedImage>, String> {
+    let paths = startup.take();
+    if paths.is_empty() {
+        return Ok(Vec::new());
     }
+    crate::image_input::open_files(&app, &paths, OpenTarget::Editor)
+}
+
+// ============================================
+// Window Enumeration for Smart Select
+// ============================================
+
+/// Top-level windows front to back (z_order 0 = top-most), without JustSnap's own and shell
+/// windows. Filters by app name / title regex for the window picker and scripts.
+#[command]
+pub async fn list_windows(filter: Option<WindowFilter>) -> Result<Vec<WindowInfo>, String> {
+    crate::window_list::list_windows(&filter.unwrap_or_default())
+}
+
+/// Window under the point, looking through JustSnap's own windows, with the parts of it that
+/// are not covered (`visible_regions`, `visible_ratio`). Windows showing less than
+/// `min_visible_ratio` (0-1, default 0: any visible part) are not returned.
+/// Fails with `WindowLookupError::Unsupported` on Wayland.
+#[command]
+pub async fn get_window_at_point(
+    x: i32,
+    y: i32,
+    min_visible_ratio: Option<f64>,
+) -> Result<Option<WindowInfo>, WindowLookupError> {
+    #[cfg(target_os = "linux")]
+    if crate::x11_windows::is_wayland_session() {
+        return Err(WindowLookupError::Unsupported {
+            message: crate::x11_windows::UNSUPPORTED_ON_WAYLAND.to_string(),
+        });
+    }
+
+    let hit = window_at_point(x, y)?;
+    Ok(hit.filter(|window| window.visible_ratio >= min_visible_ratio.unwrap_or(0.0)))
+}
+
+/// `get_window_at_point` plus the nested elements of the window under the point (child
+/// windows, accessibility elements), outermost first, so the overlay can snap to a part of it
+/// `window` skips the window lookup, for callers that already asked `get_window_at_point`
+/// (the overlay would otherwise have to let clicks through while the elements are looked up).
+/// The accessibility connection is kept until `hide_overlay`; a lookup that takes too long
+/// returns just the window.
+#[command]
+pub async fn get_elements_at_point(
+    app: tauri::AppHandle,
+    x: i32,
+    y: i32,
+    min_visible_ratio: Option<f64>,
+    window: Option<WindowInfo>,
+) -> Result<Option<crate::ui_elements::ElementStack>, WindowLookupError> {
+    use tauri::Manager;
+
+    let window = match window {
+        Some(window) => Some(window),
+        None => get_window_at_point(x, y, min_visible_ratio).await?,
+    };
+    let Some(window) = window else {
+        return Ok(None);
+    };
+
+    // Walking an accessibility tree waits on the application, so keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let lookup = app.state::<crate::ui_elements::ElementLookup>();
+        Some(lookup.elements_at_point(window, x, y))
+    })
+    .await
+    .map_err(|e| WindowLookupError::from(format!("Element lookup failed: {}", e)))
+}
+
+#[cfg(windows)]
+fn window_at_point(x: i32, y: i32) -> Result<Option<WindowInfo>, String> {
+    use crate::window_list::is_own_or_system;
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetAncestor, IsIconic, IsWindowVisible, WindowFromPoint, GA_ROOT,
+    };
+
+    let window_id = unsafe {
+        // Get the top-most visible window at this point
+        let hwnd = WindowFromPoint(POINT { x, y });
+        if hwnd.is_invalid() {
+            return Ok(None);
+        }
+
+        // Get the root window (not a child control)
+        let hwnd = GetAncestor(hwnd, GA_ROOT);
+        if hwnd.is_invalid() || !IsWindowVisible(hwnd).as_bool() || IsIconic(hwnd).as_bool() {
+            return Ok(None);
+        }
+        hwnd.0 as u32
+    };
+
+    // Full details and visibility from the window list (also drops system/overlay windows)
+    Ok(crate::window_list::all_windows()?
+        .into_iter()
+        .find(|window| window.id == window_id)
+        .filter(|window| window.is_visible && !is_own_or_system(&window.title, window.pid)))
+}
+
+/// X11 through the window manager's stacking list; an error on Wayland
+#[cfg(target_os = "linux")]
+fn window_at_point(x: i32, y: i32) -> Result<Option<WindowInfo>, String> {
+    let windows = crate::x11_windows::client_windows()?
+        .into_iter()
+        .enumerate()
+        .map(|(z_order, window)| WindowInfo {
+            id: window.id,
+            title: window.title,
+            app_name: window.app_name,
+            x: window.x,
+            y: window.y,
+            width: window.width,
+            height: window.height,
+            z_order: z_order as i32,
+            monitor_id: None,
+            pid: window.pid,
+            is_minimized: false,
+            is_visible: true,
+            visible_regions: Vec::new(),
+            visible_ratio: 0.0,
+        })
+        .collect();
+    let windows = crate::window_list::with_visibility(windows);
+    Ok(crate::window_list::window_at_point(windows, x, y))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn window_at_point(_x: i32, _y: i32) -> Result<Option<WindowInfo>, String> {
+    Ok(None)
+}
+
+/// Capture one window's own pixels, so windows covering it do not show up.
+/// Returns a PNG whose background (rounded corners, kept shadow) is transparent.
+#[command]
+pub async fn capture_window(
+    id: u32,
+    options: Option<crate::window_capture::WindowCaptureOptions>,
+) -> Result<Response, String> {
+    let image = crate::window_capture::capture_window(id, &options.unwrap_or_default())?;
+    let png = crate::encoding::encode_image(&image, &OutputFormat::Png { level: None })?;
+    Ok(Response::new(png))
 }
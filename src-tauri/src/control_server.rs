@@ -0,0 +1,785 @@
+// JustSnap - Control Server Module
+// Opt-in JSON-RPC 2.0 server on a local socket so scripts and editor plugins can drive JustSnap
+
+use crate::capture_backend::CaptureBackendState;
+use crate::capture_store::{CaptureSource, CaptureStore, StoredCapture};
+use crate::encoding::OutputFormat;
+use crate::screen_capture::{CaptureRegion, StitchOptions};
+use interprocess::local_socket::{prelude::*, ListenerOptions, Name, Stream};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tauri::{AppHandle, EventId, Listener, Manager};
+
+const CONTROL_FILE: &str = "control.json";
+
+/// Longest request line accepted
+const MAX_REQUEST_LEN: u64 = 1024 * 1024;
+
+/// App events forwarded to subscribed clients as `event` notifications
+pub const STREAMED_EVENTS: [&str; 6] = [
+    "screen-capture-ready",
+    crate::hotkeys::HOTKEY_ACTION_EVENT,
+    crate::capture_timer::COUNTDOWN_EVENT,
+    crate::history::HISTORY_CHANGED_EVENT,
+    crate::settings::SETTINGS_CHANGED_EVENT,
+    crate::image_input::OPEN_IN_EDITOR_EVENT,
+];
+
+// ============================================
+// Endpoint and Token
+// ============================================
+
+/// Where clients find the server: `<config dir>/JustSnap/control.json`
+pub fn default_control_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("JustSnap").join(CONTROL_FILE))
+}
+
+/// Contents of the control file
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct ControlInfo {
+    /// Unix domain socket path, or named pipe (`\\.\pipe\...`) on Windows
+    pub endpoint: String,
+    /// Secret to pass to `authenticate` before any other method
+    pub token: String,
+}
+
+/// Unix domain socket in the runtime dir (config dir when there is none)
+#[cfg(unix)]
+fn default_endpoint() -> Result<String, String> {
+    let dir = dirs::runtime_dir()
+        .or_else(dirs::config_dir)
+        .ok_or_else(|| "No directory for the control socket".to_string())?
+        .join("JustSnap");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join("control.sock").to_string_lossy().into_owned())
+}
+
+/// Named pipe private to the user name
+#[cfg(windows)]
+fn default_endpoint() -> Result<String, String> {
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    Ok(format!(r"\\.\pipe\justsnap-control-{}", user))
+}
+
+fn endpoint_name(endpoint: &str) -> std::io::Result<Name<'_>> {
+    #[cfg(unix)]
+    {
+        use interprocess::local_socket::GenericFilePath;
+        endpoint.to_fs_name::<GenericFilePath>()
+    }
+    #[cfg(windows)]
+    {
+        use interprocess::local_socket::GenericNamespaced;
+        endpoint
+            .trim_start_matches(r"\\.\pipe\")
+            .to_ns_name::<GenericNamespaced>()
+    }
+}
+
+/// 128 bits from the OS random number generator, as hex
+fn random_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Failed to generate a token: {}", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Keep the token of an earlier run so clients can cache it; write a new one otherwise
+fn publish(path: &PathBuf, endpoint: &str) -> Result<ControlInfo, String> {
+    let existing = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<ControlInfo>(&contents).ok())
+        .map(|info| info.token);
+    let token = match existing {
+        Some(token) => token,
+        None => random_token()?,
+    };
+    let info = ControlInfo {
+        endpoint: endpoint.to_string(),
+        token,
+    };
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_vec_pretty(&info).map_err(|e| e.to_string())?;
+    crate::auto_save::write_atomically(path, &json)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(info)
+}
+
+// ============================================
+// JSON-RPC Sessions
+// ============================================
+
+/// JSON-RPC error object
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    const PARSE_ERROR: i64 = -32700;
+    const INVALID_REQUEST: i64 = -32600;
+    const METHOD_NOT_FOUND: i64 = -32601;
+    const INVALID_PARAMS: i64 = -32602;
+    /// The operation itself failed (e.g. no such capture)
+    const FAILED: i64 = -32000;
+    const NOT_AUTHENTICATED: i64 = -32001;
+
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        Self::new(Self::FAILED, message)
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    /// Missing for notifications, which get no reply
+    id: Option<Value>,
+}
+
+/// Deserialize method parameters (missing parameters read as `{}`)
+fn params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(RpcError::INVALID_PARAMS, e.to_string()))
+}
+
+/// Events a client subscribed to (None until it subscribes; empty = every event).
+/// Shared with the event broadcast, so it has its own lock rather than the session's.
+#[derive(Default)]
+struct Subscriptions(Mutex<Option<Vec<String>>>);
+
+impl Subscriptions {
+    fn lock(&self) -> MutexGuard<'_, Option<Vec<String>>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn wants(&self, event: &str) -> bool {
+        self.lock()
+            .as_ref()
+            .is_some_and(|events| events.is_empty() || events.iter().any(|e| e == event))
+    }
+}
+
+/// State of one client connection
+#[derive(Default)]
+pub struct Session {
+    authenticated: bool,
+    subscriptions: Arc<Subscriptions>,
+}
+
+impl Session {
+    /// Handle one request line and return the response line (None for notifications).
+    /// `call` runs every method other than `authenticate` and `subscribe`.
+    pub fn handle_line(
+        &mut self,
+        line: &str,
+        token: &str,
+        call: &mut dyn FnMut(&str, Value) -> Result<Value, RpcError>,
+    ) -> Option<String> {
+        let (id, result) = match serde_json::from_str::<Value>(line) {
+            Err(e) => (
+                Value::Null,
+                Err(RpcError::new(RpcError::PARSE_ERROR, e.to_string())),
+            ),
+            Ok(value) => match serde_json::from_value::<RpcRequest>(value) {
+                Ok(request) if request.jsonrpc == "2.0" => {
+                    let id = request.id.clone();
+                    let result = self.handle(request, token, call);
+                    (id?, result)
+                }
+                _ => (
+                    Value::Null,
+                    Err(RpcError::new(
+                        RpcError::INVALID_REQUEST,
+                        "Expected a JSON-RPC 2.0 request object (batches are not supported)",
+                    )),
+                ),
+            },
+        };
+
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+        };
+        Some(response.to_string())
+    }
+
+    fn handle(
+        &mut self,
+        request: RpcRequest,
+        token: &str,
+        call: &mut dyn FnMut(&str, Value) -> Result<Value, RpcError>,
+    ) -> Result<Value, RpcError> {
+        #[derive(serde::Deserialize)]
+        struct Authenticate {
+            token: String,
+        }
+        #[derive(serde::Deserialize)]
+        struct Subscribe {
+            #[serde(default)]
+            events: Vec<String>,
+        }
+
+        if request.method == "authenticate" {
+            let given: Authenticate = params(request.params)?;
+            self.authenticated = given.token == token;
+            return match self.authenticated {
+                true => Ok(Value::Bool(true)),
+                false => Err(RpcError::new(RpcError::NOT_AUTHENTICATED, "Wrong token")),
+            };
+        }
+        if !self.authenticated {
+            return Err(RpcError::new(
+                RpcError::NOT_AUTHENTICATED,
+                "Call `authenticate` with the token from control.json first",
+            ));
+        }
+
+        match request.method.as_str() {
+            "subscribe" => {
+                let Subscribe { events } = params(request.params)?;
+                if let Some(unknown) = events
+                    .iter()
+                    .find(|e| !STREAMED_EVENTS.contains(&e.as_str()))
+                {
+                    return Err(RpcError::new(
+                        RpcError::INVALID_PARAMS,
+                        format!("Unknown event '{}'", unknown),
+                    ));
+                }
+                *self.subscriptions.lock() = Some(events.clone());
+                Ok(json!(events))
+            }
+            "unsubscribe" => {
+                *self.subscriptions.lock() = None;
+                Ok(Value::Null)
+            }
+            method => call(method, request.params),
+        }
+    }
+}
+
+// ============================================
+// Methods
+// ============================================
+
+/// A capture placed in the capture store
+#[derive(serde::Serialize)]
+struct CaptureResult {
+    capture_id: String,
+    width: u32,
+    height: u32,
+}
+
+#[derive(serde::Deserialize)]
+struct CaptureIdParams {
+    capture_id: String,
+}
+
+fn store_capture(app: &AppHandle, capture: StoredCapture) -> Value {
+    let (width, height) = capture.image.dimensions();
+    let capture_id = app.state::<CaptureStore>().insert(capture);
+    json!(CaptureResult {
+        capture_id,
+        width,
+        height
+    })
+}
+
+/// Run a method. Captures stay in the capture store and are referred to by id.
+fn call(app: &AppHandle, method: &str, raw: Value) -> Result<Value, RpcError> {
+    let backend = app.state::<CaptureBackendState>();
+    let store = app.state::<CaptureStore>();
+
+    match method {
+        "get_monitors" => Ok(json!(crate::screen_capture::get_all_monitors(
+            backend.backend()
+        )?)),
+        "capture_screen" => {
+            #[derive(serde::Deserialize)]
+            struct Params {
+                #[serde(flatten)]
+                region: CaptureRegion,
+                #[serde(default)]
+                options: StitchOptions,
+            }
+            let Params { region, options } = params(raw)?;
+
+            let image =
+                crate::screen_capture::capture_region_raw(backend.backend(), region, &options)?;
+            let monitors = crate::screen_capture::get_all_monitors(backend.backend())?;
+            let monitor = crate::screen_capture::monitor_at_point(
+                &monitors,
+                region.x + region.width / 2,
+                region.y + region.height / 2,
+            );
+            let capture = StoredCapture::new(image).with_source(CaptureSource {
+                monitor_name: monitor.map(|m| m.name.clone()),
+                scale_factor: monitor.map(|m| m.scale_factor),
+                ..CaptureSource::default()
+            });
+
+            // Remember the region for "repeat last region", like the Tauri command
+            app.state::<crate::recent_regions::RecentRegions>()
+                .remember(region, &monitors, capture.captured_at.timestamp_millis());
+            Ok(store_capture(app, capture))
+        }
+        "capture_full_screen" => {
+            let (image, monitor) =
+                crate::screen_capture::capture_primary_monitor_raw(backend.backend())?;
+            Ok(store_capture(
+                app,
+                StoredCapture::from_monitor(image, &monitor),
+            ))
+        }
+        "get_capture" => {
+            #[derive(serde::Deserialize)]
+            struct Params {
+                capture_id: String,
+                format: Option<OutputFormat>,
+            }
+            let Params { capture_id, format } = params(raw)?;
+            use base64::Engine;
+
+            let capture = store.get(&capture_id)?;
+            let format = format.unwrap_or(OutputFormat::Png { level: None });
+            let bytes = crate::encoding::encode_image(&capture.image, &format)?;
+            Ok(json!({
+                "format": format.extension(),
+                "data": base64::engine::general_purpose::STANDARD.encode(bytes),
+            }))
+        }
+        "save_capture" => {
+            #[derive(serde::Deserialize)]
+            struct Params {
+                capture_id: String,
+                /// None saves to the auto-save folder with the auto-save template
+                path: Option<String>,
+                include_metadata: Option<bool>,
+            }
+            let Params {
+                capture_id,
+                path,
+                include_metadata,
+            } = params(raw)?;
+            save_capture(app, &capture_id, path, include_metadata.unwrap_or(true))
+                .map(|path| json!({ "path": path }))
+                .map_err(RpcError::from)
+        }
+        "copy_capture_to_clipboard" => {
+            let CaptureIdParams { capture_id } = params(raw)?;
+            crate::clipboard::set_image(&store.get(&capture_id)?.image)?;
+            Ok(Value::Null)
+        }
+        "create_sticky_window" => {
+            let CaptureIdParams { capture_id } = params(raw)?;
+            let (width, height) = store.get(&capture_id)?.image.dimensions();
+            crate::image_input::open_sticky(app, &capture_id, width, height, 0)?;
+            Ok(Value::Null)
+        }
+        "release_capture" => {
+            let CaptureIdParams { capture_id } = params(raw)?;
+            Ok(Value::Bool(store.remove(&capture_id)))
+        }
+        _ => Err(RpcError::new(
+            RpcError::METHOD_NOT_FOUND,
+            format!("Unknown method '{}'", method),
+        )),
+    }
+}
+
+fn save_capture(
+    app: &AppHandle,
+    capture_id: &str,
+    path: Option<String>,
+    include_metadata: bool,
+) -> Result<String, String> {
+    use crate::export::{export_to_file, ExportMetadata};
+
+    let capture = app.state::<CaptureStore>().get(capture_id)?;
+
+    let Some(path) = path else {
+        use crate::auto_save::{save_with_template, SaveTemplate};
+
+        let settings = app.state::<crate::settings::SettingsStore>().get();
+        let directory = settings
+            .save_path
+            .ok_or_else(|| "No path given and no save folder set".to_string())?;
+        let path = save_with_template(
+            &capture.image,
+            &capture.captured_at,
+            &capture.source,
+            std::path::Path::new(&directory),
+            &SaveTemplate::parse(&settings.auto_save_template)?,
+            &OutputFormat::Png { level: None },
+        )?;
+        return Ok(path.to_string_lossy().into_owned());
+    };
+
+    let metadata = include_metadata
+        .then(|| ExportMetadata::from_capture(&capture.captured_at, &capture.source));
+    export_to_file(
+        &[&capture.image],
+        std::path::Path::new(&path),
+        metadata.as_ref(),
+    )?;
+    Ok(path)
+}
+
+// ============================================
+// Server
+// ============================================
+
+/// A connected client: its subscriptions and the channel its writer thread sends from
+struct Client {
+    id: u64,
+    subscriptions: Arc<Subscriptions>,
+    lines: Sender<String>,
+}
+
+/// Clients of a running server
+#[derive(Default)]
+struct Clients {
+    next_id: AtomicU64,
+    clients: Mutex<Vec<Client>>,
+}
+
+impl Clients {
+    fn lock(&self) -> MutexGuard<'_, Vec<Client>> {
+        self.clients.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Send an event notification to every client subscribed to it
+    fn broadcast(&self, event: &str, payload: &str) {
+        let payload = serde_json::from_str::<Value>(payload).unwrap_or(Value::Null);
+        let line = json!({
+            "jsonrpc": "2.0",
+            "method": "event",
+            "params": { "event": event, "payload": payload },
+        })
+        .to_string();
+
+        // Clients whose writer is gone have disconnected
+        self.lock().retain(|client| {
+            !client.subscriptions.wants(event) || client.lines.send(line.clone()).is_ok()
+        });
+    }
+}
+
+struct Running {
+    info: ControlInfo,
+    stop: Arc<AtomicBool>,
+    clients: Arc<Clients>,
+    listeners: Vec<EventId>,
+}
+
+/// Managed state: the control server, when enabled in the settings
+#[derive(Default)]
+pub struct ControlServer {
+    running: Mutex<Option<Running>>,
+}
+
+impl ControlServer {
+    fn lock(&self) -> MutexGuard<'_, Option<Running>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start or stop the server to match the `control_server` setting
+    pub fn set_enabled(&self, app: &AppHandle, enabled: bool) {
+        let result = match enabled {
+            true => self.start(app),
+            false => {
+                self.stop(app);
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("[Error] Failed to start the control server: {}", e);
+        }
+    }
+
+    fn start(&self, app: &AppHandle) -> Result<(), String> {
+        let mut running = self.lock();
+        if running.is_some() {
+            return Ok(());
+        }
+
+        let control_file =
+            default_control_file().ok_or_else(|| "No config directory".to_string())?;
+        let endpoint = default_endpoint()?;
+        let listener = ListenerOptions::new()
+            .name(endpoint_name(&endpoint).map_err(|e| e.to_string())?)
+            // A socket file left behind by a crash
+            .try_overwrite(true)
+            .create_sync()
+            .map_err(|e| format!("Failed to listen on {}: {}", endpoint, e))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(&endpoint, std::fs::Permissions::from_mode(0o600));
+        }
+        let info = publish(&control_file, &endpoint)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let clients = Arc::new(Clients::default());
+
+        let listeners = STREAMED_EVENTS
+            .iter()
+            .map(|event| {
+                let clients = clients.clone();
+                app.listen_any(*event, move |e| clients.broadcast(event, e.payload()))
+            })
+            .collect();
+
+        {
+            let (app, token, stop, clients) = (
+                app.clone(),
+                info.token.clone(),
+                stop.clone(),
+                clients.clone(),
+            );
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    match stream {
+                        Ok(stream) => serve_client(&app, stream, &token, &clients, &stop),
+                        Err(e) => eprintln!("[Error] Control connection failed: {}", e),
+                    }
+                }
+            });
+        }
+
+        if cfg!(debug_assertions) {
+            println!("[Control] Listening on {}", info.endpoint);
+        }
+        *running = Some(Running {
+            info,
+            stop,
+            clients,
+            listeners,
+        });
+        Ok(())
+    }
+
+    fn stop(&self, app: &AppHandle) {
+        let Some(running) = self.lock().take() else {
+            return;
+        };
+
+        for listener in running.listeners {
+            app.unlisten(listener);
+        }
+        // Disconnect event streams and wake the accept loop so it sees the flag; connected
+        // clients are dropped before their next request runs
+        running.clients.lock().clear();
+        running.stop.store(true, Ordering::SeqCst);
+        if let Ok(name) = endpoint_name(&running.info.endpoint) {
+            let _ = Stream::connect(name);
+        }
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(&running.info.endpoint);
+
+        if cfg!(debug_assertions) {
+            println!("[Control] Stopped");
+        }
+    }
+}
+
+/// Read requests from a client on its own thread; replies and events go out through a writer
+/// thread. Methods run without any lock held, so a slow capture does not hold up events.
+fn serve_client(
+    app: &AppHandle,
+    stream: Stream,
+    token: &str,
+    clients: &Arc<Clients>,
+    stop: &Arc<AtomicBool>,
+) {
+    let (reader, mut writer) = stream.split();
+    let (lines, outgoing) = channel::<String>();
+
+    std::thread::spawn(move || {
+        for line in outgoing {
+            if writer
+                .write_all(line.as_bytes())
+                .and_then(|_| writer.write_all(b"\n"))
+                .and_then(|_| writer.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let mut session = Session::default();
+    let id = clients.next_id.fetch_add(1, Ordering::SeqCst);
+    clients.lock().push(Client {
+        id,
+        subscriptions: session.subscriptions.clone(),
+        lines: lines.clone(),
+    });
+
+    let (app, token, clients, stop) = (
+        app.clone(),
+        token.to_string(),
+        clients.clone(),
+        stop.clone(),
+    );
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(reader.take(MAX_REQUEST_LEN));
+        let mut line = String::new();
+
+        while matches!(reader.read_line(&mut line), Ok(read) if read > 0) {
+            // Every line gets a fresh budget
+            reader.get_mut().set_limit(MAX_REQUEST_LEN);
+
+            // The server was switched off while this client stayed connected
+            if stop.load(Ordering::SeqCst) {
+                break;
+            }
+            let request = std::mem::take(&mut line);
+            if request.trim().is_empty() {
+                continue;
+            }
+            let response = session.handle_line(&request, &token, &mut |method, params| {
+                call(&app, method, params)
+            });
+            if let Some(response) = response {
+                if lines.send(response).is_err() {
+                    break;
+                }
+            }
+        }
+
+        clients.lock().retain(|client| client.id != id);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo(method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "echo" => Ok(params),
+            _ => Err(RpcError::new(RpcError::METHOD_NOT_FOUND, method)),
+        }
+    }
+
+    fn send(session: &mut Session, line: &str) -> Option<Value> {
+        session
+            .handle_line(line, "secret", &mut echo)
+            .map(|response| serde_json::from_str(&response).unwrap())
+    }
+
+    #[test]
+    fn methods_need_the_token_first() {
+        let mut session = Session::default();
+
+        let refused = send(&mut session, r#"{"jsonrpc":"2.0","id":1,"method":"echo"}"#).unwrap();
+        assert_eq!(refused["error"]["code"], RpcError::NOT_AUTHENTICATED);
+
+        let wrong =
+            r#"{"jsonrpc":"2.0","id":2,"method":"authenticate","params":{"token":"guess"}}"#;
+        assert_eq!(send(&mut session, wrong).unwrap()["error"]["code"], -32001);
+
+        let right =
+            r#"{"jsonrpc":"2.0","id":3,"method":"authenticate","params":{"token":"secret"}}"#;
+        assert_eq!(send(&mut session, right).unwrap()["result"], true);
+
+        let echoed = send(
+            &mut session,
+            r#"{"jsonrpc":"2.0","id":"a","method":"echo","params":{"x":1}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            echoed,
+            json!({ "jsonrpc": "2.0", "id": "a", "result": { "x": 1 } })
+        );
+
+        // Notifications get no response
+        assert_eq!(
+            send(&mut session, r#"{"jsonrpc":"2.0","method":"echo"}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn reports_malformed_requests() {
+        let mut session = Session::default();
+
+        assert_eq!(send(&mut session, "{").unwrap()["error"]["code"], -32700);
+        assert_eq!(
+            send(
+                &mut session,
+                r#"[{"jsonrpc":"2.0","id":1,"method":"echo"}]"#
+            )
+            .unwrap()["error"]["code"],
+            -32600
+        );
+        assert_eq!(
+            send(&mut session, r#"{"jsonrpc":"1.0","id":1,"method":"echo"}"#).unwrap()["error"]
+                ["code"],
+            -32600
+        );
+    }
+
+    #[test]
+    fn subscribes_to_known_events() {
+        let mut session = Session::default();
+        send(
+            &mut session,
+            r#"{"jsonrpc":"2.0","id":1,"method":"authenticate","params":{"token":"secret"}}"#,
+        );
+        assert!(!session.subscriptions.wants("screen-capture-ready"));
+
+        let unknown = r#"{"jsonrpc":"2.0","id":2,"method":"subscribe","params":{"events":["x"]}}"#;
+        assert_eq!(
+            send(&mut session, unknown).unwrap()["error"]["code"],
+            -32602
+        );
+
+        let one = json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "subscribe",
+            "params": { "events": ["history-changed"] },
+        });
+        send(&mut session, &one.to_string());
+        assert!(session.subscriptions.wants("history-changed"));
+        assert!(!session.subscriptions.wants("screen-capture-ready"));
+
+        send(
+            &mut session,
+            r#"{"jsonrpc":"2.0","id":4,"method":"subscribe"}"#,
+        );
+        assert!(session.subscriptions.wants("screen-capture-ready"));
+    }
+}
@@ -0,0 +1,236 @@
+// JustSnap - Image Encoding Module
+// Encodes captured RGBA images into the output format requested by the caller
+
+use image::{ExtendedColorType, ImageEncoder, RgbaImage};
+use std::io::Cursor;
+
+/// Output encoding for capture commands
+///
+/// Deserialized from `{ "format": "jpeg", "quality": 85 }` style objects.
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Uncompressed BMP (fastest, the historical default)
+    #[default]
+    Bmp,
+    Png {
+        /// zlib level 0-9 (0 = uncompressed); fast compression when omitted
+        #[serde(default)]
+        level: Option<u8>,
+    },
+    Jpeg {
+        /// Quality 1-100
+        #[serde(default = "default_jpeg_quality")]
+        quality: u8,
+    },
+    Webp {
+        #[serde(default)]
+        lossless: bool,
+        /// Quality 0-100, ignored when lossless
+        #[serde(default = "default_webp_quality")]
+        quality: f32,
+    },
+    Qoi,
+    /// Raw RGBA8 pixels prefixed by an 8-byte header: width and height as little-endian u32
+    Rgba,
+}
+
+impl OutputFormat {
+    /// Pick an output format from a file extension (e.g. "png", "JPG")
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "bmp" => Some(Self::Bmp),
+            "png" => Some(Self::Png { level: None }),
+            "jpg" | "jpeg" => Some(Self::Jpeg {
+                quality: default_jpeg_quality(),
+            }),
+            "webp" => Some(Self::Webp {
+                lossless: true,
+                quality: default_webp_quality(),
+            }),
+            "qoi" => Some(Self::Qoi),
+            "rgba" | "raw" => Some(Self::Rgba),
+            _ => None,
+        }
+    }
+
+    /// File extension for this format (without the dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Bmp => "bmp",
+            Self::Png { .. } => "png",
+            Self::Jpeg { .. } => "jpg",
+            Self::Webp { .. } => "webp",
+            Self::Qoi => "qoi",
+            Self::Rgba => "rgba",
+        }
+    }
+}
+
+pub fn default_jpeg_quality() -> u8 {
+    90
+}
+
+fn default_webp_quality() -> f32 {
+    80.0
+}
+
+/// Size of the width/height header written in front of raw RGBA output
+pub const RGBA_HEADER_LEN: usize = 8;
+
+/// Encode an RGBA image in the requested output format
+pub fn encode_image(image: &RgbaImage, format: &OutputFormat) -> Result<Vec<u8>, String> {
+    match format {
+        OutputFormat::Bmp => encode_as_bmp(image),
+        OutputFormat::Png { level } => encode_as_png(image, *level),
+        OutputFormat::Jpeg { quality } => encode_as_jpeg(image, *quality),
+        OutputFormat::Webp { lossless, quality } => Ok(encode_as_webp(image, *lossless, *quality)),
+        OutputFormat::Qoi => encode_as_qoi(image),
+        OutputFormat::Rgba => Ok(encode_as_raw_rgba(image)),
+    }
+}
+
+/// Encode an image as BMP bytes (uncompressed, faster than PNG)
+fn encode_as_bmp(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    use image::codecs::bmp::BmpEncoder;
+
+    let mut buffer = Cursor::new(Vec::new());
+
+    // Use BMP encoder for zero compression overhead
+    let encoder = BmpEncoder::new(&mut buffer);
+
+    encoder
+        .write_image(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            ExtendedColorType::Rgba8,
+        )
+        .map_err(|e| format!("Failed to encode image as BMP: {}", e))?;
+
+    Ok(buffer.into_inner())
+}
+
+fn encode_as_png(image: &RgbaImage, level: Option<u8>) -> Result<Vec<u8>, String> {
+    use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+
+    let compression = match level {
+        None => CompressionType::Fast,
+        Some(0) => CompressionType::Uncompressed,
+        Some(level) => CompressionType::Level(level.min(9)),
+    };
+
+    let mut buffer = Vec::new();
+    PngEncoder::new_with_quality(&mut buffer, compression, FilterType::Adaptive)
+        .write_image(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            ExtendedColorType::Rgba8,
+        )
+        .map_err(|e| format!("Failed to encode image as PNG: {}", e))?;
+
+    Ok(buffer)
+}
+
+fn encode_as_jpeg(image: &RgbaImage, quality: u8) -> Result<Vec<u8>, String> {
+    use image::codecs::jpeg::JpegEncoder;
+
+    // JPEG has no alpha channel
+    let rgb = image::DynamicImage::ImageRgba8(image.clone()).to_rgb8();
+
+    let mut buffer = Vec::new();
+    JpegEncoder::new_with_quality(&mut buffer, quality.clamp(1, 100))
+        .write_image(
+            rgb.as_raw(),
+            rgb.width(),
+            rgb.height(),
+            ExtendedColorType::Rgb8,
+        )
+        .map_err(|e| format!("Failed to encode image as JPEG: {}", e))?;
+
+    Ok(buffer)
+}
+
+fn encode_as_webp(image: &RgbaImage, lossless: bool, quality: f32) -> Vec<u8> {
+    let encoder = webp::Encoder::from_rgba(image.as_raw(), image.width(), image.height());
+
+    let encoded = if lossless {
+        encoder.encode_lossless()
+    } else {
+        encoder.encode(quality.clamp(0.0, 100.0))
+    };
+
+    encoded.to_vec()
+}
+
+fn encode_as_qoi(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    use image::codecs::qoi::QoiEncoder;
+
+    let mut buffer = Vec::new();
+    QoiEncoder::new(&mut buffer)
+        .write_image(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            ExtendedColorType::Rgba8,
+        )
+        .map_err(|e| format!("Failed to encode image as QOI: {}", e))?;
+
+    Ok(buffer)
+}
+
+fn encode_as_raw_rgba(image: &RgbaImage) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(RGBA_HEADER_LEN + image.as_raw().len());
+    buffer.extend_from_slice(&image.width().to_le_bytes());
+    buffer.extend_from_slice(&image.height().to_le_bytes());
+    buffer.extend_from_slice(image.as_raw());
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_format_deserializes_with_defaults() {
+        let format: OutputFormat = serde_json::from_str(r#"{ "format": "jpeg" }"#).unwrap();
+        assert!(matches!(format, OutputFormat::Jpeg { quality: 90 }));
+
+        let format: OutputFormat =
+            serde_json::from_str(r#"{ "format": "webp", "lossless": true }"#).unwrap();
+        assert!(matches!(format, OutputFormat::Webp { lossless: true, .. }));
+    }
+
+    #[test]
+    fn every_format_round_trips_dimensions() {
+        let image = RgbaImage::from_pixel(7, 3, image::Rgba([10, 20, 30, 255]));
+        let formats = [
+            OutputFormat::Bmp,
+            OutputFormat::Png { level: Some(6) },
+            OutputFormat::Jpeg { quality: 80 },
+            OutputFormat::Webp {
+                lossless: false,
+                quality: 75.0,
+            },
+            OutputFormat::Qoi,
+        ];
+
+        for format in &formats {
+            let bytes = encode_image(&image, format).unwrap();
+            let decoded = image::load_from_memory(&bytes).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (7, 3), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn raw_rgba_has_size_header() {
+        let image = RgbaImage::from_pixel(2, 1, image::Rgba([1, 2, 3, 4]));
+
+        let bytes = encode_image(&image, &OutputFormat::Rgba).unwrap();
+
+        assert_eq!(&bytes[..4], &2u32.to_le_bytes());
+        assert_eq!(&bytes[4..RGBA_HEADER_LEN], &1u32.to_le_bytes());
+        assert_eq!(&bytes[RGBA_HEADER_LEN..], &[1, 2, 3, 4, 1, 2, 3, 4]);
+    }
+}
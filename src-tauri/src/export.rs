@@ -0,0 +1,639 @@
+// JustSnap - Export Module
+// Writes captures as PNG, JPEG, WebP, TIFF, BMP, QOI, raw RGBA or PDF files, optionally recording
+// where they came from
+
+use crate::capture_store::CaptureSource;
+use crate::encoding::{default_jpeg_quality, encode_image, OutputFormat};
+use chrono::{DateTime, FixedOffset, Local};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::buffer::ConvertBuffer;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::{ImageEncoder, RgbImage, RgbaImage};
+use std::io::{Cursor, Write};
+use std::path::Path;
+use tiff::encoder::colortype::RGBA8;
+use tiff::encoder::TiffValue;
+use tiff::tags::{Tag, Type};
+
+/// Software name recorded in exported files
+const SOFTWARE: &str = "JustSnap";
+
+/// Pixels per inch of a display at scale factor 1
+const BASE_DPI: f64 = 96.0;
+
+/// File formats `export_images` can write
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Tiff,
+    Bmp,
+    Qoi,
+    /// Raw RGBA pixels behind a width/height header, as `OutputFormat::Rgba`
+    Rgba,
+    /// One image per page
+    Pdf,
+}
+
+impl ExportFormat {
+    /// Pick the format from a file extension (PNG when the path has none)
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension() {
+            Some(extension) => Self::from_extension(&extension.to_string_lossy()),
+            None => Ok(Self::Png),
+        }
+    }
+
+    /// Pick the format from an extension or name such as "png" or "JPG"
+    pub fn from_extension(extension: &str) -> Result<Self, String> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Ok(Self::Png),
+            "jpg" | "jpeg" => Ok(Self::Jpeg),
+            "webp" => Ok(Self::Webp),
+            "tif" | "tiff" => Ok(Self::Tiff),
+            "bmp" => Ok(Self::Bmp),
+            "qoi" => Ok(Self::Qoi),
+            "rgba" | "raw" => Ok(Self::Rgba),
+            "pdf" => Ok(Self::Pdf),
+            other => Err(format!(
+                "Unsupported export format '.{}' (use png, jpg, webp, tiff, bmp, qoi, raw or pdf)",
+                other
+            )),
+        }
+    }
+}
+
+/// Provenance recorded in exported files
+///
+/// Stored as EXIF (PNG, JPEG, WebP), as tags of the TIFF image itself or as the PDF document
+/// info. BMP, QOI and raw files carry no metadata.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ExportMetadata {
+    /// RFC 3339 timestamp
+    pub captured_at: Option<String>,
+    pub monitor_name: Option<String>,
+    pub window_title: Option<String>,
+    pub scale_factor: Option<f64>,
+}
+
+impl ExportMetadata {
+    pub fn from_capture(captured_at: &DateTime<Local>, source: &CaptureSource) -> Self {
+        Self {
+            captured_at: Some(captured_at.to_rfc3339()),
+            monitor_name: source.monitor_name.clone(),
+            window_title: source.window_title.clone(),
+            scale_factor: source.scale_factor,
+        }
+    }
+
+    fn captured_at(&self) -> Result<Option<DateTime<FixedOffset>>, String> {
+        self.captured_at
+            .as_deref()
+            .map(|time| {
+                DateTime::parse_from_rfc3339(time)
+                    .map_err(|e| format!("Invalid capture time '{}': {}", time, e))
+            })
+            .transpose()
+    }
+}
+
+/// Encode images in `format`. Only PDF holds more than one image.
+pub fn export_images(
+    images: &[&RgbaImage],
+    format: ExportFormat,
+    metadata: Option<&ExportMetadata>,
+) -> Result<Vec<u8>, String> {
+    if format == ExportFormat::Pdf {
+        return encode_pdf(images, metadata);
+    }
+
+    let image = match images {
+        [image] => *image,
+        [] => return Err("Nothing to export".to_string()),
+        _ => return Err("Only PDF can hold several images, export them one by one".to_string()),
+    };
+
+    let mut buffer = Vec::new();
+    match format {
+        ExportFormat::Png => image.write_with_encoder(with_exif(
+            PngEncoder::new_with_quality(&mut buffer, CompressionType::Fast, FilterType::Adaptive),
+            metadata,
+        )?),
+        ExportFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let rgb: RgbImage = image.convert();
+            rgb.write_with_encoder(with_exif(
+                JpegEncoder::new_with_quality(&mut buffer, default_jpeg_quality()),
+                metadata,
+            )?)
+        }
+        ExportFormat::Webp => {
+            image.write_with_encoder(with_exif(WebPEncoder::new_lossless(&mut buffer), metadata)?)
+        }
+        ExportFormat::Tiff => return encode_tiff(image, metadata),
+        ExportFormat::Bmp => return encode_image(image, &OutputFormat::Bmp),
+        ExportFormat::Qoi => return encode_image(image, &OutputFormat::Qoi),
+        ExportFormat::Rgba => return encode_image(image, &OutputFormat::Rgba),
+        ExportFormat::Pdf => unreachable!("handled above"),
+    }
+    .map_err(|e| format!("Failed to export image as {:?}: {}", format, e))?;
+
+    Ok(buffer)
+}
+
+/// Export images to `path`, choosing the format from its extension
+pub fn export_to_file(
+    images: &[&RgbaImage],
+    path: &Path,
+    metadata: Option<&ExportMetadata>,
+) -> Result<(), String> {
+    let bytes = export_images(images, ExportFormat::from_path(path)?, metadata)?;
+    crate::auto_save::write_atomically(path, &bytes)
+}
+
+/// Hand the metadata to an image encoder, which writes it as EXIF
+fn with_exif<E: ImageEncoder>(
+    mut encoder: E,
+    metadata: Option<&ExportMetadata>,
+) -> Result<E, String> {
+    if let Some(metadata) = metadata {
+        encoder
+            .set_exif_metadata(exif_block(metadata)?)
+            .map_err(|e| format!("Failed to add metadata: {}", e))?;
+    }
+    Ok(encoder)
+}
+
+// ============================================
+// EXIF
+// ============================================
+
+enum TagValue {
+    Short(Vec<u16>),
+    Rational(Vec<(u32, u32)>),
+    Ascii(String),
+}
+
+impl TagValue {
+    /// TIFF field type and value count
+    fn kind(&self) -> (u16, u32) {
+        match self {
+            TagValue::Short(values) => (3, values.len() as u32),
+            TagValue::Rational(values) => (5, values.len() as u32),
+            TagValue::Ascii(text) => (2, text.len() as u32 + 1),
+        }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            TagValue::Short(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            TagValue::Rational(values) => values
+                .iter()
+                .flat_map(|(num, den)| [num.to_le_bytes(), den.to_le_bytes()])
+                .flatten()
+                .collect(),
+            TagValue::Ascii(text) => {
+                let mut bytes = text.as_bytes().to_vec();
+                bytes.push(0);
+                bytes
+            }
+        }
+    }
+}
+
+/// Little-endian IFD placed at `offset` (from the start of the TIFF header), followed by the
+/// values that do not fit in an entry
+fn tiff_ifd(entries: &mut [(u16, TagValue)], offset: u32) -> Vec<u8> {
+    entries.sort_by_key(|(tag, _)| *tag);
+
+    let mut data_offset = offset + 2 + 12 * entries.len() as u32 + 4;
+    let mut ifd = Vec::new();
+    let mut data = Vec::new();
+
+    ifd.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    for (tag, value) in entries.iter() {
+        let (kind, count) = value.kind();
+        let mut bytes = value.bytes();
+
+        ifd.extend_from_slice(&tag.to_le_bytes());
+        ifd.extend_from_slice(&kind.to_le_bytes());
+        ifd.extend_from_slice(&count.to_le_bytes());
+        if bytes.len() <= 4 {
+            bytes.resize(4, 0);
+            ifd.extend_from_slice(&bytes);
+        } else {
+            ifd.extend_from_slice(&data_offset.to_le_bytes());
+            // Values start on a word boundary
+            if bytes.len() % 2 == 1 {
+                bytes.push(0);
+            }
+            data_offset += bytes.len() as u32;
+            data.extend_from_slice(&bytes);
+        }
+    }
+    // No further IFDs
+    ifd.extend_from_slice(&0u32.to_le_bytes());
+
+    ifd.extend_from_slice(&data);
+    ifd
+}
+
+/// EXIF tags for the metadata (description, monitor, software, time, resolution)
+fn metadata_tags(metadata: &ExportMetadata) -> Result<Vec<(u16, TagValue)>, String> {
+    let mut tags = vec![(0x0131, TagValue::Ascii(SOFTWARE.to_string()))];
+
+    if let Some(ref title) = metadata.window_title {
+        // ImageDescription
+        tags.push((0x010E, TagValue::Ascii(title.clone())));
+    }
+    if let Some(ref monitor) = metadata.monitor_name {
+        // Model: the display the capture was taken from
+        tags.push((0x0110, TagValue::Ascii(monitor.clone())));
+    }
+    if let Some(time) = metadata.captured_at()? {
+        tags.push((
+            0x0132,
+            TagValue::Ascii(time.format("%Y:%m:%d %H:%M:%S").to_string()),
+        ));
+    }
+    if let Some(scale) = metadata.scale_factor {
+        // Scale factor as resolution: 96 dpi at 100%
+        let dpi = ((BASE_DPI * scale) * 100.0).round() as u32;
+        tags.push((0x011A, TagValue::Rational(vec![(dpi, 100)])));
+        tags.push((0x011B, TagValue::Rational(vec![(dpi, 100)])));
+        tags.push((0x0128, TagValue::Short(vec![2])));
+    }
+
+    Ok(tags)
+}
+
+const TIFF_HEADER: [u8; 8] = [b'I', b'I', 42, 0, 8, 0, 0, 0];
+
+/// EXIF block (TIFF structure with IFD0 holding the metadata tags)
+fn exif_block(metadata: &ExportMetadata) -> Result<Vec<u8>, String> {
+    let mut exif = TIFF_HEADER.to_vec();
+    exif.extend_from_slice(&tiff_ifd(&mut metadata_tags(metadata)?, 8));
+    Ok(exif)
+}
+
+// ============================================
+// TIFF
+// ============================================
+
+/// Text tag in UTF-8. The EXIF block stores titles the same way; the tiff crate's own `str`
+/// value refuses anything but ASCII.
+struct TiffText<'a>(&'a str);
+
+impl TiffValue for TiffText<'_> {
+    const BYTE_LEN: u8 = 1;
+    const FIELD_TYPE: Type = Type::ASCII;
+
+    fn count(&self) -> usize {
+        self.0.len() + 1
+    }
+
+    fn data(&self) -> std::borrow::Cow<'_, [u8]> {
+        [self.0.as_bytes(), &[0]].concat().into()
+    }
+}
+
+/// TIFF holding the metadata tags in the image's own IFD
+fn encode_tiff(image: &RgbaImage, metadata: Option<&ExportMetadata>) -> Result<Vec<u8>, String> {
+    let error = |e: tiff::TiffError| format!("Failed to export image as Tiff: {}", e);
+    let mut buffer = Cursor::new(Vec::new());
+    let mut encoder = tiff::encoder::TiffEncoder::new(&mut buffer).map_err(error)?;
+    let mut tiff = encoder
+        .new_image::<RGBA8>(image.width(), image.height())
+        .map_err(error)?;
+
+    if let Some(metadata) = metadata {
+        for (tag, value) in metadata_tags(metadata)? {
+            // Replaces the encoder's default resolution tags
+            let tag = Tag::from_u16_exhaustive(tag);
+            let directory = tiff.encoder();
+            match value {
+                TagValue::Short(values) => directory.write_tag(tag, &values[..]),
+                TagValue::Rational(values) => {
+                    let values: Vec<_> = values
+                        .into_iter()
+                        .map(|(n, d)| tiff::encoder::Rational { n, d })
+                        .collect();
+                    directory.write_tag(tag, &values[..])
+                }
+                TagValue::Ascii(text) => directory.write_tag(tag, TiffText(&text)),
+            }
+            .map_err(error)?;
+        }
+    }
+
+    tiff.write_data(image.as_raw()).map_err(error)?;
+    Ok(buffer.into_inner())
+}
+
+// ============================================
+// PDF
+// ============================================
+
+fn zlib(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| format!("Failed to compress PDF image: {}", e))
+}
+
+/// PDF text string (UTF-16BE with byte order mark, hex encoded)
+fn pdf_text(text: &str) -> String {
+    let mut hex = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        hex.push_str(&format!("{:04X}", unit));
+    }
+    hex.push('>');
+    hex
+}
+
+fn pdf_stream(dictionary: &str, data: &[u8]) -> Vec<u8> {
+    let mut object = format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+    object.extend_from_slice(data);
+    object.extend_from_slice(b"\nendstream");
+    object
+}
+
+/// One page per image, sized so the image appears at its logical (unscaled) size
+fn encode_pdf(images: &[&RgbaImage], metadata: Option<&ExportMetadata>) -> Result<Vec<u8>, String> {
+    if images.is_empty() {
+        return Err("Nothing to export".to_string());
+    }
+    let scale = metadata
+        .and_then(|m| m.scale_factor)
+        .unwrap_or(1.0)
+        .max(0.1);
+
+    // Objects 1-3: catalog, page tree, document info; then page, content, image (+ alpha mask) per page
+    let mut objects: Vec<Vec<u8>> = vec![Vec::new(), Vec::new(), Vec::new()];
+    let mut page_ids = Vec::new();
+
+    for image in images {
+        let (width, height) = image.dimensions();
+        let page_width = width as f64 * 72.0 / (BASE_DPI * scale);
+        let page_height = height as f64 * 72.0 / (BASE_DPI * scale);
+
+        let page_id = objects.len() + 1;
+        let content_id = page_id + 1;
+        let image_id = page_id + 2;
+        let has_alpha = image.pixels().any(|pixel| pixel[3] < 255);
+        page_ids.push(page_id);
+
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                page_width, page_height, image_id, content_id
+            )
+            .into_bytes(),
+        );
+
+        let content = format!(
+            "q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q",
+            page_width, page_height
+        );
+        objects.push(pdf_stream("", content.as_bytes()));
+
+        let rgb: Vec<u8> = image
+            .pixels()
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect();
+        let mask = if has_alpha {
+            format!(" /SMask {} 0 R", image_id + 1)
+        } else {
+            String::new()
+        };
+        objects.push(pdf_stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+                 /BitsPerComponent 8 /Filter /FlateDecode{}",
+                width, height, mask
+            ),
+            &zlib(&rgb)?,
+        ));
+
+        if has_alpha {
+            let alpha: Vec<u8> = image.pixels().map(|pixel| pixel[3]).collect();
+            objects.push(pdf_stream(
+                &format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceGray \
+                     /BitsPerComponent 8 /Filter /FlateDecode",
+                    width, height
+                ),
+                &zlib(&alpha)?,
+            ));
+        }
+    }
+
+    let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+    objects[0] = b"<< /Type /Catalog /Pages 2 0 R >>".to_vec();
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        page_ids.len()
+    )
+    .into_bytes();
+
+    let mut info = format!(
+        "<< /Producer {} /Creator {}",
+        pdf_text(SOFTWARE),
+        pdf_text(SOFTWARE)
+    );
+    if let Some(metadata) = metadata {
+        if let Some(time) = metadata.captured_at()? {
+            let offset = time.format("%z").to_string();
+            info.push_str(&format!(
+                " /CreationDate (D:{}{}'{}')",
+                time.format("%Y%m%d%H%M%S"),
+                &offset[..3],
+                &offset[3..]
+            ));
+        }
+        if let Some(ref title) = metadata.window_title {
+            info.push_str(&format!(" /Title {}", pdf_text(title)));
+        }
+        if let Some(ref monitor) = metadata.monitor_name {
+            info.push_str(&format!(" /Subject {}", pdf_text(monitor)));
+        }
+        if let Some(scale) = metadata.scale_factor {
+            info.push_str(&format!(
+                " /Keywords {}",
+                pdf_text(&format!("scale_factor={}", scale))
+            ));
+        }
+    }
+    info.push_str(" >>");
+    objects[2] = info.into_bytes();
+
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_offset = pdf.len();
+    pdf.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 3 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        )
+        .as_bytes(),
+    );
+
+    Ok(pdf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> ExportMetadata {
+        ExportMetadata {
+            captured_at: Some("2024-03-09T14:05:07+01:00".to_string()),
+            monitor_name: Some("DELL U2720Q".to_string()),
+            window_title: Some("Résumé.docx".to_string()),
+            scale_factor: Some(1.5),
+        }
+    }
+
+    fn image() -> RgbaImage {
+        RgbaImage::from_fn(3, 2, |x, y| {
+            image::Rgba([x as u8 * 80, y as u8 * 100, 7, 255])
+        })
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    #[test]
+    fn picks_format_from_extension() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.JPEG")),
+            Ok(ExportFormat::Jpeg)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.tif")),
+            Ok(ExportFormat::Tiff)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a")),
+            Ok(ExportFormat::Png)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.raw")),
+            Ok(ExportFormat::Rgba)
+        );
+        assert!(ExportFormat::from_path(Path::new("a.gif")).is_err());
+    }
+
+    #[test]
+    fn image_formats_decode_with_metadata() {
+        let image = image();
+        let metadata = metadata();
+
+        for format in [
+            ExportFormat::Png,
+            ExportFormat::Jpeg,
+            ExportFormat::Webp,
+            ExportFormat::Tiff,
+            ExportFormat::Bmp,
+        ] {
+            let bytes = export_images(&[&image], format, Some(&metadata)).unwrap();
+            let decoded = image::load_from_memory(&bytes)
+                .unwrap_or_else(|e| panic!("{:?} did not decode: {}", format, e));
+            assert_eq!((decoded.width(), decoded.height()), (3, 2), "{:?}", format);
+        }
+
+        for (format, marker) in [
+            (ExportFormat::Png, &b"eXIfII*\0"[..]),
+            (ExportFormat::Jpeg, &b"Exif\0\0II*\0"[..]),
+            (ExportFormat::Webp, &b"EXIF"[..]),
+            (ExportFormat::Tiff, &b"II*\0"[..]),
+        ] {
+            let bytes = export_images(&[&image], format, Some(&metadata)).unwrap();
+            assert!(contains(&bytes, marker), "{:?}", format);
+            assert!(contains(&bytes, b"2024:03:09 14:05:07\0"), "{:?}", format);
+            assert!(contains(&bytes, b"DELL U2720Q\0"), "{:?}", format);
+            assert!(contains(&bytes, "Résumé.docx".as_bytes()), "{:?}", format);
+        }
+
+        let tiff = export_images(&[&image], ExportFormat::Tiff, Some(&metadata)).unwrap();
+        let decoded = image::load_from_memory(&tiff).unwrap().to_rgba8();
+        assert_eq!(decoded, image);
+        let mut decoder = tiff::decoder::Decoder::new(Cursor::new(&tiff)).unwrap();
+        assert_eq!(
+            decoder.get_tag_ascii_string(Tag::Software).unwrap(),
+            SOFTWARE
+        );
+        assert_eq!(
+            decoder.get_tag(Tag::XResolution).unwrap(),
+            tiff::decoder::ifd::Value::Rational(14400, 100)
+        );
+
+        let qoi = export_images(&[&image], ExportFormat::Qoi, Some(&metadata)).unwrap();
+        let decoded = image::load_from_memory(&qoi).unwrap().to_rgba8();
+        assert_eq!(decoded, image);
+
+        let raw = export_images(&[&image], ExportFormat::Rgba, None).unwrap();
+        assert_eq!(raw.len(), crate::encoding::RGBA_HEADER_LEN + 3 * 2 * 4);
+    }
+
+    #[test]
+    fn pdf_has_one_page_per_image() {
+        let opaque = image();
+        let mut transparent = image();
+        transparent.put_pixel(0, 0, image::Rgba([0, 0, 0, 0]));
+
+        let pdf = export_images(
+            &[&opaque, &transparent],
+            ExportFormat::Pdf,
+            Some(&metadata()),
+        )
+        .unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.contains("/Count 2"));
+        assert_eq!(text.matches("/Type /Page ").count(), 2);
+        assert_eq!(text.matches("/SMask").count(), 1);
+        assert!(text.contains("/CreationDate (D:20240309140507+01'00')"));
+        // 3 px at 1.5x scale = 2 logical px = 1.5 pt
+        assert!(text.contains("/MediaBox [0 0 1.50 1.00]"));
+
+        // Every xref entry points at its object
+        let xref = pdf
+            .windows(5)
+            .rposition(|window| window == b"xref\n")
+            .unwrap();
+        let table = String::from_utf8(pdf[xref..].to_vec()).unwrap();
+        for (index, line) in table.lines().skip(3).take(4).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj", index + 1).as_bytes()));
+        }
+
+        assert!(export_images(&[&opaque, &opaque], ExportFormat::Png, None).is_err());
+    }
+}
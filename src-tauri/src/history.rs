@@ -0,0 +1,602 @@
+// JustSnap - Capture History Module
+// Records every capture in a local SQLite database with a thumbnail, for search and reopening
+
+use crate::encoding::{encode_image, OutputFormat};
+use crate::settings::SettingsStore;
+use image::RgbaImage;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event broadcast after entries are added, changed or removed
+pub const HISTORY_CHANGED_EVENT: &str = "history-changed";
+
+const DATABASE_FILE: &str = "history.db";
+const IMAGES_DIR: &str = "images";
+
+/// Longest side of a thumbnail in pixels
+const THUMBNAIL_SIZE: u32 = 256;
+
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// Schema migrations, applied in order (tracked with `PRAGMA user_version`)
+const MIGRATIONS: &[&str] = &["CREATE TABLE captures (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        captured_at INTEGER NOT NULL,
+        width INTEGER NOT NULL,
+        height INTEGER NOT NULL,
+        region_x INTEGER,
+        region_y INTEGER,
+        region_width INTEGER,
+        region_height INTEGER,
+        monitor_name TEXT,
+        window_title TEXT,
+        app_name TEXT,
+        saved_path TEXT,
+        ocr_text TEXT,
+        thumbnail BLOB NOT NULL
+    );
+    CREATE INDEX captures_captured_at ON captures (captured_at);"];
+
+const ENTRY_COLUMNS: &str = "id, captured_at, width, height, region_x, region_y, region_width, \
+     region_height, monitor_name, window_title, app_name, saved_path, ocr_text";
+
+/// Captured area in virtual desktop pixels
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct HistoryRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// What is known about a capture when it is recorded
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct HistoryDetails {
+    pub region: Option<HistoryRegion>,
+    pub monitor_name: Option<String>,
+    pub window_title: Option<String>,
+    pub app_name: Option<String>,
+    pub saved_path: Option<String>,
+    pub ocr_text: Option<String>,
+}
+
+/// A recorded capture
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+    pub id: i64,
+    /// Unix time in milliseconds
+    pub captured_at: i64,
+    pub width: u32,
+    pub height: u32,
+    pub region: Option<HistoryRegion>,
+    pub monitor_name: Option<String>,
+    pub window_title: Option<String>,
+    pub app_name: Option<String>,
+    pub saved_path: Option<String>,
+    pub ocr_text: Option<String>,
+}
+
+impl HistoryEntry {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        let region = match (row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?) {
+            (Some(x), Some(y), Some(width), Some(height)) => Some(HistoryRegion {
+                x,
+                y,
+                width,
+                height,
+            }),
+            _ => None,
+        };
+
+        Ok(Self {
+            id: row.get(0)?,
+            captured_at: row.get(1)?,
+            width: row.get(2)?,
+            height: row.get(3)?,
+            region,
+            monitor_name: row.get(8)?,
+            window_title: row.get(9)?,
+            app_name: row.get(10)?,
+            saved_path: row.get(11)?,
+            ocr_text: row.get(12)?,
+        })
+    }
+}
+
+/// Filters for listing history, newest first
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Matched against OCR text, window title, app name, monitor and saved path
+    pub text: Option<String>,
+    /// Unix time in milliseconds (inclusive)
+    pub since: Option<i64>,
+    /// Unix time in milliseconds (exclusive)
+    pub until: Option<i64>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+/// Fields that can be filled in after a capture is recorded
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct HistoryUpdate {
+    pub saved_path: Option<String>,
+    pub ocr_text: Option<String>,
+}
+
+/// How much history to keep (None = no limit)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Retention {
+    pub max_entries: Option<u32>,
+    pub max_age_days: Option<u32>,
+}
+
+/// Managed capture history: entries in SQLite, full images as PNG files next to it
+pub struct CaptureHistory {
+    conn: Mutex<Connection>,
+    images_dir: PathBuf,
+}
+
+impl CaptureHistory {
+    /// Open (or create) the history stored in `dir`
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let images_dir = dir.join(IMAGES_DIR);
+        std::fs::create_dir_all(&images_dir)
+            .map_err(|e| format!("Failed to create history directory: {}", e))?;
+
+        let conn = Connection::open(dir.join(DATABASE_FILE))
+            .map_err(|e| format!("Failed to open history database: {}", e))?;
+        Self::with_connection(conn, images_dir)
+    }
+
+    /// Open the history in `dir`, or keep it for this session only if that fails
+    pub fn open_or_temporary(dir: Option<PathBuf>) -> Self {
+        let opened = match dir {
+            Some(ref dir) => Self::open(dir),
+            None => Err("No data directory".to_string()),
+        };
+
+        opened.unwrap_or_else(|e| {
+            eprintln!("[Error] Capture history not saved: {}", e);
+
+            let images_dir = std::env::temp_dir()
+                .join(format!("justsnap-history-{}", std::process::id()))
+                .join(IMAGES_DIR);
+            let _ = std::fs::create_dir_all(&images_dir);
+            let conn = Connection::open_in_memory().expect("in-memory SQLite database");
+            Self::with_connection(conn, images_dir).expect("in-memory history schema")
+        })
+    }
+
+    fn with_connection(conn: Connection, images_dir: PathBuf) -> Result<Self, String> {
+        migrate(&conn).map_err(|e| format!("Failed to prepare history database: {}", e))?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            images_dir,
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn image_path(&self, id: i64) -> PathBuf {
+        self.images_dir.join(format!("{}.png", id))
+    }
+
+    /// Record a capture, keeping its full image and a thumbnail
+    pub fn record(
+        &self,
+        image: &RgbaImage,
+        captured_at: i64,
+        details: HistoryDetails,
+    ) -> Result<HistoryEntry, String> {
+        // Encode before taking the lock
+        let png = encode_image(image, &OutputFormat::Png { level: None })?;
+        let thumbnail = encode_thumbnail(image)?;
+        let region = details.region;
+
+        let id = {
+            let conn = self.lock();
+            conn.execute(
+                "INSERT INTO captures (captured_at, width, height, region_x, region_y, region_width,
+                     region_height, monitor_name, window_title, app_name, saved_path, ocr_text, thumbnail)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    captured_at,
+                    image.width(),
+                    image.height(),
+                    region.map(|r| r.x),
+                    region.map(|r| r.y),
+                    region.map(|r| r.width),
+                    region.map(|r| r.height),
+                    details.monitor_name,
+                    details.window_title,
+                    details.app_name,
+                    details.saved_path,
+                    details.ocr_text,
+                    thumbnail,
+                ],
+            )
+            .map_err(|e| format!("Failed to record capture: {}", e))?;
+            conn.last_insert_rowid()
+        };
+
+        if let Err(e) = crate::auto_save::write_atomically(&self.image_path(id), &png) {
+            let _ = self.delete(&[id]);
+            return Err(e);
+        }
+
+        if cfg!(debug_assertions) {
+            println!("[History] Recorded capture {}", id);
+        }
+
+        self.get(id)
+    }
+
+    pub fn get(&self, id: i64) -> Result<HistoryEntry, String> {
+        self.lock()
+            .query_row(
+                &format!("SELECT {} FROM captures WHERE id = ?1", ENTRY_COLUMNS),
+                [id],
+                HistoryEntry::from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read history: {}", e))?
+            .ok_or_else(|| format!("History entry {} not found", id))
+    }
+
+    /// Entries matching the query, newest first
+    pub fn list(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>, String> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+
+        if let Some(text) = query
+            .text
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+        {
+            let pattern = format!(
+                "%{}%",
+                text.replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_")
+            );
+            let columns = [
+                "ocr_text",
+                "window_title",
+                "app_name",
+                "monitor_name",
+                "saved_path",
+            ];
+            let matches: Vec<String> = columns
+                .iter()
+                .map(|column| format!("{} LIKE ? ESCAPE '\\'", column))
+                .collect();
+            conditions.push(format!("({})", matches.join(" OR ")));
+            values.extend(columns.iter().map(|_| Value::Text(pattern.clone())));
+        }
+        if let Some(since) = query.since {
+            conditions.push("captured_at >= ?".to_string());
+            values.push(Value::Integer(since));
+        }
+        if let Some(until) = query.until {
+            conditions.push("captured_at < ?".to_string());
+            values.push(Value::Integer(until));
+        }
+
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        values.push(Value::Integer(
+            query.limit.unwrap_or(DEFAULT_PAGE_SIZE) as i64
+        ));
+        values.push(Value::Integer(query.offset.unwrap_or(0) as i64));
+
+        let conn = self.lock();
+        let mut statement = conn
+            .prepare(&format!(
+                "SELECT {} FROM captures {} ORDER BY captured_at DESC, id DESC LIMIT ? OFFSET ?",
+                ENTRY_COLUMNS, filter
+            ))
+            .map_err(|e| format!("Failed to search history: {}", e))?;
+        let entries = statement
+            .query_map(params_from_iter(values), HistoryEntry::from_row)
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to search history: {}", e))?;
+
+        Ok(entries)
+    }
+
+    /// JPEG thumbnail of an entry
+    pub fn thumbnail(&self, id: i64) -> Result<Vec<u8>, String> {
+        self.lock()
+            .query_row(
+                "SELECT thumbnail FROM captures WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read history: {}", e))?
+            .ok_or_else(|| format!("History entry {} not found", id))
+    }
+
+    /// Full image of an entry
+    pub fn load_image(&self, id: i64) -> Result<RgbaImage, String> {
+        let path = self.image_path(id);
+        image::open(&path)
+            .map(|image| image.to_rgba8())
+            .map_err(|e| format!("Failed to load history image {}: {}", path.display(), e))
+    }
+
+    /// Fill in the saved path or OCR text of an entry
+    pub fn update(&self, id: i64, update: HistoryUpdate) -> Result<HistoryEntry, String> {
+        self.lock()
+            .execute(
+                "UPDATE captures SET saved_path = COALESCE(?2, saved_path),
+                     ocr_text = COALESCE(?3, ocr_text) WHERE id = ?1",
+                params![id, update.saved_path, update.ocr_text],
+            )
+            .map_err(|e| format!("Failed to update history: {}", e))?;
+        self.get(id)
+    }
+
+    /// Delete entries and their images, returning how many were removed
+    pub fn delete(&self, ids: &[i64]) -> Result<usize, String> {
+        let mut removed = 0;
+        {
+            let conn = self.lock();
+            for id in ids {
+                removed += conn
+                    .execute("DELETE FROM captures WHERE id = ?1", [id])
+                    .map_err(|e| format!("Failed to delete history entry: {}", e))?;
+            }
+        }
+
+        for id in ids {
+            let _ = std::fs::remove_file(self.image_path(*id));
+        }
+        Ok(removed)
+    }
+
+    /// Drop entries beyond the retention limits, returning how many were removed
+    pub fn apply_retention(&self, retention: Retention, now: i64) -> Result<usize, String> {
+        let mut expired: Vec<i64> = Vec::new();
+        {
+            let conn = self.lock();
+            let mut collect = |sql: &str, value: i64| -> rusqlite::Result<()> {
+                let mut statement = conn.prepare(sql)?;
+                let ids = statement.query_map([value], |row| row.get::<_, i64>(0))?;
+                for id in ids {
+                    expired.push(id?);
+                }
+                Ok(())
+            };
+
+            if let Some(days) = retention.max_age_days {
+                let cutoff = now - i64::from(days) * 24 * 60 * 60 * 1000;
+                collect("SELECT id FROM captures WHERE captured_at < ?1", cutoff)
+                    .map_err(|e| format!("Failed to apply history retention: {}", e))?;
+            }
+            if let Some(max) = retention.max_entries {
+                collect(
+                    "SELECT id FROM captures ORDER BY captured_at DESC, id DESC LIMIT -1 OFFSET ?1",
+                    i64::from(max),
+                )
+                .map_err(|e| format!("Failed to apply history retention: {}", e))?;
+            }
+        }
+
+        expired.sort_unstable();
+        expired.dedup();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        if cfg!(debug_assertions) {
+            println!("[History] Removing {} expired captures", expired.len());
+        }
+        self.delete(&expired)
+    }
+}
+
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch(&format!(
+            "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+            migration,
+            index + 1
+        ))?;
+    }
+    Ok(())
+}
+
+fn encode_thumbnail(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    let (width, height) = image.dimensions();
+    let scale = (THUMBNAIL_SIZE as f64 / width.max(height).max(1) as f64).min(1.0);
+    let thumbnail = image::imageops::thumbnail(
+        image,
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    );
+
+    encode_image(&thumbnail, &OutputFormat::Jpeg { quality: 80 })
+}
+
+/// Record a capture, apply the retention settings and let every window know
+pub fn record_capture(
+    app: &AppHandle,
+    image: &RgbaImage,
+    captured_at: i64,
+    details: HistoryDetails,
+) -> Result<HistoryEntry, String> {
+    let history = app.state::<CaptureHistory>();
+    let entry = history.record(image, captured_at, details)?;
+
+    let retention = app.state::<SettingsStore>().get().history_retention();
+    history.apply_retention(retention, chrono::Utc::now().timestamp_millis())?;
+
+    let _ = app.emit(HISTORY_CHANGED_EVENT, ());
+    Ok(entry)
+}
+
+/// Default location: `<data dir>/JustSnap/history`
+pub fn default_history_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("JustSnap").join("history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60 * 1000;
+
+    /// History in a directory removed when the returned `TempDir` is dropped
+    fn temp_history() -> (CaptureHistory, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        (CaptureHistory::open(dir.path()).unwrap(), dir)
+    }
+
+    fn details(title: &str, ocr: &str) -> HistoryDetails {
+        HistoryDetails {
+            window_title: Some(title.to_string()),
+            ocr_text: Some(ocr.to_string()),
+            ..HistoryDetails::default()
+        }
+    }
+
+    #[test]
+    fn records_and_reopens_captures() {
+        let (history, _dir) = temp_history();
+        let image = RgbaImage::from_pixel(600, 300, image::Rgba([10, 20, 30, 255]));
+        let region = HistoryRegion {
+            x: -100,
+            y: 5,
+            width: 600,
+            height: 300,
+        };
+
+        let entry = history
+            .record(
+                &image,
+                1_000,
+                HistoryDetails {
+                    region: Some(region),
+                    ..details("Terminal", "")
+                },
+            )
+            .unwrap();
+
+        assert_eq!(entry.region, Some(region));
+        assert_eq!(history.load_image(entry.id).unwrap(), image);
+        let thumbnail = image::load_from_memory(&history.thumbnail(entry.id).unwrap()).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (256, 128));
+
+        let updated = history
+            .update(
+                entry.id,
+                HistoryUpdate {
+                    saved_path: Some("/tmp/shot.png".to_string()),
+                    ocr_text: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(updated.saved_path.as_deref(), Some("/tmp/shot.png"));
+        assert_eq!(updated.window_title.as_deref(), Some("Terminal"));
+
+        assert_eq!(history.delete(&[entry.id]).unwrap(), 1);
+        assert!(history.get(entry.id).is_err());
+        assert!(history.load_image(entry.id).is_err());
+    }
+
+    #[test]
+    fn searches_by_text_and_date() {
+        let (history, _dir) = temp_history();
+        let image = RgbaImage::new(4, 4);
+        history
+            .record(&image, DAY, details("Invoice", "total 100%"))
+            .unwrap();
+        history
+            .record(&image, 2 * DAY, details("Chat", "lunch at noon"))
+            .unwrap();
+        history
+            .record(&image, 3 * DAY, details("Browser", "invoice paid"))
+            .unwrap();
+
+        let titles = |query: HistoryQuery| -> Vec<String> {
+            history
+                .list(&query)
+                .unwrap()
+                .into_iter()
+                .filter_map(|entry| entry.window_title)
+                .collect()
+        };
+
+        assert_eq!(
+            titles(HistoryQuery::default()),
+            ["Browser", "Chat", "Invoice"]
+        );
+        let search = |text: &str| HistoryQuery {
+            text: Some(text.to_string()),
+            ..HistoryQuery::default()
+        };
+        assert_eq!(titles(search("INVOICE")), ["Browser", "Invoice"]);
+        // LIKE wildcards are matched literally
+        assert_eq!(titles(search("100%")), ["Invoice"]);
+        assert!(titles(search("_")).is_empty());
+        assert_eq!(
+            titles(HistoryQuery {
+                since: Some(2 * DAY),
+                until: Some(3 * DAY),
+                ..HistoryQuery::default()
+            }),
+            ["Chat"]
+        );
+    }
+
+    #[test]
+    fn retention_limits_count_and_age() {
+        let (history, _dir) = temp_history();
+        let image = RgbaImage::new(2, 2);
+        for day in 1..=5 {
+            history
+                .record(&image, day * DAY, HistoryDetails::default())
+                .unwrap();
+        }
+
+        let now = 5 * DAY + 1;
+        let by_count = Retention {
+            max_entries: Some(4),
+            max_age_days: None,
+        };
+        assert_eq!(history.apply_retention(by_count, now).unwrap(), 1);
+
+        let by_age = Retention {
+            max_entries: Some(4),
+            max_age_days: Some(2),
+        };
+        assert_eq!(history.apply_retention(by_age, now).unwrap(), 2);
+        assert_eq!(
+            history.apply_retention(Retention::default(), now).unwrap(),
+            0
+        );
+
+        let kept: Vec<i64> = history
+            .list(&HistoryQuery::default())
+            .unwrap()
+            .iter()
+            .map(|entry| entry.captured_at)
+            .collect();
+        assert_eq!(kept, [5 * DAY, 4 * DAY]);
+    }
+}
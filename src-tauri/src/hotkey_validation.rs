@@ -0,0 +1,254 @@
+// JustSnap - Hotkey Validation Module
+// Normalises user-entered shortcuts and explains why a binding is refused
+
+use crate::hotkeys::HotkeyAction;
+use std::fmt;
+use tauri_plugin_global_shortcut::Shortcut;
+
+/// Shortcuts owned by the OS or by editing commands every application relies on
+const RESERVED_SHORTCUTS: &[&str] = &[
+    // System
+    "Alt+Tab",
+    "Alt+F4",
+    "Ctrl+Alt+Delete",
+    "Ctrl+Shift+Escape",
+    "Ctrl+Escape",
+    "Super+L",
+    "Super+D",
+    "Super+Tab",
+    // Editing
+    "Ctrl+A",
+    "Ctrl+C",
+    "Ctrl+S",
+    "Ctrl+V",
+    "Ctrl+X",
+    "Ctrl+Y",
+    "Ctrl+Z",
+];
+
+/// Canonical modifier names, in the order they are written out
+const MODIFIER_ORDER: [&str; 4] = ["Ctrl", "Alt", "Shift", "Super"];
+
+/// Why a hotkey was refused
+///
+/// Serialized as `{ "kind": "duplicate", "shortcut": "Ctrl+Shift+S", "action": "capture" }`.
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HotkeyError {
+    /// No key was given
+    MissingKey,
+    /// Only modifier keys were pressed
+    ModifierOnly,
+    UnknownModifier {
+        modifier: String,
+    },
+    UnknownKey {
+        key: String,
+    },
+    /// A plain key would swallow normal typing (only function keys may go without a modifier)
+    NoModifier {
+        key: String,
+    },
+    /// Owned by the OS or by common editing commands
+    Reserved {
+        shortcut: String,
+    },
+    /// Already bound to another JustSnap action
+    Duplicate {
+        shortcut: String,
+        action: HotkeyAction,
+    },
+    /// The OS or another application refused the shortcut
+    RegistrationFailed {
+        shortcut: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for HotkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HotkeyError::MissingKey => write!(f, "No key given"),
+            HotkeyError::ModifierOnly => write!(f, "A shortcut needs a key besides modifiers"),
+            HotkeyError::UnknownModifier { modifier } => {
+                write!(f, "Unknown modifier '{}'", modifier)
+            }
+            HotkeyError::UnknownKey { key } => write!(f, "Unsupported key '{}'", key),
+            HotkeyError::NoModifier { key } => {
+                write!(
+                    f,
+                    "'{}' needs at least one modifier (Ctrl, Alt, Shift, Super)",
+                    key
+                )
+            }
+            HotkeyError::Reserved { shortcut } => {
+                write!(f, "{} is reserved by the system", shortcut)
+            }
+            HotkeyError::Duplicate { shortcut, action } => {
+                write!(f, "{} is already used by {}", shortcut, action.label())
+            }
+            HotkeyError::RegistrationFailed { shortcut, message } => {
+                write!(f, "Could not register {}: {}", shortcut, message)
+            }
+        }
+    }
+}
+
+/// A hotkey in canonical form (e.g. "Ctrl+Shift+S")
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct NormalizedHotkey {
+    pub shortcut: String,
+    pub modifiers: Vec<String>,
+    pub key: String,
+    #[serde(skip)]
+    pub parsed: Shortcut,
+}
+
+/// Canonical name of a modifier (Ctrl/Control/CmdOrCtrl, Super/Meta/Win/Cmd, Alt/Option)
+pub fn normalize_modifier(name: &str) -> Option<&'static str> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => Some("Ctrl"),
+        "cmdorctrl" | "cmdorcontrol" | "commandorctrl" | "commandorcontrol" => {
+            if cfg!(target_os = "macos") {
+                Some("Super")
+            } else {
+                Some("Ctrl")
+            }
+        }
+        "alt" | "option" => Some("Alt"),
+        "shift" => Some("Shift"),
+        "super" | "meta" | "win" | "windows" | "cmd" | "command" => Some("Super"),
+        _ => None,
+    }
+}
+
+/// Keys allowed without a modifier: function keys (F1-F24), Print Screen and Pause
+fn allows_bare_key(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    let function_key = key
+        .strip_prefix('F')
+        .and_then(|number| number.parse::<u8>().ok())
+        .is_some_and(|number| (1..=24).contains(&number));
+
+    function_key || matches!(key.as_str(), "PRINTSCREEN" | "PAUSE" | "PAUSEBREAK")
+}
+
+/// Normalise modifier names and key, and reject combinations that cannot or should not be bound
+pub fn normalize_hotkey(modifiers: &[String], key: &str) -> Result<NormalizedHotkey, HotkeyError> {
+    let mut canonical = Vec::new();
+    for modifier in modifiers {
+        let name = normalize_modifier(modifier).ok_or_else(|| HotkeyError::UnknownModifier {
+            modifier: modifier.clone(),
+        })?;
+        if !canonical.contains(&name) {
+            canonical.push(name);
+        }
+    }
+    canonical.sort_by_key(|name| MODIFIER_ORDER.iter().position(|m| m == name));
+
+    // Browsers report the space bar as " "
+    let key = if key == " " { "Space" } else { key.trim() };
+    if key.is_empty() {
+        return Err(if canonical.is_empty() {
+            HotkeyError::MissingKey
+        } else {
+            HotkeyError::ModifierOnly
+        });
+    }
+    if normalize_modifier(key).is_some() {
+        return Err(HotkeyError::ModifierOnly);
+    }
+    let key = if key.chars().count() == 1 {
+        key.to_uppercase()
+    } else {
+        key.to_string()
+    };
+
+    let mut parts: Vec<&str> = canonical.clone();
+    parts.push(&key);
+    let shortcut = parts.join("+");
+
+    let parsed: Shortcut = shortcut
+        .parse()
+        .map_err(|_| HotkeyError::UnknownKey { key: key.clone() })?;
+
+    if canonical.is_empty() && !allows_bare_key(&key) {
+        return Err(HotkeyError::NoModifier { key });
+    }
+
+    let reserved = RESERVED_SHORTCUTS
+        .iter()
+        .filter_map(|reserved| reserved.parse::<Shortcut>().ok())
+        .any(|reserved| reserved == parsed);
+    if reserved {
+        return Err(HotkeyError::Reserved { shortcut });
+    }
+
+    Ok(NormalizedHotkey {
+        shortcut,
+        modifiers: canonical.into_iter().map(str::to_string).collect(),
+        key,
+        parsed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(modifiers: &[&str], key: &str) -> Result<NormalizedHotkey, HotkeyError> {
+        let modifiers: Vec<String> = modifiers.iter().map(|m| m.to_string()).collect();
+        normalize_hotkey(&modifiers, key)
+    }
+
+    #[test]
+    fn modifier_aliases_are_normalized() {
+        let hotkey = normalize(&["Shift", "Control", "Win"], "s").unwrap();
+        assert_eq!(hotkey.shortcut, "Ctrl+Shift+Super+S");
+
+        let hotkey = normalize(&["Meta", "Ctrl", "ctrl"], "F5").unwrap();
+        assert_eq!(hotkey.modifiers, vec!["Ctrl", "Super"]);
+    }
+
+    #[test]
+    fn rejects_incomplete_shortcuts() {
+        assert_eq!(normalize(&[], ""), Err(HotkeyError::MissingKey));
+        assert_eq!(
+            normalize(&["Ctrl"], "Shift"),
+            Err(HotkeyError::ModifierOnly)
+        );
+        assert_eq!(
+            normalize(&["Hyper"], "S"),
+            Err(HotkeyError::UnknownModifier {
+                modifier: "Hyper".to_string()
+            })
+        );
+        assert_eq!(
+            normalize(&["Ctrl"], "!"),
+            Err(HotkeyError::UnknownKey {
+                key: "!".to_string()
+            })
+        );
+        assert_eq!(
+            normalize(&[], "a"),
+            Err(HotkeyError::NoModifier {
+                key: "A".to_string()
+            })
+        );
+        assert!(normalize(&[], "PrintScreen").is_ok());
+    }
+
+    #[test]
+    fn rejects_reserved_shortcuts() {
+        assert_eq!(
+            normalize(&["Control"], "c"),
+            Err(HotkeyError::Reserved {
+                shortcut: "Ctrl+C".to_string()
+            })
+        );
+        assert!(matches!(
+            normalize(&["Alt", "Ctrl"], "Delete"),
+            Err(HotkeyError::Reserved { .. })
+        ));
+    }
+}
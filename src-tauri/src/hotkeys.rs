@@ -2,34 +2,181 @@
 // Handles registering and listening for global keyboard shortcuts
 
 // use image::EncodableLayout; // Use simple bytes for now
+use crate::capture_backend::CaptureBackendState;
+use crate::capture_store::{CaptureStore, StoredCapture};
+use crate::history::{record_capture, HistoryDetails, HistoryRegion};
+use crate::hotkey_validation::{normalize_hotkey, HotkeyError, NormalizedHotkey};
+use crate::screen_capture::MonitorInfo;
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
 use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
+/// Event emitted after a hotkey action runs, for the parts the frontend carries out
+pub const HOTKEY_ACTION_EVENT: &str = "hotkey-action";
+
+/// Actions a global hotkey can be bound to
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Region capture: open the snipping overlay on the monitor under the cursor
+    Capture,
+    FullScreen,
+    WindowCapture,
+    RepeatRegion,
+    OpenLastSticky,
+    Ocr,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 6] = [
+        HotkeyAction::Capture,
+        HotkeyAction::FullScreen,
+        HotkeyAction::WindowCapture,
+        HotkeyAction::RepeatRegion,
+        HotkeyAction::OpenLastSticky,
+        HotkeyAction::Ocr,
+    ];
+
+    /// Human readable name used in conflict messages
+    pub fn label(&self) -> &'static str {
+        match self {
+            HotkeyAction::Capture => "Region capture",
+            HotkeyAction::FullScreen => "Full-screen capture",
+            HotkeyAction::WindowCapture => "Window capture",
+            HotkeyAction::RepeatRegion => "Repeat last region",
+            HotkeyAction::OpenLastSticky => "Open last sticky",
+            HotkeyAction::Ocr => "OCR selection",
+        }
+    }
+}
+
 pub struct Hotkey {
     pub key: String,
     pub modifiers: Vec<String>,
+    /// Count down this many seconds before capturing (timed capture)
+    pub delay_secs: Option<u32>,
 }
 
-/// Register global hotkey (Ctrl+Shift+S by default)
-pub fn register_global_hotkey(app: &AppHandle, hotkey: Hotkey) -> Result<(), String> {
-    // Build shortcut string (e.g., "Ctrl+Shift+S")
-    let mut shortcut_str = String::new();
+impl Hotkey {
+    /// Split a shortcut string (e.g., "Ctrl+Shift+S") into modifiers and key
+    pub fn from_shortcut_string(shortcut: &str) -> Self {
+        let mut parts: Vec<String> = shortcut.split('+').map(str::to_string).collect();
+        let key = parts.pop().unwrap_or_default();
 
-    for modifier in &hotkey.modifiers {
-        shortcut_str.push_str(modifier);
-        shortcut_str.push('+');
+        Self {
+            key,
+            modifiers: parts,
+            delay_secs: None,
+        }
     }
-    shortcut_str.push_str(&hotkey.key);
 
-    if cfg!(debug_assertions) {
-        println!("[Hotkey] Registering: {}", shortcut_str);
+    /// Build shortcut string (e.g., "Ctrl+Shift+S")
+    pub fn to_shortcut_string(&self) -> String {
+        let mut shortcut_str = String::new();
+
+        for modifier in &self.modifiers {
+            shortcut_str.push_str(modifier);
+            shortcut_str.push('+');
+        }
+        shortcut_str.push_str(&self.key);
+
+        shortcut_str
+    }
+}
+
+/// A shortcut currently bound to an action
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct HotkeyBinding {
+    pub action: HotkeyAction,
+    pub shortcut: String,
+    pub delay_secs: Option<u32>,
+}
+
+/// Outcome of registering one action's hotkey
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct HotkeyRegistration {
+    pub action: HotkeyAction,
+    pub shortcut: String,
+    /// Why the shortcut could not be bound (None on success)
+    pub error: Option<HotkeyError>,
+}
+
+struct RegisteredHotkey {
+    shortcut: Shortcut,
+    binding: HotkeyBinding,
+}
+
+/// Managed registry of the shortcut bound to each action
+#[derive(Default)]
+pub struct HotkeyRegistry {
+    bindings: Mutex<HashMap<HotkeyAction, RegisteredHotkey>>,
+}
+
+impl HotkeyRegistry {
+    fn lock(&self) -> MutexGuard<'_, HashMap<HotkeyAction, RegisteredHotkey>> {
+        self.bindings.lock().unwrap_or_else(|e| e.into_inner())
     }
 
-    // Parse the shortcut
-    let shortcut: Shortcut = shortcut_str
-        .parse()
-        .map_err(|e| format!("Invalid shortcut format: {}", e))?;
+    /// Current bindings, in `HotkeyAction::ALL` order
+    pub fn bindings(&self) -> Vec<HotkeyBinding> {
+        let bindings = self.lock();
+        HotkeyAction::ALL
+            .iter()
+            .filter_map(|action| bindings.get(action).map(|r| r.binding.clone()))
+            .collect()
+    }
+}
+
+/// Another action already holding `shortcut`, if any
+fn conflicting_action(
+    bindings: &HashMap<HotkeyAction, RegisteredHotkey>,
+    action: Option<HotkeyAction>,
+    shortcut: &Shortcut,
+) -> Option<HotkeyAction> {
+    bindings
+        .iter()
+        .find(|(other, registered)| Some(**other) != action && registered.shortcut == *shortcut)
+        .map(|(other, _)| *other)
+}
 
+/// Fail with `HotkeyError::Duplicate` if another action already holds the shortcut
+fn ensure_unique(
+    bindings: &HashMap<HotkeyAction, RegisteredHotkey>,
+    action: Option<HotkeyAction>,
+    hotkey: &NormalizedHotkey,
+) -> Result<(), HotkeyError> {
+    match conflicting_action(bindings, action, &hotkey.parsed) {
+        Some(other) => Err(HotkeyError::Duplicate {
+            shortcut: hotkey.shortcut.clone(),
+            action: other,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Check whether a hotkey could be bound to `action` (any action when None),
+/// without registering it
+pub fn validate_action_hotkey(
+    registry: &HotkeyRegistry,
+    action: Option<HotkeyAction>,
+    hotkey: &Hotkey,
+) -> Result<NormalizedHotkey, HotkeyError> {
+    let normalized = normalize_hotkey(&hotkey.modifiers, &hotkey.key)?;
+    ensure_unique(&registry.lock(), action, &normalized)?;
+    Ok(normalized)
+}
+
+/// Hook a shortcut up to an action with the global shortcut plugin
+fn bind_shortcut(
+    app: &AppHandle,
+    action: HotkeyAction,
+    shortcut: Shortcut,
+    delay_secs: Option<u32>,
+) -> Result<(), String> {
     // Clone app handle for the closure
     let app_handle = app.clone();
 
@@ -37,160 +184,454 @@ pub fn register_global_hotkey(app: &AppHandle, hotkey: Hotkey) -> Result<(), Str
     app.global_shortcut()
         .on_shortcut(shortcut, move |_app, _shortcut, event| {
             if event.state == ShortcutState::Pressed {
-                // 1. Get cursor position to determine which monitor to capture
-                #[cfg(windows)]
-                let cursor_pos: Option<(i32, i32)> = {
-                    use windows::Win32::Foundation::POINT;
-                    use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
-                    unsafe {
-                        let mut point = POINT::default();
-                        if GetCursorPos(&mut point).is_ok() {
-                            Some((point.x, point.y))
-                        } else {
-                            None
-                        }
+                match delay_secs {
+                    Some(seconds) if seconds > 0 => {
+                        start_delayed_action(&app_handle, action, seconds)
                     }
-                };
-                #[cfg(not(windows))]
-                let cursor_pos: Option<(i32, i32)> = None;
-
-                // 2. Hide window to capture clean screen
-                if let Some(window) = app_handle.get_webview_window("main") {
-                    let _ = window.hide();
+                    _ => trigger_action(&app_handle, action),
                 }
+            }
+        })
+        .map_err(|e| format!("Failed to register global shortcut: {}", e))
+}
 
-                // Give the OS a moment to repaint the background
-                std::thread::sleep(std::time::Duration::from_millis(10));
-
-                // 3. Capture monitor at cursor position (or primary if cursor detection failed)
-                let capture_result = if let Some((cx, cy)) = cursor_pos {
-                    crate::screen_capture::capture_monitor_at_point_raw(cx, cy)
-                } else {
-                    // Fallback: capture primary monitor
-                    tauri::async_runtime::block_on(async {
-                        crate::screen_capture::capture_full_screen_raw().await
-                    })
-                    .map(|img| (img, 0, 0, 1920, 1080, 1.0)) // Dummy values for primary
-                };
-
-                match capture_result {
-                    Ok((raw_image, mon_x, mon_y, mon_width, mon_height, scale_factor)) => {
-                        if cfg!(debug_assertions) {
-                            eprintln!(
-                                "[Hotkey] Captured monitor at ({},{}) size {}x{} scale {}",
-                                mon_x, mon_y, mon_width, mon_height, scale_factor
-                            );
-                        }
-
-                        // 4. SHOW WINDOW ON THE CAPTURED MONITOR
-                        if let Some(window) = app_handle.get_webview_window("main") {
-                            // Basic window setup
-                            let _ = window.set_decorations(false);
-                            let _ = window.set_always_on_top(true);
-                            let _ = window.set_skip_taskbar(true);
-                            let _ = window.set_shadow(false);
-                            let _ = window.set_resizable(true);
-
-                            // Position window on the detected monitor
-                            // xcap returns physical pixel coordinates, so use PhysicalPosition/Size
-                            let _ = window.set_position(tauri::PhysicalPosition::new(mon_x, mon_y));
-                            let _ =
-                                window.set_size(tauri::PhysicalSize::new(mon_width, mon_height));
-
-                            // Ensure window is visible and focused
-                            let _ = window.set_fullscreen(true);
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            let _ = window.unminimize();
-                            let _ = window.set_ignore_cursor_events(false);
-
-                            // Trigger UI to show crosshair/overlay with monitor info for coordinate translation
-                            #[derive(serde::Serialize, Clone)]
-                            struct MonitorOffset {
-                                x: i32,
-                                y: i32,
-                                width: u32,
-                                height: u32,
-                                scale_factor: f64,
-                            }
-                            let _ = app_handle.emit(
-                                "hotkey-triggered",
-                                MonitorOffset {
-                                    x: mon_x,
-                                    y: mon_y,
-                                    width: mon_width,
-                                    height: mon_height,
-                                    scale_factor,
-                                },
-                            );
-                        }
-
-                        // 3. ENCODE & SEND IMAGE (BACKGROUND)
-                        // Using JPEG for speed to minimize the "Jump" delay
-                        let app_handle_clone = app_handle.clone();
-                        tauri::async_runtime::spawn(async move {
-                            // 4. SAVE TO TEMP FILE
-                            // Using file-based transfer is more robust than large Base64 IPC payloads
-                            let temp_dir = std::env::temp_dir();
-                            let file_path = temp_dir.join("justsnap_capture.png"); // Use PNG for lossless quality
-
-                            if cfg!(debug_assertions) {
-                                eprintln!("[Hotkey] Saving capture to: {:?}", file_path);
-                            }
-
-                            // Save as PNG directly (supports RGBA)
-                            if let Err(e) =
-                                raw_image.save_with_format(&file_path, image::ImageFormat::Png)
-                            {
-                                eprintln!("[Error] Failed to save temp image: {}", e);
-                                return;
-                            }
-
-                            // Emit the FILE PATH
-                            let path_string = file_path.to_string_lossy().to_string();
-                            if cfg!(debug_assertions) {
-                                eprintln!("[Hotkey] Emitting path: {}", path_string);
-                            }
-
-                            if let Err(e) =
-                                app_handle_clone.emit("screen-capture-ready", path_string)
-                            {
-                                eprintln!("[Error] Failed to emit screen capture event: {}", e);
-                            }
-                        });
-                    }
-                    Err(e) => {
-                        eprintln!("[Error] Failed to capture screen: {}", e);
-                        // If capture failed, we should probably still show window or show error
-                        let _ = app_handle.emit("capture-debug", format!("Capture failed: {}", e));
+/// Bind a hotkey to an action, replacing the action's previous shortcut.
+/// Fails if the hotkey is invalid, another action already uses it or the OS refuses it;
+/// the previous shortcut stays active in that case.
+pub fn register_action_hotkey(
+    app: &AppHandle,
+    action: HotkeyAction,
+    hotkey: Hotkey,
+) -> Result<(), HotkeyError> {
+    let normalized = normalize_hotkey(&hotkey.modifiers, &hotkey.key)?;
+
+    if cfg!(debug_assertions) {
+        println!("[Hotkey] Registering {:?}: {}", action, normalized.shortcut);
+    }
+
+    let registry = app.state::<HotkeyRegistry>();
+    let mut bindings = registry.lock();
+
+    ensure_unique(&bindings, Some(action), &normalized)?;
+
+    // Release the action's current shortcut before binding the new one
+    let previous = bindings.remove(&action);
+    if let Some(ref previous) = previous {
+        let _ = app.global_shortcut().unregister(previous.shortcut);
+    }
+
+    if let Err(message) = bind_shortcut(app, action, normalized.parsed, hotkey.delay_secs) {
+        if let Some(previous) = previous {
+            if bind_shortcut(app, action, previous.shortcut, previous.binding.delay_secs).is_ok() {
+                bindings.insert(action, previous);
+            }
+        }
+        return Err(HotkeyError::RegistrationFailed {
+            shortcut: normalized.shortcut,
+            message,
+        });
+    }
+
+    bindings.insert(
+        action,
+        RegisteredHotkey {
+            shortcut: normalized.parsed,
+            binding: HotkeyBinding {
+                action,
+                shortcut: normalized.shortcut,
+                delay_secs: hotkey.delay_secs,
+            },
+        },
+    );
+
+    Ok(())
+}
+
+/// Register several actions at once, reporting success or the conflict for each.
+/// Shortcuts may be swapped between actions in a single call. An action whose new shortcut
+/// fails keeps its previous one, unless another action took that over in the same call.
+pub fn register_action_hotkeys(
+    app: &AppHandle,
+    hotkeys: Vec<(HotkeyAction, Hotkey)>,
+) -> Vec<HotkeyRegistration> {
+    let registry = app.state::<HotkeyRegistry>();
+
+    // Release every listed action's shortcut first, so they are free to be swapped
+    let mut previous = HashMap::new();
+    {
+        let mut bindings = registry.lock();
+        for (action, _) in &hotkeys {
+            if let Some(registered) = bindings.remove(action) {
+                let _ = app.global_shortcut().unregister(registered.shortcut);
+                previous.insert(*action, registered);
+            }
+        }
+    }
+
+    let registrations: Vec<HotkeyRegistration> = hotkeys
+        .into_iter()
+        .map(|(action, hotkey)| {
+            let shortcut = hotkey.to_shortcut_string();
+            let error = register_action_hotkey(app, action, hotkey).err();
+
+            if let Some(ref e) = error {
+                eprintln!("[Error] Failed to register hotkey for {:?}: {}", action, e);
+            }
+
+            HotkeyRegistration {
+                action,
+                shortcut,
+                error,
+            }
+        })
+        .collect();
+
+    // Give the actions that failed their previous shortcut back
+    let mut bindings = registry.lock();
+    for registration in registrations.iter().filter(|r| r.error.is_some()) {
+        let action = registration.action;
+        let Some(old) = previous.remove(&action) else {
+            continue;
+        };
+        if conflicting_action(&bindings, Some(action), &old.shortcut).is_none()
+            && bind_shortcut(app, action, old.shortcut, old.binding.delay_secs).is_ok()
+        {
+            bindings.insert(action, old);
+        }
+    }
+
+    registrations
+}
+
+/// Unbind an action's hotkey, returning whether it had one
+pub fn unregister_action_hotkey(app: &AppHandle, action: HotkeyAction) -> Result<bool, String> {
+    let registry = app.state::<HotkeyRegistry>();
+    let Some(registered) = registry.lock().remove(&action) else {
+        return Ok(false);
+    };
+
+    if cfg!(debug_assertions) {
+        println!(
+            "[Hotkey] Unregistering {:?}: {}",
+            action, registered.binding.shortcut
+        );
+    }
+
+    app.global_shortcut()
+        .unregister(registered.shortcut)
+        .map_err(|e| format!("Failed to unregister shortcut: {}", e))?;
+
+    Ok(true)
+}
+
+/// Run the action bound to a hotkey
+pub fn trigger_action(app: &AppHandle, action: HotkeyAction) {
+    if cfg!(debug_assertions) {
+        println!("[Hotkey] Triggered {:?}", action);
+    }
+
+    let capture_id = match action {
+        // The overlay is told the action and switches to window / OCR selection
+        HotkeyAction::Capture | HotkeyAction::WindowCapture | HotkeyAction::Ocr => {
+            capture_at_cursor(app, action)
+        }
+        HotkeyAction::FullScreen => capture_screen_at_cursor(app),
+        HotkeyAction::RepeatRegion => {
+            if let Err(e) = crate::recent_regions::repeat_last_region(app) {
+                eprintln!("[Error] Failed to repeat last region: {}", e);
+                let _ = app.emit("capture-debug", format!("Capture failed: {}", e));
+            }
+            None
+        }
+        HotkeyAction::OpenLastSticky => {
+            if let Err(e) = crate::image_input::reopen_last_sticky(app) {
+                eprintln!("[Error] Failed to open the last sticky: {}", e);
+                let _ = app.emit("capture-debug", format!("Open last sticky failed: {}", e));
+            }
+            None
+        }
+    };
+
+    #[derive(serde::Serialize, Clone)]
+    struct HotkeyActionEvent {
+        action: HotkeyAction,
+        capture_id: Option<String>,
+    }
+    let _ = app.emit(
+        HOTKEY_ACTION_EVENT,
+        HotkeyActionEvent { action, capture_id },
+    );
+}
+
+/// Cursor position in physical desktop coordinates, where the platform provides it
+fn cursor_position() -> Option<(i32, i32)> {
+    #[cfg(windows)]
+    {
+        use windows::Win32::Foundation::POINT;
+        use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+        unsafe {
+            let mut point = POINT::default();
+            if GetCursorPos(&mut point).is_ok() {
+                Some((point.x, point.y))
+            } else {
+                None
+            }
+        }
+    }
+    #[cfg(not(windows))]
+    None
+}
+
+/// Hide the overlay and grab the monitor under the cursor (or the primary monitor)
+/// Returns the image and the monitor it was taken from
+fn grab_monitor_at_cursor(app_handle: &AppHandle) -> Result<(RgbaImage, MonitorInfo), String> {
+    // 1. Get cursor position to determine which monitor to capture
+    let cursor_pos = cursor_position();
+
+    // 2. Hide window to capture clean screen
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.hide();
+    }
+
+    // Give the OS a moment to repaint the background
+    std::thread::sleep(std::time::Duration::from_millis(10));
+
+    // 3. Capture monitor at cursor position (or primary if cursor detection failed)
+    let backend = app_handle.state::<CaptureBackendState>();
+    if let Some((cx, cy)) = cursor_pos {
+        crate::screen_capture::capture_monitor_at_point_raw(backend.backend(), cx, cy)
+    } else {
+        // Fallback: capture primary monitor with its real geometry
+        crate::screen_capture::capture_primary_monitor_raw(backend.backend())
+    }
+}
+
+/// Capture the monitor under the cursor without opening the overlay, returning the capture id
+fn capture_screen_at_cursor(app_handle: &AppHandle) -> Option<String> {
+    match grab_monitor_at_cursor(app_handle) {
+        Ok((raw_image, monitor)) => {
+            let store = app_handle.state::<CaptureStore>();
+            let capture_id = store.insert(StoredCapture::from_monitor(raw_image, &monitor));
+
+            // Record in the history off the hotkey thread (PNG encoding takes a while)
+            if let Ok(capture) = store.get(&capture_id) {
+                let app = app_handle.clone();
+                std::thread::spawn(move || {
+                    let details = HistoryDetails {
+                        region: Some(HistoryRegion {
+                            x: monitor.x,
+                            y: monitor.y,
+                            width: monitor.width,
+                            height: monitor.height,
+                        }),
+                        monitor_name: Some(monitor.name.clone()),
+                        ..HistoryDetails::default()
+                    };
+                    let captured_at = capture.captured_at.timestamp_millis();
+                    if let Err(e) = record_capture(&app, &capture.image, captured_at, details) {
+                        eprintln!("[Error] {}", e);
                     }
+                });
+            }
+
+            Some(capture_id)
+        }
+        Err(e) => {
+            eprintln!("[Error] Failed to capture screen: {}", e);
+            let _ = app_handle.emit("capture-debug", format!("Capture failed: {}", e));
+            None
+        }
+    }
+}
+
+/// Show the main window full-screen on a monitor (physical pixel geometry from xcap)
+pub fn show_main_window_on(app_handle: &AppHandle, monitor: &MonitorInfo) -> bool {
+    let Some(window) = app_handle.get_webview_window("main") else {
+        return false;
+    };
+
+    // Basic window setup
+    let _ = window.set_decorations(false);
+    let _ = window.set_always_on_top(true);
+    let _ = window.set_skip_taskbar(true);
+    let _ = window.set_shadow(false);
+    let _ = window.set_resizable(true);
+
+    // Position window on the detected monitor
+    // xcap returns physical pixel coordinates, so use PhysicalPosition/Size
+    let _ = window.set_position(tauri::PhysicalPosition::new(monitor.x, monitor.y));
+    let _ = window.set_size(tauri::PhysicalSize::new(monitor.width, monitor.height));
+
+    // Ensure window is visible and focused
+    let _ = window.set_fullscreen(true);
+    let _ = window.show();
+    let _ = window.set_focus();
+    let _ = window.unminimize();
+    let _ = window.set_ignore_cursor_events(false);
+    true
+}
+
+/// The monitor under the cursor (or the primary monitor)
+pub fn monitor_under_cursor(app_handle: &AppHandle) -> Result<MonitorInfo, String> {
+    let backend = app_handle.state::<CaptureBackendState>();
+    let monitors = crate::screen_capture::get_all_monitors(backend.backend())?;
+
+    let monitor = match cursor_position() {
+        Some((x, y)) => crate::screen_capture::monitor_at_point(&monitors, x, y),
+        None => monitors.iter().find(|monitor| monitor.is_primary),
+    };
+    monitor
+        .or(monitors.first())
+        .cloned()
+        .ok_or_else(|| "No monitors found".to_string())
+}
+
+/// Capture the monitor under the cursor and show the overlay on it, returning the capture id
+pub fn capture_at_cursor(app_handle: &AppHandle, action: HotkeyAction) -> Option<String> {
+    match grab_monitor_at_cursor(app_handle) {
+        Ok((raw_image, monitor)) => {
+            let (mon_x, mon_y, mon_width, mon_height, scale_factor) = (
+                monitor.x,
+                monitor.y,
+                monitor.width,
+                monitor.height,
+                monitor.scale_factor,
+            );
+            if cfg!(debug_assertions) {
+                eprintln!(
+                    "[Hotkey] Captured monitor at ({},{}) size {}x{} scale {}",
+                    mon_x, mon_y, mon_width, mon_height, scale_factor
+                );
+            }
+
+            // 4. SHOW WINDOW ON THE CAPTURED MONITOR
+            if show_main_window_on(app_handle, &monitor) {
+                // Trigger UI to show crosshair/overlay with monitor info for coordinate translation
+                #[derive(serde::Serialize, Clone)]
+                struct MonitorOffset {
+                    x: i32,
+                    y: i32,
+                    width: u32,
+                    height: u32,
+                    scale_factor: f64,
+                    /// Selection the overlay offers: region, window or text to recognise
+                    action: HotkeyAction,
                 }
+                let _ = app_handle.emit(
+                    "hotkey-triggered",
+                    MonitorOffset {
+                        x: mon_x,
+                        y: mon_y,
+                        width: mon_width,
+                        height: mon_height,
+                        scale_factor,
+                        action,
+                    },
+                );
             }
-        })
-        .map_err(|e| format!("Failed to register global shortcut: {}", e))?;
 
-    Ok(())
+            // 5. STORE & SEND CAPTURE ID
+            // The capture stays in memory; the webview loads it through the
+            // justsnap:// protocol and commands reference it by id
+            let store = app_handle.state::<CaptureStore>();
+            let capture_id = store.insert(StoredCapture::from_monitor(raw_image, &monitor));
+
+            if cfg!(debug_assertions) {
+                eprintln!("[Hotkey] Emitting capture id: {}", capture_id);
+            }
+
+            if let Err(e) = app_handle.emit("screen-capture-ready", capture_id.clone()) {
+                eprintln!("[Error] Failed to emit screen capture event: {}", e);
+            }
+
+            Some(capture_id)
+        }
+        Err(e) => {
+            eprintln!("[Error] Failed to capture screen: {}", e);
+            // If capture failed, we should probably still show window or show error
+            let _ = app_handle.emit("capture-debug", format!("Capture failed: {}", e));
+            None
+        }
+    }
 }
 
-/// Unregister all global hotkeys
+/// Count down (emitting `capture-countdown`), then run the action as if its hotkey was pressed.
+/// Returns immediately; the action is skipped if the countdown is cancelled.
+pub fn start_delayed_action(app: &AppHandle, action: HotkeyAction, seconds: u32) {
+    // Keep the overlay out of the way while the user opens menus/tooltips
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.hide();
+    }
+
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = crate::capture_timer::run_countdown(&app_handle, seconds) {
+            if cfg!(debug_assertions) {
+                println!("[Hotkey] {}", e);
+            }
+            return;
+        }
+
+        trigger_action(&app_handle, action);
+    });
+}
+
+/// Unregister every action's hotkey
 pub fn unregister_global_hotkey(app: &AppHandle) -> Result<(), String> {
     if cfg!(debug_assertions) {
         println!("[Hotkey] Unregistering all global hotkeys");
     }
 
-    app.global_shortcut()
-        .unregister_all()
-        .map_err(|e| format!("Failed to unregister shortcuts: {}", e))?;
+    for action in HotkeyAction::ALL {
+        unregister_action_hotkey(app, action)?;
+    }
 
     Ok(())
 }
 
-/// Register default hotkey (Ctrl+Shift+S) on app startup
-pub fn register_default_hotkey(app: &AppHandle) -> Result<(), String> {
-    let hotkey = Hotkey {
-        key: "S".to_string(),
-        modifiers: vec!["Ctrl".to_string(), "Shift".to_string()],
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registered(shortcut: &str) -> RegisteredHotkey {
+        RegisteredHotkey {
+            shortcut: shortcut.parse().unwrap(),
+            binding: HotkeyBinding {
+                action: HotkeyAction::Capture,
+                shortcut: shortcut.to_string(),
+                delay_secs: None,
+            },
+        }
+    }
 
-    register_global_hotkey(app, hotkey)
+    #[test]
+    fn action_names_match_settings_keys() {
+        let action: HotkeyAction = serde_json::from_str(r#""open_last_sticky""#).unwrap();
+        assert_eq!(action, HotkeyAction::OpenLastSticky);
+        assert_eq!(
+            serde_json::to_string(&HotkeyAction::Capture).unwrap(),
+            r#""capture""#
+        );
+    }
+
+    #[test]
+    fn conflict_only_reported_for_other_actions() {
+        let mut bindings = HashMap::new();
+        bindings.insert(HotkeyAction::Capture, registered("Ctrl+Shift+S"));
+
+        // Same keys written differently still conflict
+        let shortcut: Shortcut = "Shift+Control+S".parse().unwrap();
+        assert_eq!(
+            conflicting_action(&bindings, Some(HotkeyAction::Ocr), &shortcut),
+            Some(HotkeyAction::Capture)
+        );
+        assert_eq!(
+            conflicting_action(&bindings, None, &shortcut),
+            Some(HotkeyAction::Capture)
+        );
+        assert_eq!(
+            conflicting_action(&bindings, Some(HotkeyAction::Capture), &shortcut),
+            None
+        );
+    }
 }
@@ -0,0 +1,481 @@
+// JustSnap - Image Input Module
+// Opens images that were not just captured (clipboard, files, history) in the editor or as stickies
+
+use crate::capture_store::{CaptureStore, StoredCapture};
+use crate::screen_capture::MonitorInfo;
+use image::RgbaImage;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Event asking the main window to open a stored capture in the editor
+pub const OPEN_IN_EDITOR_EVENT: &str = "open-in-editor";
+
+/// Payload of `open-in-editor`
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct OpenInEditorEvent {
+    pub capture_id: String,
+    /// Image size in pixels
+    pub width: u32,
+    pub height: u32,
+    /// Unix time in milliseconds
+    pub captured_at: i64,
+    /// History entry the image was reopened from
+    pub history_id: Option<i64>,
+    /// Monitor the editor is shown on
+    pub monitor: MonitorInfo,
+}
+
+/// Where an opened image goes
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenTarget {
+    /// The editor, on the monitor under the cursor
+    #[default]
+    Editor,
+    /// A sticky window in the middle of the monitor under the cursor
+    Sticky,
+    /// Only store it; the caller decides (e.g. pins it as a sticky)
+    None,
+}
+
+/// An image placed in the capture store
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct OpenedImage {
+    pub capture_id: String,
+    pub width: u32,
+    pub height: u32,
+    /// File the image was read from, if any
+    pub path: Option<String>,
+}
+
+/// Show the main window on the monitor under the cursor and open a stored capture in its editor
+pub fn open_in_editor(
+    app: &AppHandle,
+    capture_id: &str,
+    history_id: Option<i64>,
+) -> Result<(), String> {
+    let capture = app.state::<CaptureStore>().get(capture_id)?;
+    let monitor = crate::hotkeys::monitor_under_cursor(app)?;
+
+    crate::hotkeys::show_main_window_on(app, &monitor);
+
+    app.emit(
+        OPEN_IN_EDITOR_EVENT,
+        OpenInEditorEvent {
+            capture_id: capture_id.to_string(),
+            width: capture.image.width(),
+            height: capture.image.height(),
+            captured_at: capture.captured_at.timestamp_millis(),
+            history_id,
+            monitor,
+        },
+    )
+    .map_err(|e| format!("Failed to open image: {}", e))
+}
+
+/// Store an image like a capture and send it to `target`
+pub fn open_image(
+    app: &AppHandle,
+    image: RgbaImage,
+    path: Option<PathBuf>,
+    target: OpenTarget,
+) -> Result<OpenedImage, String> {
+    let (width, height) = image.dimensions();
+    let capture_id = app
+        .state::<CaptureStore>()
+        .insert(StoredCapture::new(image));
+
+    match target {
+        OpenTarget::Editor => open_in_editor(app, &capture_id, None)?,
+        OpenTarget::Sticky => open_sticky(app, &capture_id, width, height, 0)?,
+        OpenTarget::None => {}
+    }
+
+    if cfg!(debug_assertions) {
+        println!(
+            "[Open] {}x{} image from {} as {}",
+            width,
+            height,
+            path.as_deref()
+                .map_or("clipboard".into(), |p| p.to_string_lossy()),
+            capture_id
+        );
+    }
+
+    Ok(OpenedImage {
+        capture_id,
+        width,
+        height,
+        path: path.map(|p| p.to_string_lossy().into_owned()),
+    })
+}
+
+/// Read an image (or a copied image file) from the clipboard and send it to `target`
+pub fn paste_from_clipboard(app: &AppHandle, target: OpenTarget) -> Result<OpenedImage, String> {
+    let pasted = crate::clipboard::get_image()?;
+    open_image(app, pasted.image, pasted.path, target)
+}
+
+// ============================================
+// Sticky Windows
+// ============================================
+
+/// A sticky (always-on-top) window showing an image
+#[derive(Clone)]
+pub struct StickyWindow {
+    /// Image URL, used when `capture_id` is None
+    pub image_src: String,
+    /// Stored capture to show instead of `image_src`
+    pub capture_id: Option<String>,
+    /// JSON array of annotations drawn over the image
+    pub annotations_json: String,
+    /// Logical position and size of the window
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Physical size of the image
+    pub native_width: f64,
+    pub native_height: f64,
+}
+
+/// Create a sticky window. A stored capture it shows belongs to it and is released when the
+/// sticky closes.
+pub fn create_sticky_window(app: &AppHandle, sticky: StickyWindow) -> Result<(), String> {
+    use tauri::{WebviewUrl, WebviewWindowBuilder};
+
+    let label = format!("sticky_{}", chrono::Utc::now().timestamp_micros());
+
+    // Stored captures are served to the webview through the justsnap:// protocol
+    let image_src = match sticky.capture_id {
+        Some(ref id) => crate::capture_store::capture_url(id),
+        None => sticky.image_src.clone(),
+    };
+
+    // Inject window type and image src, AND native dimensions
+    let init_script = format!(
+        "window.__WINDOW_TYPE__ = 'sticky'; 
+         window.__STICKY_IMAGE_SRC__ = {:?}; 
+         window.__STICKY_ANNOTATIONS__ = {:?};
+         window.__STICKY_NATIVE_WIDTH__ = {};
+         window.__STICKY_NATIVE_HEIGHT__ = {};",
+        image_src, sticky.annotations_json, sticky.native_width, sticky.native_height
+    );
+
+    let window = WebviewWindowBuilder::new(app, &label, WebviewUrl::App("index.html".into()))
+        .title("JustSnap Sticky")
+        .decorations(false)
+        .resizable(true)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .shadow(true)
+        .inner_size(sticky.width, sticky.height)
+        .position(sticky.x, sticky.y)
+        .initialization_script(&init_script)
+        .build()
+        .map_err(|e: tauri::Error| e.to_string())?;
+
+    // Kept for `reopen_last_sticky`
+    if let Err(e) = remember_sticky(app, label, &sticky) {
+        eprintln!("[Error] Failed to remember the sticky: {}", e);
+    }
+
+    if let Some(capture_id) = sticky.capture_id {
+        let app = app.clone();
+        window.on_window_event(move |event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                app.state::<CaptureStore>().remove(&capture_id);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// The most recently created sticky
+#[derive(Clone)]
+struct RememberedSticky {
+    label: String,
+    /// Its contents, with a capture handle of its own that outlives the sticky
+    sticky: StickyWindow,
+}
+
+/// Managed state: the last sticky, so a hotkey can bring it back after it was closed
+#[derive(Default)]
+pub struct LastSticky(Mutex<Option<RememberedSticky>>);
+
+impl LastSticky {
+    fn lock(&self) -> MutexGuard<'_, Option<RememberedSticky>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn remember_sticky(app: &AppHandle, label: String, sticky: &StickyWindow) -> Result<(), String> {
+    let store = app.state::<CaptureStore>();
+    let capture_id = sticky
+        .capture_id
+        .as_deref()
+        .map(|id| store.share(id))
+        .transpose()?;
+    let remembered = RememberedSticky {
+        label,
+        sticky: StickyWindow {
+            capture_id,
+            ..sticky.clone()
+        },
+    };
+
+    let previous = app.state::<LastSticky>().lock().replace(remembered);
+    if let Some(capture_id) = previous.and_then(|previous| previous.sticky.capture_id) {
+        store.remove(&capture_id);
+    }
+    Ok(())
+}
+
+/// Bring back the last sticky: focus it while it is open, otherwise pin what it showed again
+/// where it was opened
+pub fn reopen_last_sticky(app: &AppHandle) -> Result<(), String> {
+    let Some(last) = app.state::<LastSticky>().lock().clone() else {
+        return Err("No sticky was opened yet".to_string());
+    };
+
+    if let Some(window) = app.get_webview_window(&last.label) {
+        let _ = window.show();
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+
+    // The new sticky gets its own handle, released when it closes
+    let capture_id = last
+        .sticky
+        .capture_id
+        .as_deref()
+        .map(|id| app.state::<CaptureStore>().share(id))
+        .transpose()?;
+    create_sticky_window(
+        app,
+        StickyWindow {
+            capture_id,
+            ..last.sticky
+        },
+    )
+}
+
+/// Pin a stored image as a sticky in the middle of the monitor under the cursor,
+/// scaled down to fit. `cascade` shifts each further sticky opened at once.
+pub fn open_sticky(
+    app: &AppHandle,
+    capture_id: &str,
+    width: u32,
+    height: u32,
+    cascade: u32,
+) -> Result<(), String> {
+    let monitor = crate::hotkeys::monitor_under_cursor(app)?;
+    let (x, y, sticky_width, sticky_height) = sticky_bounds(&monitor, width, height, cascade);
+
+    create_sticky_window(
+        app,
+        StickyWindow {
+            image_src: String::new(),
+            capture_id: Some(capture_id.to_string()),
+            annotations_json: "[]".to_string(),
+            x,
+            y,
+            width: sticky_width,
+            height: sticky_height,
+            native_width: width as f64,
+            native_height: height as f64,
+        },
+    )
+}
+
+/// Logical bounds of a sticky centred on `monitor` and at most 80% of its size
+fn sticky_bounds(
+    monitor: &MonitorInfo,
+    width: u32,
+    height: u32,
+    cascade: u32,
+) -> (f64, f64, f64, f64) {
+    let scale = monitor.scale_factor;
+    let fit = (monitor.width as f64 * 0.8 / width as f64)
+        .min(monitor.height as f64 * 0.8 / height as f64)
+        .min(1.0);
+    let (physical_width, physical_height) = (width as f64 * fit, height as f64 * fit);
+    let offset = cascade as f64 * 32.0 * scale;
+
+    (
+        (monitor.x as f64 + (monitor.width as f64 - physical_width) / 2.0 + offset) / scale,
+        (monitor.y as f64 + (monitor.height as f64 - physical_height) / 2.0 + offset) / scale,
+        physical_width / scale,
+        physical_height / scale,
+    )
+}
+
+// ============================================
+// Image Files
+// ============================================
+
+/// Extensions offered by the open dialog (anything `decode_image_file` understands is accepted)
+pub const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "tif", "tiff", "ico", "tga",
+];
+
+/// Decode an image file. The format is detected from the contents, so misnamed files still open;
+/// animated images (GIF, WebP, APNG) give their first frame.
+pub fn decode_image_file(path: &Path) -> Result<RgbaImage, String> {
+    let fail = |e: &dyn std::fmt::Display| format!("Failed to open {}: {}", path.display(), e);
+
+    image::ImageReader::open(path)
+        .map_err(|e| fail(&e))?
+        .with_guessed_format()
+        .map_err(|e| fail(&e))?
+        .decode()
+        .map(|image| image.to_rgba8())
+        .map_err(|e| fail(&e))
+}
+
+/// Open image files: the first one goes to `target`, the others are pinned as stickies
+/// (the editor holds one image at a time). Files that fail to open are reported and skipped.
+pub fn open_files(
+    app: &AppHandle,
+    paths: &[PathBuf],
+    target: OpenTarget,
+) -> Result<Vec<OpenedImage>, String> {
+    let mut opened = Vec::new();
+    let mut errors = Vec::new();
+
+    for path in paths {
+        let image = match decode_image_file(path) {
+            Ok(image) => image,
+            Err(e) => {
+                eprintln!("[Error] {}", e);
+                errors.push(e);
+                continue;
+            }
+        };
+
+        if opened.is_empty() || target == OpenTarget::None {
+            opened.push(open_image(app, image, Some(path.clone()), target)?);
+            continue;
+        }
+
+        let image = open_image(app, image, Some(path.clone()), OpenTarget::None)?;
+        open_sticky(
+            app,
+            &image.capture_id,
+            image.width,
+            image.height,
+            opened.len() as u32,
+        )?;
+        opened.push(image);
+    }
+
+    if opened.is_empty() && !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+    Ok(opened)
+}
+
+/// Ask for image files with the open dialog and open them (nothing when cancelled)
+pub fn open_with_dialog(app: &AppHandle, target: OpenTarget) -> Result<Vec<OpenedImage>, String> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let Some(files) = app
+        .dialog()
+        .file()
+        .set_title("Open Image")
+        .add_filter("Images", IMAGE_EXTENSIONS)
+        .blocking_pick_files()
+    else {
+        return Ok(Vec::new());
+    };
+
+    let paths = files
+        .into_iter()
+        .filter_map(|file| file.into_path().ok())
+        .collect::<Vec<_>>();
+    open_files(app, &paths, target)
+}
+
+// ============================================
+// Files From the Command Line
+// ============================================
+
+/// Image files passed on the command line, kept until the editor is ready to receive them
+#[derive(Default)]
+pub struct StartupFiles(Mutex<Vec<PathBuf>>);
+
+impl StartupFiles {
+    /// Collect the file arguments (flags such as `--silence` are skipped)
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        Self(Mutex::new(file_args(args)))
+    }
+
+    pub fn take(&self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Existing files among the arguments (the program name excluded)
+pub fn file_args(args: impl IntoIterator<Item = String>) -> Vec<PathBuf> {
+    args.into_iter()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .map(PathBuf::from)
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, Rgba};
+
+    #[test]
+    fn decodes_the_first_frame_of_an_animated_gif() {
+        // Named .png to check that the format comes from the contents
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("animated.png");
+        {
+            let file = std::fs::File::create(&path).unwrap();
+            let mut encoder = GifEncoder::new(file);
+            for color in [Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 255])] {
+                let frame = Frame::from_parts(
+                    RgbaImage::from_pixel(4, 3, color),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(100, 1),
+                );
+                encoder.encode_frame(frame).unwrap();
+            }
+        }
+
+        let image = decode_image_file(&path).unwrap();
+        assert_eq!(image.dimensions(), (4, 3));
+        assert_eq!(image.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+
+        std::fs::write(&path, b"not an image").unwrap();
+        let error = decode_image_file(&path).unwrap_err();
+        assert!(error.contains("animated.png"), "{}", error);
+    }
+
+    #[test]
+    fn takes_existing_files_from_the_arguments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("argument.png");
+        std::fs::write(&path, b"").unwrap();
+
+        let args = [
+            "justsnap".to_string(),
+            "--silence".to_string(),
+            path.to_string_lossy().into_owned(),
+            dir.path()
+                .join("missing.png")
+                .to_string_lossy()
+                .into_owned(),
+        ];
+        let startup = StartupFiles::from_args(args);
+        assert_eq!(startup.take(), vec![path.clone()]);
+        assert!(startup.take().is_empty());
+    }
+}
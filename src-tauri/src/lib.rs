@@ -1,15 +1,82 @@
 // JustSnap - Tauri Backend Library
 
 // Module declarations (Rust will find the files in the src directory)
+mod auto_save;
+mod capture_backend;
+mod capture_store;
+mod capture_timer;
+mod cli;
+mod clipboard;
 mod commands;
+mod control_server;
+mod encoding;
+mod export;
+mod history;
+mod hotkey_validation;
 mod hotkeys;
+mod image_input;
+mod recent_regions;
 mod screen_capture;
+mod settings;
+mod single_instance;
+mod ui_elements;
+mod window_capture;
+mod window_list;
+#[cfg(target_os = "linux")]
+mod x11_windows;
 
 use tauri_plugin_autostart::MacosLauncher;
 
+/// Run a command-line invocation (`justsnap capture ...`) without starting the app.
+/// Returns None when the arguments are for the app itself.
+pub fn run_cli() -> Option<std::process::ExitCode> {
+    cli::run(&std::env::args().collect::<Vec<_>>())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
     tauri::Builder::default()
+        // A second launch hands its arguments to the running instance and exits
+        // (before it would try to register the same global hotkeys). Must be the first plugin.
+        .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            let args = argv.get(1..).unwrap_or_default();
+            single_instance::handle_args(app, args, Some(std::path::Path::new(&cwd)));
+        }))
+        // Screen capture backend (xcap by default, in-memory for headless runs).
+        .manage(capture_backend::CaptureBackendState::from_env())
+        // Recent captures kept in memory and referenced by id.
+        .manage(capture_store::CaptureStore::default())
+        // Countdown state for timed captures.
+        .manage(capture_timer::CaptureTimer::default())
+        // Last captured regions, for repeating a capture.
+        .manage(recent_regions::RecentRegions::load(
+            recent_regions::default_recent_regions_path(),
+        ))
+        // Image files passed on the command line, opened once the editor listens.
+        .manage(image_input::StartupFiles::from_args(std::env::args()))
+        // Shortcut bound to each hotkey action.
+        .manage(hotkeys::HotkeyRegistry::default())
+        // Local JSON-RPC socket for scripts, started when the setting is on.
+        .manage(control_server::ControlServer::default())
+        // Last sticky window, reopened by its hotkey.
+        .manage(image_input::LastSticky::default())
+        // Accessibility bus connection reused by Smart Select while the overlay is up.
+        .manage(ui_elements::ElementLookup::default())
+        // Serves stored captures to the webview (justsnap://localhost/<id>).
+        .register_asynchronous_uri_scheme_protocol(
+            capture_store::PROTOCOL_SCHEME,
+            |ctx, request, responder| {
+                use tauri::Manager;
+
+                let app = ctx.app_handle().clone();
+                std::thread::spawn(move || {
+                    let store = app.state::<capture_store::CaptureStore>();
+                    responder.respond(capture_store::protocol_response(&store, &request));
+                });
+            },
+        )
         // Allows registering hotkeys like Ctrl+Shift+S and listening globally.
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         // Allows opening file dialogs for saving images and text.
@@ -19,6 +86,24 @@ pub fn run() {
             MacosLauncher::LaunchAgent,
             Some(vec!["--silence"]),
         ))
+        // Image files dropped on the welcome window are opened in the editor.
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                if window.label() == "welcome" {
+                    use image_input::{open_files, OpenTarget};
+                    use tauri::Manager;
+
+                    // Decode off the event loop
+                    let app = window.app_handle().clone();
+                    let paths = paths.clone();
+                    std::thread::spawn(move || {
+                        if let Err(e) = open_files(&app, &paths, OpenTarget::Editor) {
+                            eprintln!("[Error] {}", e);
+                        }
+                    });
+                }
+            }
+        })
         // Run the code here before the app window is created.
         .setup(|app| {
             // Debug logging. (Only enabled in debug mode)
@@ -38,7 +123,15 @@ pub fn run() {
             // Create menu items for the system tray.
             let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
             let show_i = MenuItem::with_id(app, "show", "Show", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show_i, &quit_i])?;
+            let paste_i = MenuItem::with_id(
+                app,
+                "paste",
+                "Edit Image from Clipboard",
+                true,
+                None::<&str>,
+            )?;
+            let open_i = MenuItem::with_id(app, "open", "Open Image...", true, None::<&str>)?;
+            let menu = Menu::with_items(app, &[&show_i, &open_i, &paste_i, &quit_i])?;
 
             // Create a tray icon for the system tray.
             let _tray = TrayIconBuilder::new()
@@ -49,6 +142,26 @@ pub fn run() {
                     "quit" => {
                         app.exit(0);
                     }
+                    "open" => {
+                        // The dialog blocks until closed, so keep it off the event loop
+                        let app = app.clone();
+                        std::thread::spawn(move || {
+                            use image_input::{open_with_dialog, OpenTarget};
+                            if let Err(e) = open_with_dialog(&app, OpenTarget::Editor) {
+                                eprintln!("[Error] {}", e);
+                            }
+                        });
+                    }
+                    "paste" => {
+                        // Reading and decoding a large clipboard image takes a while
+                        let app = app.clone();
+                        std::thread::spawn(move || {
+                            use image_input::{paste_from_clipboard, OpenTarget};
+                            if let Err(e) = paste_from_clipboard(&app, OpenTarget::Editor) {
+                                eprintln!("[Error] {}", e);
+                            }
+                        });
+                    }
                     "show" => {
                         // Show or Recreate the welcome window
                         if let Some(window) = app.get_webview_window("welcome") {
@@ -116,11 +229,23 @@ pub fn run() {
                 })
                 .build(app)?;
 
-            // Register default hotkey (Ctrl+Shift+S) on startup
-            if let Err(e) = hotkeys::register_default_hotkey(&app.handle()) {
-                eprintln!("[Error] Failed to register default hotkey: {}", e);
-            } else if cfg!(debug_assertions) {
-                println!("[Hotkey] ✓ Global hotkey (Ctrl+Shift+S) registered");
+            // Capture history (opened first so the retention settings apply to it)
+            app.manage(history::CaptureHistory::open_or_temporary(
+                history::default_history_dir(),
+            ));
+
+            // Load settings and bind the saved hotkeys (Ctrl+Shift+S by default).
+            // Managed first, so applying them can tell whether old webview settings are pending.
+            app.manage(settings::SettingsStore::load(
+                settings::default_settings_path(),
+            ));
+            let settings = app.state::<settings::SettingsStore>().get();
+            settings::apply_settings(app.handle(), &settings, None);
+
+            // This launch's own actions (e.g. `--capture`) run now, its files wait for the
+            // editor (StartupFiles); later launches are handled by the single-instance plugin
+            for action in single_instance::LaunchRequest::parse(&args, None).actions {
+                hotkeys::trigger_action(app.handle(), action);
             }
 
             Ok(())
@@ -130,11 +255,24 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // Screen Capture
             commands::capture_screen,
+            commands::capture_selection,
             commands::capture_full_screen,
+            commands::repeat_last_region,
+            commands::get_recent_regions,
+            commands::capture_all_monitors,
             commands::get_monitors,
             // Hotkeys
             commands::register_hotkey,
+            commands::register_hotkeys,
             commands::unregister_hotkey,
+            commands::validate_hotkey,
+            commands::get_hotkeys,
+            commands::start_delayed_capture,
+            commands::cancel_delayed_capture,
+            // Settings
+            commands::get_settings,
+            commands::update_settings,
+            commands::import_legacy_settings,
             // Overlay
             commands::show_overlay,
             commands::hide_overlay,
@@ -144,13 +282,38 @@ pub fn run() {
             commands::open_save_dialog,
             // Clipboard
             commands::copy_image_to_clipboard,
+            commands::copy_to_clipboard,
             commands::copy_text_to_clipboard,
             commands::save_temp_image,
+            // Capture Store
+            commands::get_capture,
+            commands::store_capture,
+            commands::crop_capture,
+            commands::save_capture,
+            commands::export_captures,
+            commands::auto_save_capture,
+            commands::copy_capture_to_clipboard,
+            commands::release_capture,
+            // Capture History
+            commands::record_capture_history,
+            commands::record_stored_capture_history,
+            commands::list_history,
+            commands::get_history_thumbnail,
+            commands::update_history_entry,
+            commands::delete_history,
+            commands::reopen_history,
+            // Image Input
+            commands::paste_image_from_clipboard,
+            commands::open_image,
+            commands::open_startup_files,
             commands::create_sticky_window,
             commands::create_ai_panel_window,
             commands::create_translation_window,
             commands::close_window,
             commands::get_window_at_point,
+            commands::get_elements_at_point,
+            commands::list_windows,
+            commands::capture_window,
         ])
         // generate_context!() : Loads config from: tauri.conf.json and Cargo.toml
         .run(tauri::generate_context!())
@@ -1,7 +1,13 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-fn main() {
+fn main() -> std::process::ExitCode {
+  // `justsnap capture ...` and friends run headless and exit
+  if let Some(code) = app_lib::run_cli() {
+    return code;
+  }
+
   app_lib::run();
+  std::process::ExitCode::SUCCESS
 }
   
\ No newline at end of file
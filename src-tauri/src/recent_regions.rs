@@ -0,0 +1,292 @@
+// JustSnap - Recent Regions Module
+// Remembers the last regions captured with `capture_screen` so they can be captured again
+
+use crate::capture_backend::CaptureBackendState;
+use crate::capture_store::{CaptureSource, StoredCapture};
+use crate::screen_capture::{CaptureRegion, MonitorInfo};
+use crate::settings::SettingsStore;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager};
+
+/// How many regions are remembered
+pub const MAX_RECENT_REGIONS: usize = 10;
+
+const RECENT_REGIONS_FILE: &str = "recent_regions.json";
+
+/// A region captured before
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct RecentRegion {
+    /// Virtual desktop pixels at the time of the capture
+    pub region: CaptureRegion,
+    /// Monitor holding the middle of the region, so the region follows it when the layout changes
+    pub monitor_id: Option<u32>,
+    pub monitor_name: Option<String>,
+    /// Origin of that monitor at the time of the capture
+    pub monitor_origin: Option<(i32, i32)>,
+    /// Unix time in milliseconds
+    pub captured_at: i64,
+}
+
+impl RecentRegion {
+    fn new(region: CaptureRegion, monitors: &[MonitorInfo], captured_at: i64) -> Self {
+        let monitor = crate::screen_capture::monitor_at_point(
+            monitors,
+            region.x + region.width / 2,
+            region.y + region.height / 2,
+        );
+
+        Self {
+            region,
+            monitor_id: monitor.map(|m| m.id),
+            monitor_name: monitor.map(|m| m.name.clone()),
+            monitor_origin: monitor.map(|m| (m.x, m.y)),
+            captured_at,
+        }
+    }
+
+    /// The monitor the region was on, matched by id and then by name (ids may change between sessions)
+    fn monitor<'a>(&self, monitors: &'a [MonitorInfo]) -> Option<&'a MonitorInfo> {
+        let by_id = self
+            .monitor_id
+            .and_then(|id| monitors.iter().find(|m| m.id == id));
+        let by_name = || {
+            let name = self.monitor_name.as_deref()?;
+            monitors.iter().find(|m| m.name == name)
+        };
+        by_id.or_else(by_name)
+    }
+
+    /// Where the region is now: moved along with its monitor if that monitor moved
+    pub fn resolve(&self, monitors: &[MonitorInfo]) -> Result<CaptureRegion, String> {
+        let Some((origin_x, origin_y)) = self.monitor_origin else {
+            return Ok(self.region);
+        };
+
+        let monitor = self.monitor(monitors).ok_or_else(|| {
+            format!(
+                "The monitor of the last region ({}) is no longer connected",
+                self.monitor_name.as_deref().unwrap_or("unknown")
+            )
+        })?;
+
+        Ok(CaptureRegion {
+            x: self.region.x + monitor.x - origin_x,
+            y: self.region.y + monitor.y - origin_y,
+            ..self.region
+        })
+    }
+}
+
+/// Default location: `<config dir>/JustSnap/recent_regions.json`
+pub fn default_recent_regions_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("JustSnap").join(RECENT_REGIONS_FILE))
+}
+
+/// Managed list of recent regions, newest first, saved to a file
+pub struct RecentRegions {
+    path: Option<PathBuf>,
+    regions: Mutex<Vec<RecentRegion>>,
+}
+
+impl RecentRegions {
+    /// Load the regions saved at `path` (in-memory only when None)
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let regions = path
+            .as_deref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str::<Vec<RecentRegion>>(&contents).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            regions: Mutex::new(regions),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<RecentRegion>> {
+        self.regions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn list(&self) -> Vec<RecentRegion> {
+        self.lock().clone()
+    }
+
+    pub fn latest(&self) -> Option<RecentRegion> {
+        self.lock().first().cloned()
+    }
+
+    /// Remember a captured region (capturing the same region again moves it to the front)
+    pub fn remember(&self, region: CaptureRegion, monitors: &[MonitorInfo], captured_at: i64) {
+        let mut regions = self.lock();
+        regions.retain(|recent| recent.region != region);
+        regions.insert(0, RecentRegion::new(region, monitors, captured_at));
+        regions.truncate(MAX_RECENT_REGIONS);
+
+        if let Some(ref path) = self.path {
+            if let Err(e) = save_regions(path, &regions) {
+                eprintln!("[Error] Failed to save recent regions: {}", e);
+            }
+        }
+    }
+}
+
+fn save_regions(path: &Path, regions: &[RecentRegion]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_vec_pretty(regions).map_err(|e| e.to_string())?;
+    crate::auto_save::write_atomically(path, &json)
+}
+
+// ============================================
+// Repeating the Last Region
+// ============================================
+
+/// Result of capturing the last region again
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct RepeatedCapture {
+    /// Where the region was captured (after following its monitor)
+    pub region: CaptureRegion,
+    /// Auto-saved file, or None when the capture was copied to the clipboard
+    pub saved_path: Option<String>,
+}
+
+/// Capture the most recent region again without the overlay.
+/// The capture goes to the auto-save folder when auto-save is on, to the clipboard otherwise.
+pub fn repeat_last_region(app: &AppHandle) -> Result<RepeatedCapture, String> {
+    use crate::history::{record_capture, HistoryDetails, HistoryRegion};
+
+    let recent = app
+        .state::<RecentRegions>()
+        .latest()
+        .ok_or_else(|| "No region has been captured yet".to_string())?;
+
+    let backend = app.state::<CaptureBackendState>();
+    let monitors = crate::screen_capture::get_all_monitors(backend.backend())?;
+    let region = recent.resolve(&monitors)?;
+    let image =
+        crate::screen_capture::capture_region_raw(backend.backend(), region, &Default::default())?;
+
+    let monitor = recent.monitor(&monitors);
+    let capture = StoredCapture::new(image).with_source(CaptureSource {
+        monitor_name: monitor.map(|m| m.name.clone()),
+        scale_factor: monitor.map(|m| m.scale_factor),
+        ..CaptureSource::default()
+    });
+
+    let settings = app.state::<SettingsStore>().get();
+    let saved_path = match settings.save_path {
+        Some(ref directory) if settings.auto_save => {
+            use crate::auto_save::{save_with_template, SaveTemplate};
+            use crate::encoding::OutputFormat;
+
+            let path = save_with_template(
+                &capture.image,
+                &capture.captured_at,
+                &capture.source,
+                Path::new(directory),
+                &SaveTemplate::parse(&settings.auto_save_template)?,
+                &OutputFormat::Png { level: None },
+            )?;
+            Some(path.to_string_lossy().into_owned())
+        }
+        _ => {
+            crate::clipboard::set_image(&capture.image)?;
+            None
+        }
+    };
+
+    if cfg!(debug_assertions) {
+        println!(
+            "[Repeat] Captured {:?} -> {}",
+            region,
+            saved_path.as_deref().unwrap_or("clipboard")
+        );
+    }
+
+    let details = HistoryDetails {
+        region: Some(HistoryRegion {
+            x: region.x,
+            y: region.y,
+            width: region.width as u32,
+            height: region.height as u32,
+        }),
+        monitor_name: capture.source.monitor_name.clone(),
+        saved_path: saved_path.clone(),
+        ..HistoryDetails::default()
+    };
+    if let Err(e) = record_capture(
+        app,
+        &capture.image,
+        capture.captured_at.timestamp_millis(),
+        details,
+    ) {
+        eprintln!("[Error] {}", e);
+    }
+
+    Ok(RepeatedCapture { region, saved_path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(id: u32, name: &str, x: i32) -> MonitorInfo {
+        MonitorInfo {
+            id,
+            name: name.to_string(),
+            x,
+            y: 0,
+            width: 1920,
+            height: 1080,
+            scale_factor: 1.0,
+            is_primary: x == 0,
+        }
+    }
+
+    fn region(x: i32) -> CaptureRegion {
+        CaptureRegion {
+            x,
+            y: 100,
+            width: 400,
+            height: 300,
+        }
+    }
+
+    #[test]
+    fn keeps_newest_regions_and_saves_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(RECENT_REGIONS_FILE);
+        let monitors = [monitor(1, "Left", 0), monitor(2, "Right", 1920)];
+
+        let recent = RecentRegions::load(Some(path.clone()));
+        for x in 0..12 {
+            recent.remember(region(x * 10), &monitors, x as i64);
+        }
+        // Capturing a region again moves it to the front instead of adding a copy
+        recent.remember(region(50), &monitors, 99);
+        recent.remember(region(2000), &monitors, 100);
+
+        let reloaded = RecentRegions::load(Some(path));
+        let regions = reloaded.list();
+        assert_eq!(regions.len(), MAX_RECENT_REGIONS);
+        assert_eq!(regions[0].region, region(2000));
+        assert_eq!(regions[0].monitor_id, Some(2));
+        assert_eq!(regions[1].region, region(50));
+        assert_eq!(regions.iter().filter(|r| r.region == region(50)).count(), 1);
+    }
+
+    #[test]
+    fn region_follows_its_monitor() {
+        let before = [monitor(1, "Left", 0), monitor(2, "Right", 1920)];
+        let recent = RecentRegion::new(region(2000), &before, 0);
+
+        // The right monitor was moved to the left side and got a new id
+        let after = [monitor(1, "Left", 0), monitor(7, "Right", -1920)];
+        assert_eq!(recent.resolve(&after).unwrap(), region(-1840));
+
+        let unplugged = [monitor(1, "Left", 0)];
+        assert!(recent.resolve(&unplugged).is_err());
+    }
+}
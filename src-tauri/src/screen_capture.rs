@@ -1,9 +1,9 @@
 // JustSnap - Screen Capture Module
-// Handles screen capture functionality using xcap
+// Handles screen capture functionality on top of a pluggable CaptureBackend
 
+use crate::capture_backend::CaptureBackend;
+use crate::encoding::{encode_image, OutputFormat};
 use image::RgbaImage;
-use std::io::Cursor;
-use xcap::Monitor;
 
 /// Information about a display monitor
 #[derive(serde::Serialize, Clone, Debug)]
@@ -19,26 +19,12 @@ pub struct MonitorInfo {
 }
 
 /// Get information about all connected monitors
-pub fn get_all_monitors() -> Result<Vec<MonitorInfo>, String> {
-    let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
-
-    let mut result = Vec::new();
-    for monitor in monitors {
-        result.push(MonitorInfo {
-            id: monitor.id().unwrap_or(0),
-            name: monitor.name().unwrap_or_default(),
-            x: monitor.x().unwrap_or(0),
-            y: monitor.y().unwrap_or(0),
-            width: monitor.width().unwrap_or(0),
-            height: monitor.height().unwrap_or(0),
-            scale_factor: monitor.scale_factor().unwrap_or(1.0) as f64,
-            is_primary: monitor.is_primary().unwrap_or(false),
-        });
-    }
-
-    Ok(result)
+pub fn get_all_monitors(backend: &dyn CaptureBackend) -> Result<Vec<MonitorInfo>, String> {
+    backend.monitors()
 }
 
+/// Region in virtual desktop pixels
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
 pub struct CaptureRegion {
     pub x: i32,
     pub y: i32,
@@ -46,169 +32,468 @@ pub struct CaptureRegion {
     pub height: i32,
 }
 
+/// Find the monitor containing the given point, falling back to the primary monitor
+pub fn monitor_at_point(monitors: &[MonitorInfo], x: i32, y: i32) -> Option<&MonitorInfo> {
+    monitors
+        .iter()
+        .find(|m| {
+            let m_right = m.x + m.width as i32;
+            let m_bottom = m.y + m.height as i32;
+            x >= m.x && x < m_right && y >= m.y && y < m_bottom
+        })
+        .or_else(|| monitors.iter().find(|m| m.is_primary))
+}
+
+/// Options for compositing a region that spans several monitors
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+pub struct StitchOptions {
+    /// RGBA colour for parts of the region not covered by any monitor (transparent by default)
+    #[serde(default)]
+    pub fill: [u8; 4],
+    /// Resample each monitor's part of the region by `target_scale / scale_factor`, so every
+    /// monitor ends up at this scale factor (None keeps physical pixels 1:1; clamped to
+    /// 0.1-8). Monitors side by side still meet at their seam; one that comes out shorter
+    /// than its neighbour leaves `fill` below it.
+    #[serde(default)]
+    pub target_scale: Option<f64>,
+}
+
+/// Lowest and highest `target_scale` honoured
+const TARGET_SCALE_MIN: f64 = 0.1;
+const TARGET_SCALE_MAX: f64 = 8.0;
+
+/// Largest stitched image allocated (1 GiB of RGBA)
+const MAX_CANVAS_PIXELS: f64 = (1u64 << 28) as f64;
+
+impl StitchOptions {
+    /// `target_scale` clamped to the supported range; values that are no scale at all fail
+    fn checked_target_scale(&self) -> Result<Option<f64>, String> {
+        match self.target_scale {
+            None => Ok(None),
+            Some(scale) if scale.is_finite() && scale > 0.0 => {
+                Ok(Some(scale.clamp(TARGET_SCALE_MIN, TARGET_SCALE_MAX)))
+            }
+            Some(scale) => Err(format!("Invalid target scale: {}", scale)),
+        }
+    }
+}
+
+/// Virtual desktop coordinates along one axis to canvas pixels. Each stretch between monitor
+/// edges is scaled by the largest factor of the monitors covering it, so tiles never overlap
+/// and monitors side by side meet at their seam.
+struct AxisMap {
+    /// Start, end and scale of each stretch, in order
+    stretches: Vec<(i32, i32, f64)>,
+}
+
+impl AxisMap {
+    /// `spans` are the monitors' start, end and scale along the axis; parts of
+    /// `start..end` no monitor covers use `gap_scale`
+    fn new(start: i32, end: i32, spans: &[(i32, i32, f64)], gap_scale: f64) -> Self {
+        let mut edges: Vec<i32> = spans
+            .iter()
+            .flat_map(|&(from, to, _)| [from, to])
+            .chain([start, end])
+            .filter(|edge| (start..=end).contains(edge))
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+
+        let stretches = edges
+            .windows(2)
+            .map(|pair| {
+                let scale = spans
+                    .iter()
+                    .filter(|&&(from, to, _)| from <= pair[0] && pair[1] <= to)
+                    .map(|&(_, _, scale)| scale)
+                    .fold(None, |max: Option<f64>, scale| {
+                        Some(max.map_or(scale, |max| max.max(scale)))
+                    })
+                    .unwrap_or(gap_scale);
+                (pair[0], pair[1], scale)
+            })
+            .collect();
+        Self { stretches }
+    }
+
+    /// Canvas position of `value` (unrounded)
+    fn position(&self, value: i32) -> f64 {
+        self.stretches
+            .iter()
+            .map(|&(from, to, scale)| (value.min(to) - from).max(0) as f64 * scale)
+            .sum()
+    }
+
+    /// Canvas start and end (rounded) of a tile spanning `from..to` at `scale`: it ends at
+    /// the mapped `to`, and so meets its neighbour, unless a larger scale widened the stretch
+    fn slot(&self, from: i32, to: i32, scale: f64) -> (f64, f64) {
+        let start = self.position(from);
+        let mapped_end = self.position(to);
+        let end = start + (to - from) as f64 * scale;
+        let end = if mapped_end - end < 1e-6 {
+            mapped_end
+        } else {
+            end
+        };
+        (start.round(), end.round())
+    }
+}
+
+impl CaptureRegion {
+    /// Overlap of two regions (None when they do not overlap)
+    pub fn intersection(&self, other: &CaptureRegion) -> Option<CaptureRegion> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+
+        if right <= left || bottom <= top {
+            return None;
+        }
+
+        Some(CaptureRegion {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        })
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    pub fn area(&self) -> i64 {
+        self.width.max(0) as i64 * self.height.max(0) as i64
+    }
+}
+
+/// Intersection of a region with a monitor's bounds (virtual desktop space)
+fn monitor_intersection(region: &CaptureRegion, monitor: &MonitorInfo) -> Option<CaptureRegion> {
+    region.intersection(&CaptureRegion {
+        x: monitor.x,
+        y: monitor.y,
+        width: monitor.width as i32,
+        height: monitor.height as i32,
+    })
+}
+
 /// Capture a specific region of the screen (multi-monitor aware)
 /// Coordinates are in virtual desktop space (can be negative for left-of-primary monitors)
-pub async fn capture_region(region: CaptureRegion) -> Result<Vec<u8>, String> {
-    // Get all monitors
-    let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+pub async fn capture_region(
+    backend: &dyn CaptureBackend,
+    region: CaptureRegion,
+    options: &StitchOptions,
+    format: &OutputFormat,
+) -> Result<Vec<u8>, String> {
+    let image = capture_region_raw(backend, region, options)?;
+
+    encode_image(&image, format)
+}
+
+/// Capture a region as a single image, stitching together every monitor it touches.
+/// Areas between monitors are painted with `options.fill`.
+pub fn capture_region_raw(
+    backend: &dyn CaptureBackend,
+    region: CaptureRegion,
+    options: &StitchOptions,
+) -> Result<RgbaImage, String> {
+    use image::imageops::{self, FilterType};
+
+    if region.width <= 0 || region.height <= 0 {
+        return Err("Invalid capture region: empty size".to_string());
+    }
 
-    // Find the monitor containing the center of the region
-    let center_x = region.x + region.width / 2;
-    let center_y = region.y + region.height / 2;
+    let monitors = backend.monitors()?;
 
-    let monitor = monitors
+    // Find every monitor the region touches, largest overlap last
+    let mut parts: Vec<(&MonitorInfo, CaptureRegion)> = monitors
         .iter()
-        .find(|m| {
-            let x = m.x().unwrap_or(0);
-            let y = m.y().unwrap_or(0);
-            let width = m.width().unwrap_or(0);
-            let height = m.height().unwrap_or(0);
-            let m_right = x + width as i32;
-            let m_bottom = y + height as i32;
-            center_x >= x && center_x < m_right && center_y >= y && center_y < m_bottom
-        })
-        .or_else(|| monitors.iter().find(|m| m.is_primary().unwrap_or(false)))
-        .ok_or_else(|| "No suitable monitor found".to_string())?;
-
-    // Convert region coordinates to monitor-local space and scale by DPI factor
-
-    // The region coordinates (from frontend) and monitor coordinates (from xcap) are likely
-    // both in physical pixels (for xcap 0.8.1), so significant scaling is handled by the frontend/xcap consistency.
-    // However, strictly speaking, we just want the offset.
-    // Note: If xcap 0.8.1 returns physical coordinates, we do NOT multiply by scale_factor.
-    let local_region = CaptureRegion {
-        x: (region.x - monitor.x().unwrap_or(0)),
-        y: (region.y - monitor.y().unwrap_or(0)),
-        width: region.width,
-        height: region.height,
+        .filter_map(|m| monitor_intersection(&region, m).map(|part| (m, part)))
+        .collect();
+    parts.sort_by_key(|(_, part)| part.width as i64 * part.height as i64);
+
+    // The monitor holding most of the region decides the output resolution
+    let anchor = parts
+        .last()
+        .map(|(m, _)| *m)
+        .ok_or_else(|| "Capture region does not intersect any monitor".to_string())?;
+
+    // Each monitor is resampled to the target scale factor on its own
+    let target_scale = options.checked_target_scale()?;
+    let scale_of = |monitor: &MonitorInfo| match target_scale {
+        Some(target) if monitor.scale_factor > 0.0 => target / monitor.scale_factor,
+        Some(target) => target,
+        None => 1.0,
     };
 
-    // Capture from the detected monitor
-    let full_image = monitor
-        .capture_image()
-        .map_err(|e| format!("Failed to capture screen: {}", e))?;
-
-    // Convert to RgbaImage (xcap 0.8.1 returns RgbaImage directly)
-    // let full_image = ... (removed manual conversion)
-
-    // Crop to the specified region (in local coordinates)
-    let cropped = crop_image(&full_image, local_region)?;
+    let x_spans: Vec<_> = parts
+        .iter()
+        .map(|(m, part)| (part.x, part.x + part.width, scale_of(m)))
+        .collect();
+    let y_spans: Vec<_> = parts
+        .iter()
+        .map(|(m, part)| (part.y, part.y + part.height, scale_of(m)))
+        .collect();
+    let region_right = region.x + region.width;
+    let region_bottom = region.y + region.height;
+    let xs = AxisMap::new(region.x, region_right, &x_spans, scale_of(anchor));
+    let ys = AxisMap::new(region.y, region_bottom, &y_spans, scale_of(anchor));
+
+    let canvas_width = xs.position(region_right).round().max(1.0);
+    let canvas_height = ys.position(region_bottom).round().max(1.0);
+    if canvas_width * canvas_height > MAX_CANVAS_PIXELS {
+        return Err(format!(
+            "Capture region is too large: {}x{} pixels",
+            canvas_width, canvas_height
+        ));
+    }
+    let mut canvas = RgbaImage::from_pixel(
+        canvas_width as u32,
+        canvas_height as u32,
+        image::Rgba(options.fill),
+    );
+
+    for (monitor, part) in parts {
+        // The region and xcap monitor bounds are both physical pixels, so only the offset matters
+        let local_region = CaptureRegion {
+            x: part.x - monitor.x,
+            y: part.y - monitor.y,
+            width: part.width,
+            height: part.height,
+        };
+
+        let frame = backend
+            .capture_monitor(monitor.id)
+            .map_err(|e| format!("Failed to capture screen: {}", e))?;
+        let mut tile = crop_image(&frame, local_region)?;
+
+        // Resample the tile to the monitor's own scale, in the slot its part takes in the canvas
+        let scale = scale_of(monitor);
+        let (offset_x, right) = xs.slot(part.x, part.x + part.width, scale);
+        let (offset_y, bottom) = ys.slot(part.y, part.y + part.height, scale);
+        let width = ((right - offset_x) as u32).max(1);
+        let height = ((bottom - offset_y) as u32).max(1);
+        if tile.dimensions() != (width, height) {
+            tile = imageops::resize(&tile, width, height, FilterType::Triangle);
+        }
+
+        let (offset_x, offset_y) = (offset_x as i64, offset_y as i64);
+        imageops::replace(&mut canvas, &tile, offset_x, offset_y);
+
+        if cfg!(debug_assertions) {
+            eprintln!(
+                "[capture_region] Monitor '{}' contributed {}x{} at ({},{})",
+                monitor.name,
+                tile.width(),
+                tile.height(),
+                offset_x,
+                offset_y
+            );
+        }
+    }
 
-    // Encode as BMP
-    encode_as_bmp(&cropped)
+    Ok(canvas)
 }
 
 /// Capture the full screen (primary monitor)
-pub async fn capture_full_screen() -> Result<Vec<u8>, String> {
-    let rgba_image = capture_full_screen_raw().await?;
-    encode_as_bmp(&rgba_image)
+pub async fn capture_full_screen(
+    backend: &dyn CaptureBackend,
+    format: &OutputFormat,
+) -> Result<Vec<u8>, String> {
+    let rgba_image = capture_full_screen_raw(backend).await?;
+    encode_image(&rgba_image, format)
 }
 
 /// Capture the full screen raw image (primary monitor)
-pub async fn capture_full_screen_raw() -> Result<RgbaImage, String> {
-    let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+pub async fn capture_full_screen_raw(backend: &dyn CaptureBackend) -> Result<RgbaImage, String> {
+    let monitors = backend.monitors()?;
 
     let monitor = monitors
-        .into_iter()
-        .find(|m| m.is_primary().unwrap_or(false))
+        .iter()
+        .find(|m| m.is_primary)
         .ok_or_else(|| "No primary monitor found".to_string())?;
 
-    monitor
-        .capture_image()
+    backend
+        .capture_monitor(monitor.id)
         .map_err(|e| format!("Failed to capture screen: {}", e))
 }
 
+/// Capture the primary monitor along with its geometry
+/// Returns the image and the monitor it was taken from
+pub fn capture_primary_monitor_raw(
+    backend: &dyn CaptureBackend,
+) -> Result<(RgbaImage, MonitorInfo), String> {
+    let monitors = backend.monitors()?;
+
+    let monitor = monitors
+        .iter()
+        .find(|m| m.is_primary)
+        .or_else(|| monitors.first())
+        .ok_or_else(|| "No primary monitor found".to_string())?;
+
+    let rgba_image = backend.capture_monitor(monitor.id)?;
+
+    Ok((rgba_image, monitor.clone()))
+}
+
 /// Capture the monitor containing the given point (cursor position)
-/// Returns (image, monitor_x, monitor_y, monitor_width, monitor_height, scale_factor)
+/// Returns the image and the monitor it was taken from
 pub fn capture_monitor_at_point_raw(
+    backend: &dyn CaptureBackend,
     x: i32,
     y: i32,
-) -> Result<(RgbaImage, i32, i32, u32, u32, f64), String> {
-    let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+) -> Result<(RgbaImage, MonitorInfo), String> {
+    let monitors = backend.monitors()?;
 
     // Find monitor containing the point
-    let monitor = monitors
-        .iter()
-        .find(|m| {
-            let mx = m.x().unwrap_or(0);
-            let my = m.y().unwrap_or(0);
-            let width = m.width().unwrap_or(0);
-            let height = m.height().unwrap_or(0);
-            let m_right = mx + width as i32;
-            let m_bottom = my + height as i32;
-            x >= mx && x < m_right && y >= my && y < m_bottom
-        })
-        .or_else(|| monitors.iter().find(|m| m.is_primary().unwrap_or(false)))
-        .ok_or_else(|| "No suitable monitor found".to_string())?;
+    let monitor =
+        monitor_at_point(&monitors, x, y).ok_or_else(|| "No suitable monitor found".to_string())?;
 
     if cfg!(debug_assertions) {
         eprintln!(
             "[capture_monitor_at_point] Point ({},{}) -> Monitor '{}' at ({},{}) {}x{}",
-            x,
-            y,
-            monitor.name().unwrap_or_default(),
-            monitor.x().unwrap_or(0),
-            monitor.y().unwrap_or(0),
-            monitor.width().unwrap_or(0),
-            monitor.height().unwrap_or(0)
+            x, y, monitor.name, monitor.x, monitor.y, monitor.width, monitor.height
         );
     }
 
-    let rgba_image = monitor
-        .capture_image()
-        .map_err(|e| format!("Failed to capture monitor: {}", e))?;
+    let rgba_image = backend.capture_monitor(monitor.id)?;
 
-    Ok((
-        rgba_image,
-        monitor.x().unwrap_or(0),
-        monitor.y().unwrap_or(0),
-        monitor.width().unwrap_or(0),
-        monitor.height().unwrap_or(0),
-        monitor.scale_factor().unwrap_or(1.0) as f64,
-    ))
+    Ok((rgba_image, monitor.clone()))
 }
 
-/// Capture a specific monitor
+/// Capture a specific monitor (by enumeration index)
 #[allow(dead_code)]
-pub async fn capture_monitor(monitor_id: i32) -> Result<Vec<u8>, String> {
-    let monitors = Monitor::all().map_err(|e| format!("Failed to get monitors: {}", e))?;
+pub async fn capture_monitor(
+    backend: &dyn CaptureBackend,
+    monitor_id: i32,
+    format: &OutputFormat,
+) -> Result<Vec<u8>, String> {
+    let monitors = backend.monitors()?;
 
     let monitor = monitors
-        .into_iter()
-        .nth(monitor_id as usize)
+        .get(monitor_id as usize)
         .ok_or_else(|| format!("Monitor {} not found", monitor_id))?;
 
-    let rgba_image = monitor
-        .capture_image()
-        .map_err(|e| format!("Failed to capture monitor: {}", e))?;
+    let rgba_image = backend.capture_monitor(monitor.id)?;
 
-    encode_as_bmp(&rgba_image)
+    encode_image(&rgba_image, format)
 }
 
-/// Encode an image as BMP bytes (uncompressed, faster than PNG)
-fn encode_as_bmp(image: &RgbaImage) -> Result<Vec<u8>, String> {
-    use image::codecs::bmp::BmpEncoder;
-    use image::ImageEncoder;
+/// Placement of a single monitor inside a virtual desktop capture
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct MonitorLayout {
+    pub id: u32,
+    pub name: String,
+    /// Offset of the monitor's top-left corner inside the composite image
+    pub offset_x: i32,
+    pub offset_y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    pub is_primary: bool,
+}
+
+/// Layout manifest describing how monitors are arranged in a virtual desktop capture
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct DesktopLayout {
+    /// Virtual desktop coordinates of the composite image's top-left corner
+    pub origin_x: i32,
+    pub origin_y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub monitors: Vec<MonitorLayout>,
+}
+
+/// Bounding box (union) of all monitor rects in virtual desktop space
+pub fn virtual_desktop_bounds(monitors: &[MonitorInfo]) -> Option<CaptureRegion> {
+    let left = monitors.iter().map(|m| m.x).min()?;
+    let top = monitors.iter().map(|m| m.y).min()?;
+    let right = monitors.iter().map(|m| m.x + m.width as i32).max()?;
+    let bottom = monitors.iter().map(|m| m.y + m.height as i32).max()?;
+
+    Some(CaptureRegion {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    })
+}
+
+/// Build the layout manifest for a set of monitors
+pub fn desktop_layout(monitors: &[MonitorInfo]) -> Option<DesktopLayout> {
+    let bounds = virtual_desktop_bounds(monitors)?;
+
+    Some(DesktopLayout {
+        origin_x: bounds.x,
+        origin_y: bounds.y,
+        width: bounds.width as u32,
+        height: bounds.height as u32,
+        monitors: monitors
+            .iter()
+            .map(|m| MonitorLayout {
+                id: m.id,
+                name: m.name.clone(),
+                offset_x: m.x - bounds.x,
+                offset_y: m.y - bounds.y,
+                width: m.width,
+                height: m.height,
+                scale_factor: m.scale_factor,
+                is_primary: m.is_primary,
+            })
+            .collect(),
+    })
+}
 
-    let mut buffer = Cursor::new(Vec::new());
+/// Capture every monitor into one image covering the whole virtual desktop.
+/// Areas of the bounding box not covered by a monitor are painted with `fill`.
+pub fn capture_virtual_desktop_raw(
+    backend: &dyn CaptureBackend,
+    fill: [u8; 4],
+) -> Result<(RgbaImage, DesktopLayout), String> {
+    let monitors = backend.monitors()?;
+
+    let layout = desktop_layout(&monitors).ok_or_else(|| "No monitors found".to_string())?;
+    let bounds = CaptureRegion {
+        x: layout.origin_x,
+        y: layout.origin_y,
+        width: layout.width as i32,
+        height: layout.height as i32,
+    };
 
-    // Use BMP encoder for zero compression overhead
-    let encoder = BmpEncoder::new(&mut buffer);
+    // Keep physical pixels 1:1 so the layout offsets map directly onto the image
+    let options = StitchOptions {
+        fill,
+        target_scale: None,
+    };
+    let image = capture_region_raw(backend, bounds, &options)?;
 
-    encoder
-        .write_image(
-            image.as_raw(),
-            image.width(),
-            image.height(),
-            image::ExtendedColorType::Rgba8,
-        )
-        .map_err(|e| format!("Failed to encode image as BMP: {}", e))?;
+    Ok((image, layout))
+}
 
-    Ok(buffer.into_inner())
+/// Length of the little-endian u32 in front of the layout manifest in `capture_virtual_desktop`
+pub const LAYOUT_HEADER_LEN: usize = 4;
+
+/// Capture the full virtual desktop together with the layout it was taken with, in one buffer:
+/// the length of the layout JSON (little-endian u32), the layout JSON, then the encoded image
+pub async fn capture_virtual_desktop(
+    backend: &dyn CaptureBackend,
+    fill: [u8; 4],
+    format: &OutputFormat,
+) -> Result<Vec<u8>, String> {
+    let (image, layout) = capture_virtual_desktop_raw(backend, fill)?;
+    let layout = serde_json::to_vec(&layout).map_err(|e| e.to_string())?;
+    let image = encode_image(&image, format)?;
+
+    let mut buffer = Vec::with_capacity(LAYOUT_HEADER_LEN + layout.len() + image.len());
+    buffer.extend_from_slice(&(layout.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&layout);
+    buffer.extend_from_slice(&image);
+    Ok(buffer)
 }
 
 /// Crop an image to a specific region
-fn crop_image(image: &RgbaImage, region: CaptureRegion) -> Result<RgbaImage, String> {
+pub fn crop_image(image: &RgbaImage, region: CaptureRegion) -> Result<RgbaImage, String> {
     use image::imageops;
 
     let x = region.x.max(0) as u32;
@@ -217,8 +502,8 @@ fn crop_image(image: &RgbaImage, region: CaptureRegion) -> Result<RgbaImage, Str
     let height = region.height.max(1) as u32;
 
     // Ensure crop region is within image bounds
-    let max_width = (image.width() - x).min(width);
-    let max_height = (image.height() - y).min(height);
+    let max_width = image.width().saturating_sub(x).min(width);
+    let max_height = image.height().saturating_sub(y).min(height);
 
     if max_width == 0 || max_height == 0 {
         return Err("Invalid crop region: outside image bounds".to_string());
@@ -228,3 +513,257 @@ fn crop_image(image: &RgbaImage, region: CaptureRegion) -> Result<RgbaImage, Str
     let sub_image = imageops::crop_imm(image, x, y, max_width, max_height);
     Ok(sub_image.to_image())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture_backend::MemoryBackend;
+
+    fn monitor(id: u32, x: i32, y: i32, width: u32, height: u32, is_primary: bool) -> MonitorInfo {
+        MonitorInfo {
+            id,
+            name: format!("Virtual-{}", id),
+            x,
+            y,
+            width,
+            height,
+            scale_factor: 1.0,
+            is_primary,
+        }
+    }
+
+    fn dual_layout() -> Vec<MonitorInfo> {
+        // Secondary monitor sits to the left of the primary one
+        vec![
+            monitor(1, 0, 0, 1920, 1080, true),
+            monitor(2, -1280, 0, 1280, 1024, false),
+        ]
+    }
+
+    #[test]
+    fn monitor_at_point_picks_containing_monitor() {
+        let monitors = dual_layout();
+
+        assert_eq!(monitor_at_point(&monitors, 100, 100).unwrap().id, 1);
+        assert_eq!(monitor_at_point(&monitors, -1, 500).unwrap().id, 2);
+        // Right/bottom edges are exclusive
+        assert_eq!(monitor_at_point(&monitors, -1280, 1023).unwrap().id, 2);
+    }
+
+    #[test]
+    fn monitor_at_point_falls_back_to_primary() {
+        let monitors = dual_layout();
+
+        assert_eq!(monitor_at_point(&monitors, -500, 2000).unwrap().id, 1);
+        assert!(monitor_at_point(&[], 0, 0).is_none());
+    }
+
+    #[test]
+    fn capture_region_inside_one_monitor_keeps_its_pixels() {
+        let backend = MemoryBackend::from_monitors(dual_layout());
+        let region = CaptureRegion {
+            x: -600,
+            y: 200,
+            width: 300,
+            height: 100,
+        };
+
+        let bytes = tauri::async_runtime::block_on(capture_region(
+            &backend,
+            region,
+            &StitchOptions::default(),
+            &OutputFormat::Bmp,
+        ))
+        .unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+
+        assert_eq!(image.dimensions(), (300, 100));
+        assert_eq!(*image.get_pixel(0, 0), MemoryBackend::fill_for(2));
+    }
+
+    #[test]
+    fn capture_monitor_at_point_reports_geometry() {
+        let backend = MemoryBackend::from_monitors(dual_layout());
+
+        let (image, monitor) = capture_monitor_at_point_raw(&backend, -10, 10).unwrap();
+
+        assert_eq!(
+            (monitor.x, monitor.y, monitor.width, monitor.height),
+            (-1280, 0, 1280, 1024)
+        );
+        assert_eq!(image.dimensions(), (1280, 1024));
+    }
+
+    #[test]
+    fn capture_region_stitches_across_monitors() {
+        let backend = MemoryBackend::from_monitors(dual_layout());
+        let region = CaptureRegion {
+            x: -100,
+            y: 1000,
+            width: 200,
+            height: 50,
+        };
+        let options = StitchOptions {
+            fill: [1, 2, 3, 255],
+            target_scale: None,
+        };
+
+        let image = capture_region_raw(&backend, region, &options).unwrap();
+
+        assert_eq!(image.dimensions(), (200, 50));
+        assert_eq!(*image.get_pixel(0, 0), MemoryBackend::fill_for(2));
+        assert_eq!(*image.get_pixel(199, 0), MemoryBackend::fill_for(1));
+        // Below the shorter secondary monitor (height 1024) nothing is captured
+        assert_eq!(*image.get_pixel(0, 49), image::Rgba([1, 2, 3, 255]));
+        assert_eq!(*image.get_pixel(199, 49), MemoryBackend::fill_for(1));
+    }
+
+    #[test]
+    fn capture_region_normalises_mixed_scale_factors() {
+        let mut hidpi = monitor(2, 1920, 0, 3840, 2160, false);
+        hidpi.scale_factor = 2.0;
+        let backend = MemoryBackend::from_monitors(vec![monitor(1, 0, 0, 1920, 1080, true), hidpi]);
+        let region = CaptureRegion {
+            x: 1820,
+            y: 0,
+            width: 300,
+            height: 100,
+        };
+        let options = StitchOptions {
+            fill: [0, 0, 0, 0],
+            target_scale: Some(1.0),
+        };
+
+        let image = capture_region_raw(&backend, region, &options).unwrap();
+
+        // The 1x monitor's 100x100 stay as they are; the 2x monitor's 200x100 are halved to
+        // 100x50 and start right at the seam, with fill below them
+        assert_eq!(image.dimensions(), (200, 100));
+        for y in [0, 49, 50, 99] {
+            for x in 0..200 {
+                let expected = match (x < 100, y < 50) {
+                    (true, _) => MemoryBackend::fill_for(1),
+                    (false, true) => MemoryBackend::fill_for(2),
+                    (false, false) => image::Rgba([0, 0, 0, 0]),
+                };
+                assert_eq!(*image.get_pixel(x, y), expected, "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn capture_region_fits_each_monitor_to_its_slot() {
+        // 1x monitor next to a 2x monitor, both brought to 2x: the 1x monitor is doubled to
+        // 300x200 and the 2x monitor kept at 100x100, starting where the 1x monitor ends
+        let mut hidpi = monitor(2, 150, 0, 100, 100, false);
+        hidpi.scale_factor = 2.0;
+        let backend = MemoryBackend::from_monitors(vec![monitor(1, 0, 0, 150, 100, true), hidpi]);
+        let region = CaptureRegion {
+            x: 0,
+            y: 0,
+            width: 250,
+            height: 100,
+        };
+        let options = StitchOptions {
+            fill: [0, 0, 0, 0],
+            target_scale: Some(2.0),
+        };
+
+        let image = capture_region_raw(&backend, region, &options).unwrap();
+
+        assert_eq!(image.dimensions(), (400, 200));
+        for x in 0..400 {
+            let (top, bottom) = if x < 300 {
+                (MemoryBackend::fill_for(1), MemoryBackend::fill_for(1))
+            } else {
+                (MemoryBackend::fill_for(2), image::Rgba([0, 0, 0, 0]))
+            };
+            assert_eq!(*image.get_pixel(x, 0), top, "column {}", x);
+            assert_eq!(*image.get_pixel(x, 99), top, "column {}", x);
+            assert_eq!(*image.get_pixel(x, 199), bottom, "column {}", x);
+        }
+    }
+
+    #[test]
+    fn capture_region_bounds_the_target_scale_and_canvas() {
+        let backend = MemoryBackend::from_monitors(dual_layout());
+        let region = CaptureRegion {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 50,
+        };
+        let with_scale = |target_scale| StitchOptions {
+            fill: [0, 0, 0, 0],
+            target_scale: Some(target_scale),
+        };
+
+        for invalid in [f64::NAN, f64::INFINITY, 0.0, -1.0] {
+            assert!(capture_region_raw(&backend, region, &with_scale(invalid)).is_err());
+        }
+        let image = capture_region_raw(&backend, region, &with_scale(1e9)).unwrap();
+        assert_eq!(image.dimensions(), (800, 400));
+
+        let huge = CaptureRegion {
+            x: 0,
+            y: 0,
+            width: 100_000,
+            height: 100_000,
+        };
+        assert!(capture_region_raw(&backend, huge, &StitchOptions::default()).is_err());
+    }
+
+    #[test]
+    fn capture_region_outside_all_monitors_fails() {
+        let backend = MemoryBackend::from_monitors(dual_layout());
+        let region = CaptureRegion {
+            x: 5000,
+            y: 5000,
+            width: 10,
+            height: 10,
+        };
+
+        assert!(capture_region_raw(&backend, region, &StitchOptions::default()).is_err());
+    }
+
+    #[test]
+    fn virtual_desktop_capture_covers_all_monitors() {
+        let backend = MemoryBackend::from_monitors(dual_layout());
+
+        let (image, layout) = capture_virtual_desktop_raw(&backend, [0, 0, 0, 255]).unwrap();
+
+        assert_eq!(image.dimensions(), (3200, 1080));
+        assert_eq!((layout.origin_x, layout.origin_y), (-1280, 0));
+
+        let primary = layout.monitors.iter().find(|m| m.is_primary).unwrap();
+        assert_eq!((primary.offset_x, primary.offset_y), (1280, 0));
+        assert_eq!(*image.get_pixel(1280, 0), MemoryBackend::fill_for(1));
+        assert_eq!(*image.get_pixel(0, 1079), image::Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn virtual_desktop_buffer_leads_with_its_layout() {
+        let backend = MemoryBackend::from_monitors(dual_layout());
+
+        let bytes = tauri::async_runtime::block_on(capture_virtual_desktop(
+            &backend,
+            [0, 0, 0, 255],
+            &OutputFormat::Bmp,
+        ))
+        .unwrap();
+
+        let mut len = [0u8; LAYOUT_HEADER_LEN];
+        len.copy_from_slice(&bytes[..LAYOUT_HEADER_LEN]);
+        let image_start = LAYOUT_HEADER_LEN + u32::from_le_bytes(len) as usize;
+        let layout: serde_json::Value =
+            serde_json::from_slice(&bytes[LAYOUT_HEADER_LEN..image_start]).unwrap();
+        assert_eq!(
+            (layout["width"].as_u64(), layout["origin_x"].as_i64()),
+            (Some(3200), Some(-1280))
+        );
+        assert_eq!(layout["monitors"].as_array().unwrap().len(), 2);
+
+        let image = image::load_from_memory(&bytes[image_start..]).unwrap();
+        assert_eq!((image.width(), image.height()), (3200, 1080));
+    }
+}
@@ -0,0 +1,512 @@
+// JustSnap - Settings Module
+// Versioned settings file owned by the backend and shared with every window
+
+use crate::history::{CaptureHistory, Retention};
+use crate::hotkeys::{Hotkey, HotkeyAction, HotkeyBinding, HotkeyRegistry};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager};
+
+/// Current settings schema version
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Event broadcast to every window when settings change
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+    #[default]
+    System,
+}
+
+/// User settings (mirrors the settings slice of the frontend store)
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub theme: Theme,
+    /// Directory for saved / auto-saved captures (None = ask every time)
+    pub save_path: Option<String>,
+    pub auto_save: bool,
+    /// File name template for auto-saved captures (see `auto_save::SaveTemplate`)
+    pub auto_save_template: String,
+    /// Shortcut string per hotkey action (e.g. "Ctrl+Shift+S")
+    pub hotkeys: BTreeMap<HotkeyAction, String>,
+    /// Editor toolbar layout, owned by the frontend
+    pub toolbar_config: Option<Value>,
+    pub default_tool: String,
+    pub color_palette: Vec<String>,
+    pub default_stroke_width: f64,
+    pub auto_close_after_copy: bool,
+    pub auto_close_after_save: bool,
+    pub start_at_login: bool,
+    /// Keep at most this many captures in the history (None = no limit)
+    pub history_max_entries: Option<u32>,
+    /// Forget history older than this many days (None = no limit)
+    pub history_max_age_days: Option<u32>,
+    /// Listen for JSON-RPC clients on a local socket (see `control_server`)
+    pub control_server: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            theme: Theme::System,
+            save_path: None,
+            auto_save: false,
+            auto_save_template: crate::auto_save::DEFAULT_TEMPLATE.to_string(),
+            hotkeys: BTreeMap::from([(HotkeyAction::Capture, "Ctrl+Shift+S".to_string())]),
+            toolbar_config: None,
+            default_tool: "rectangle".to_string(),
+            color_palette: ["#FF0000", "#00FF00", "#0000FF", "#000000"]
+                .iter()
+                .map(|color| color.to_string())
+                .collect(),
+            default_stroke_width: 4.0,
+            auto_close_after_copy: true,
+            auto_close_after_save: true,
+            start_at_login: false,
+            history_max_entries: Some(500),
+            history_max_age_days: Some(90),
+            control_server: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn history_retention(&self) -> Retention {
+        Retention {
+            max_entries: self.history_max_entries,
+            max_age_days: self.history_max_age_days,
+        }
+    }
+}
+
+// ============================================
+// Migrations
+// ============================================
+
+/// Upgrade a settings document of any older version to `SETTINGS_VERSION`
+fn migrate(mut value: Value) -> Value {
+    let mut version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+
+    while version < SETTINGS_VERSION {
+        value = match version {
+            0 => migrate_v0_to_v1(value),
+            _ => value,
+        };
+        version += 1;
+
+        if let Some(object) = value.as_object_mut() {
+            object.insert("version".to_string(), Value::from(version));
+        }
+
+        if cfg!(debug_assertions) {
+            println!("[Settings] Migrated settings to v{}", version);
+        }
+    }
+
+    value
+}
+
+/// v0 is the webview's persisted store state: camelCase keys and no version
+fn migrate_v0_to_v1(value: Value) -> Value {
+    let Value::Object(object) = value else {
+        return Value::Object(Map::new());
+    };
+
+    Value::Object(
+        object
+            .into_iter()
+            .map(|(key, value)| (camel_to_snake(&key), value))
+            .collect(),
+    )
+}
+
+fn camel_to_snake(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Build settings from a document of any version. Each field is read on its own: missing or
+/// invalid ones (e.g. an enum value written by a newer build) keep their defaults, and hotkeys
+/// of unknown actions are skipped.
+fn settings_from_value(value: Value) -> Settings {
+    let defaults = match serde_json::to_value(Settings::default()) {
+        Ok(Value::Object(defaults)) => defaults,
+        _ => return Settings::default(),
+    };
+    let Value::Object(fields) = migrate(value) else {
+        return Settings::default();
+    };
+
+    let mut merged = defaults.clone();
+    for (key, value) in fields {
+        if key == "version" || !defaults.contains_key(&key) {
+            continue;
+        }
+        let value = match (key.as_str(), value) {
+            ("hotkeys", Value::Object(hotkeys)) => Value::Object(known_hotkeys(hotkeys)),
+            (_, value) => value,
+        };
+
+        // Check the field against the defaults alone, so one bad field cannot spoil the rest
+        let mut candidate = defaults.clone();
+        candidate.insert(key.clone(), value.clone());
+        match serde_json::from_value::<Settings>(Value::Object(candidate)) {
+            Ok(_) => {
+                merged.insert(key, value);
+            }
+            Err(e) => eprintln!("[Error] Ignoring invalid setting '{}': {}", key, e),
+        }
+    }
+
+    let mut settings: Settings = serde_json::from_value(Value::Object(merged)).unwrap_or_default();
+    settings.version = SETTINGS_VERSION;
+    settings
+}
+
+/// Hotkeys of the actions this build knows
+fn known_hotkeys(hotkeys: Map<String, Value>) -> Map<String, Value> {
+    hotkeys
+        .into_iter()
+        .filter(|(action, _)| {
+            serde_json::from_value::<HotkeyAction>(Value::String(action.clone())).is_ok()
+        })
+        .collect()
+}
+
+// ============================================
+// Settings File
+// ============================================
+
+/// Default location: `<config dir>/JustSnap/settings.json`
+pub fn default_settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("JustSnap").join(SETTINGS_FILE))
+}
+
+/// Read the settings file. Returns None if it does not exist yet.
+/// A file that is not a JSON object is moved aside to `settings.json.bak`.
+fn read_settings(path: &Path) -> Option<Settings> {
+    let contents = std::fs::read_to_string(path).ok()?;
+
+    match serde_json::from_str::<Value>(&contents) {
+        Ok(value) if value.is_object() => Some(settings_from_value(value)),
+        parsed => {
+            let reason = parsed.map_or_else(|e| e.to_string(), |_| "not an object".to_string());
+            eprintln!(
+                "[Error] Failed to read settings, using defaults: {}",
+                reason
+            );
+            let _ = std::fs::rename(path, path.with_extension("json.bak"));
+            None
+        }
+    }
+}
+
+/// Write the settings file atomically (temp file + rename)
+fn write_settings(path: &Path, settings: &Settings) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, json).map_err(|e| format!("Failed to write settings: {}", e))?;
+    std::fs::rename(&temp_path, path).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+/// Managed settings state backed by the settings file
+pub struct SettingsStore {
+    path: Option<PathBuf>,
+    settings: Mutex<Settings>,
+    /// True until settings are first written, so the webview's old local copy can be imported once
+    legacy_import_allowed: AtomicBool,
+}
+
+impl SettingsStore {
+    /// Load settings from `path` (defaults when missing; in-memory only when None)
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let existing = path.as_deref().and_then(read_settings);
+
+        if cfg!(debug_assertions) {
+            println!(
+                "[Settings] Loaded from {:?} (file found: {})",
+                path,
+                existing.is_some()
+            );
+        }
+
+        Self {
+            path,
+            legacy_import_allowed: AtomicBool::new(existing.is_none()),
+            settings: Mutex::new(existing.unwrap_or_default()),
+        }
+    }
+
+    /// True while the webview's old settings may still be imported over the defaults
+    pub fn legacy_import_pending(&self) -> bool {
+        self.legacy_import_allowed.load(Ordering::SeqCst)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Settings> {
+        self.settings.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get(&self) -> Settings {
+        self.lock().clone()
+    }
+
+    fn replace(&self, current: &mut Settings, settings: Settings) -> Result<(), String> {
+        if let Some(ref path) = self.path {
+            write_settings(path, &settings)?;
+        }
+        *current = settings;
+        self.legacy_import_allowed.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Apply a partial update (top-level fields of `patch` replace the current ones) and save.
+    /// Returns the previous and the new settings.
+    pub fn update(&self, patch: Value) -> Result<(Settings, Settings), String> {
+        let Value::Object(patch) = patch else {
+            return Err("Settings update must be an object".to_string());
+        };
+
+        let mut current = self.lock();
+
+        let mut merged = serde_json::to_value(&*current).map_err(|e| e.to_string())?;
+        if let Some(object) = merged.as_object_mut() {
+            for (key, value) in patch {
+                if key != "version" {
+                    object.insert(key, value);
+                }
+            }
+        }
+        // An update is checked as a whole, so a bad value is reported rather than dropped
+        let updated: Settings =
+            serde_json::from_value(merged).map_err(|e| format!("Invalid settings: {}", e))?;
+
+        let previous = current.clone();
+        self.replace(&mut current, updated.clone())?;
+
+        Ok((previous, updated))
+    }
+
+    /// Import the settings the webview kept before the backend owned them (migrated from v0).
+    /// Ignored once a settings file exists; returns the previous and new settings when applied.
+    pub fn import_legacy(&self, state: Value) -> Result<Option<(Settings, Settings)>, String> {
+        if !self.legacy_import_pending() {
+            return Ok(None);
+        }
+
+        let mut current = self.lock();
+        let imported = settings_from_value(state);
+
+        let previous = current.clone();
+        self.replace(&mut current, imported.clone())?;
+
+        Ok(Some((previous, imported)))
+    }
+}
+
+// ============================================
+// Applying Settings
+// ============================================
+
+/// Apply the settings that live outside the webview (hotkeys, autostart, history retention,
+/// control server).
+/// With `previous`, only what changed is touched.
+pub fn apply_settings(app: &AppHandle, settings: &Settings, previous: Option<&Settings>) {
+    use crate::hotkeys::{register_action_hotkeys, unregister_action_hotkey};
+
+    // Hotkeys: drop removed bindings, (re)bind new or changed ones
+    let old_hotkeys = previous.map(|p| &p.hotkeys);
+    for action in HotkeyAction::ALL {
+        if settings.hotkeys.contains_key(&action) {
+            continue;
+        }
+        if old_hotkeys.map_or(true, |old| old.contains_key(&action)) {
+            let _ = unregister_action_hotkey(app, action);
+        }
+    }
+
+    // Skip shortcuts the settings window already bound live through `register_hotkey`
+    let bound: Vec<HotkeyBinding> = app.state::<HotkeyRegistry>().bindings();
+    let changed: Vec<(HotkeyAction, Hotkey)> = settings
+        .hotkeys
+        .iter()
+        .filter(|(action, shortcut)| {
+            old_hotkeys.map_or(true, |old| old.get(*action) != Some(*shortcut))
+        })
+        .filter(|(action, shortcut)| {
+            !bound
+                .iter()
+                .any(|binding| binding.action == **action && &binding.shortcut == *shortcut)
+        })
+        .map(|(action, shortcut)| (*action, Hotkey::from_shortcut_string(shortcut)))
+        .collect();
+
+    if !changed.is_empty() {
+        for registration in register_action_hotkeys(app, changed) {
+            if registration.error.is_none() && cfg!(debug_assertions) {
+                println!(
+                    "[Hotkey] ✓ {:?} bound to {}",
+                    registration.action, registration.shortcut
+                );
+            }
+        }
+    }
+
+    // Autostart. At startup it waits for the webview's old settings to be imported, so the
+    // defaults do not switch off an autostart enabled there.
+    let import_pending = app
+        .try_state::<SettingsStore>()
+        .is_some_and(|store| store.legacy_import_pending());
+    if previous.map_or(!import_pending, |p| {
+        p.start_at_login != settings.start_at_login
+    }) {
+        use tauri_plugin_autostart::ManagerExt;
+
+        let autolaunch = app.autolaunch();
+        if autolaunch.is_enabled().ok() != Some(settings.start_at_login) {
+            let result = if settings.start_at_login {
+                autolaunch.enable()
+            } else {
+                autolaunch.disable()
+            };
+            if let Err(e) = result {
+                eprintln!("[Error] Failed to update autostart: {}", e);
+            }
+        }
+    }
+
+    // History retention
+    let retention = settings.history_retention();
+    if previous.map_or(true, |p| p.history_retention() != retention) {
+        if let Some(history) = app.try_state::<CaptureHistory>() {
+            let now = chrono::Utc::now().timestamp_millis();
+            if let Err(e) = history.apply_retention(retention, now) {
+                eprintln!("[Error] {}", e);
+            }
+        }
+    }
+
+    // Control server
+    if previous.map_or(settings.control_server, |p| {
+        p.control_server != settings.control_server
+    }) {
+        if let Some(server) = app.try_state::<crate::control_server::ControlServer>() {
+            server.set_enabled(app, settings.control_server);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_webview_state() {
+        let legacy = serde_json::json!({
+            "savePath": "/home/me/Pictures",
+            "autoSave": true,
+            "hotkeys": { "capture": "Alt+S", "ocr": "Alt+O" },
+            "defaultStrokeWidth": 6,
+            "startAtLogin": true
+        });
+
+        let settings = settings_from_value(legacy);
+
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.save_path.as_deref(), Some("/home/me/Pictures"));
+        assert!(settings.auto_save && settings.start_at_login);
+        assert_eq!(settings.hotkeys[&HotkeyAction::Ocr], "Alt+O");
+        assert_eq!(settings.default_stroke_width, 6.0);
+        // Missing fields keep their defaults
+        assert_eq!(settings.default_tool, "rectangle");
+    }
+
+    #[test]
+    fn updates_are_saved_and_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        let store = SettingsStore::load(Some(path.clone()));
+        assert_eq!(store.get(), Settings::default());
+
+        let (previous, updated) = store
+            .update(serde_json::json!({ "auto_save": true, "theme": "dark" }))
+            .unwrap();
+        assert!(!previous.auto_save);
+        assert!(updated.auto_save);
+        assert!(store
+            .update(serde_json::json!({ "theme": "neon" }))
+            .is_err());
+
+        let reloaded = SettingsStore::load(Some(path.clone()));
+        assert_eq!(reloaded.get().theme, Theme::Dark);
+        assert!(reloaded.get().auto_save);
+
+        // A settings file exists now, so old webview state is not imported over it
+        let legacy = serde_json::json!({ "autoSave": false });
+        assert_eq!(reloaded.import_legacy(legacy).unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_fields_keep_their_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        let written_by_newer_build = serde_json::json!({
+            "version": 1,
+            "theme": "neon",
+            "auto_save": true,
+            "default_stroke_width": "thick",
+            "hotkeys": { "capture": "Alt+S", "record_video": "Alt+V" }
+        });
+        std::fs::write(&path, written_by_newer_build.to_string()).unwrap();
+
+        let settings = SettingsStore::load(Some(path.clone())).get();
+
+        assert_eq!(settings.theme, Theme::System);
+        assert_eq!(settings.default_stroke_width, 4.0);
+        assert!(settings.auto_save);
+        assert_eq!(
+            settings.hotkeys,
+            BTreeMap::from([(HotkeyAction::Capture, "Alt+S".to_string())])
+        );
+        // The file was readable, so it is left where it is
+        assert!(!path.with_extension("json.bak").exists());
+    }
+
+    #[test]
+    fn corrupt_file_falls_back_to_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let store = SettingsStore::load(Some(path.clone()));
+
+        assert_eq!(store.get(), Settings::default());
+        assert!(path.with_extension("json.bak").exists());
+    }
+}
@@ -0,0 +1,111 @@
+// JustSnap - Single Instance Module
+// Keeps one JustSnap running: later launches hand their arguments to it
+// (tauri-plugin-single-instance), which acts on them here
+
+use crate::hotkeys::HotkeyAction;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+// ============================================
+// Acting on Arguments
+// ============================================
+
+/// What a set of launch arguments asks for
+#[derive(Debug, Default, PartialEq)]
+pub struct LaunchRequest {
+    /// `--capture`, `--full-screen`, ... (any hotkey action as a flag)
+    pub actions: Vec<HotkeyAction>,
+    /// Image files to open
+    pub files: Vec<PathBuf>,
+}
+
+impl LaunchRequest {
+    /// Read the arguments (without the program name), resolving files against `cwd`
+    pub fn parse(args: &[String], cwd: Option<&Path>) -> Self {
+        let mut request = Self::default();
+
+        for arg in args {
+            if let Some(flag) = arg.strip_prefix("--") {
+                let name = serde_json::Value::String(flag.replace('-', "_"));
+                if let Ok(action) = serde_json::from_value(name) {
+                    request.actions.push(action);
+                }
+                // Other flags (e.g. `--silence` from autostart) mean nothing to a running instance
+                continue;
+            }
+
+            let path = match cwd {
+                Some(cwd) => cwd.join(arg),
+                None => PathBuf::from(arg),
+            };
+            if path.is_file() {
+                request.files.push(path);
+            }
+        }
+        request
+    }
+}
+
+/// Act on the arguments of a later launch: run its actions and open its files.
+/// A plain second launch brings the welcome window forward.
+pub fn handle_args(app: &AppHandle, args: &[String], cwd: Option<&Path>) {
+    let request = LaunchRequest::parse(args, cwd);
+    if cfg!(debug_assertions) {
+        println!("[Instance] Forwarded {:?} -> {:?}", args, request);
+    }
+
+    if request.actions.is_empty() && request.files.is_empty() {
+        if let Some(window) = app.get_webview_window("welcome") {
+            let _ = window.show();
+            let _ = window.unminimize();
+            let _ = window.set_focus();
+        }
+        return;
+    }
+
+    for action in request.actions {
+        crate::hotkeys::trigger_action(app, action);
+    }
+
+    if !request.files.is_empty() {
+        use crate::image_input::{open_files, OpenTarget};
+        if let Err(e) = open_files(app, &request.files, OpenTarget::Editor) {
+            eprintln!("[Error] {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn reads_actions_and_files_from_arguments() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let file = "launch.png";
+        std::fs::write(dir.join(file), b"").unwrap();
+
+        let request = LaunchRequest::parse(
+            &strings(&[
+                "--silence",
+                "--full-screen",
+                file,
+                "missing.png",
+                "--capture",
+            ]),
+            Some(dir),
+        );
+        assert_eq!(
+            request,
+            LaunchRequest {
+                actions: vec![HotkeyAction::FullScreen, HotkeyAction::Capture],
+                files: vec![dir.join(file)],
+            }
+        );
+    }
+}
@@ -0,0 +1,658 @@
+// JustSnap - UI Elements Module
+// Parts of a window under the cursor (child windows, accessibility elements), nested from the
+// window inwards, so Smart Select can snap to a panel, a toolbar or a dialog
+
+use crate::screen_capture::CaptureRegion;
+use crate::window_list::WindowInfo;
+
+/// Deepest nesting followed, in case a tree points back at itself
+const MAX_DEPTH: usize = 32;
+
+/// A part of a window (physical pixels)
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct UiElement {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Kind of element: window class on Windows, accessibility role on Linux (e.g. "panel")
+    pub role: String,
+    pub name: String,
+}
+
+impl UiElement {
+    pub fn region(&self) -> CaptureRegion {
+        CaptureRegion {
+            x: self.x,
+            y: self.y,
+            width: self.width as i32,
+            height: self.height as i32,
+        }
+    }
+}
+
+/// The window under the cursor and the elements in it that hold the cursor
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct ElementStack {
+    pub window: WindowInfo,
+    /// Outermost first; each lies inside the one before it
+    pub elements: Vec<UiElement>,
+}
+
+/// Managed state kept between element lookups while the overlay is up: on Linux, the
+/// accessibility bus connection and the process id of each application seen on it
+#[derive(Default)]
+pub struct ElementLookup {
+    #[cfg(target_os = "linux")]
+    session: std::sync::Mutex<Option<atspi::Session>>,
+}
+
+impl ElementLookup {
+    /// Elements of `window` under the point. Without child windows or an accessibility tree
+    /// to walk, the stack is just the window.
+    pub fn elements_at_point(&self, window: WindowInfo, x: i32, y: i32) -> ElementStack {
+        let found = match find_elements(self, &window, x, y) {
+            Ok(found) => found,
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                    println!("[Elements] No elements for '{}': {}", window.title, e);
+                }
+                Vec::new()
+            }
+        };
+        ElementStack {
+            elements: nest(&window, found),
+            window,
+        }
+    }
+
+    /// Forget the connection and applications (when the overlay closes)
+    pub fn reset(&self) {
+        #[cfg(target_os = "linux")]
+        {
+            *self.session.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+    }
+}
+
+/// Clip each element to the one it sits in and drop the ones that add nothing to cycle
+/// through, i.e. those covering their parent exactly (e.g. layout containers)
+pub fn nest(window: &WindowInfo, found: Vec<UiElement>) -> Vec<UiElement> {
+    let mut parent = window.region();
+    let mut elements = Vec::new();
+
+    for element in found {
+        // Past an empty element, or one outside its parent, the chain is not trustworthy
+        let Some(region) = element.region().intersection(&parent) else {
+            break;
+        };
+        if region == parent {
+            continue;
+        }
+        parent = region;
+        elements.push(UiElement {
+            x: region.x,
+            y: region.y,
+            width: region.width as u32,
+            height: region.height as u32,
+            ..element
+        });
+    }
+    elements
+}
+
+// ============================================
+// Platform Element Trees
+// ============================================
+
+/// Windows: child windows (controls, panes, embedded views), descending from the window
+#[cfg(windows)]
+fn find_elements(
+    _lookup: &ElementLookup,
+    window: &WindowInfo,
+    x: i32,
+    y: i32,
+) -> Result<Vec<UiElement>, String> {
+    use windows::Win32::Foundation::{HWND, POINT, RECT};
+    use windows::Win32::Graphics::Gdi::ScreenToClient;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        ChildWindowFromPointEx, GetClassNameW, GetWindowRect, GetWindowTextW, CWP_SKIPINVISIBLE,
+        CWP_SKIPTRANSPARENT,
+    };
+
+    let mut elements = Vec::new();
+    let mut parent = HWND(window.id as usize as *mut std::ffi::c_void);
+
+    unsafe {
+        for _ in 0..MAX_DEPTH {
+            let mut point = POINT { x, y };
+            let _ = ScreenToClient(parent, &mut point);
+            let child =
+                ChildWindowFromPointEx(parent, point, CWP_SKIPINVISIBLE | CWP_SKIPTRANSPARENT);
+            // The parent itself comes back when no child holds the point
+            if child.is_invalid() || child == parent {
+                break;
+            }
+
+            let mut rect = RECT::default();
+            GetWindowRect(child, &mut rect).map_err(|e| e.to_string())?;
+            let mut class = [0u16; 256];
+            let class_len = GetClassNameW(child, &mut class).max(0) as usize;
+            let mut text = [0u16; 256];
+            let text_len = GetWindowTextW(child, &mut text).max(0) as usize;
+
+            elements.push(UiElement {
+                x: rect.left,
+                y: rect.top,
+                width: (rect.right - rect.left).max(0) as u32,
+                height: (rect.bottom - rect.top).max(0) as u32,
+                role: String::from_utf16_lossy(&class[..class_len]),
+                name: String::from_utf16_lossy(&text[..text_len]),
+            });
+            parent = child;
+        }
+    }
+    Ok(elements)
+}
+
+/// Linux: the window's accessibility tree, where its toolkit exposes one over AT-SPI
+#[cfg(target_os = "linux")]
+fn find_elements(
+    lookup: &ElementLookup,
+    window: &WindowInfo,
+    x: i32,
+    y: i32,
+) -> Result<Vec<UiElement>, String> {
+    let pid = window.pid.ok_or("Window has no process id")?;
+    let deadline = std::time::Instant::now() + atspi::LOOKUP_BUDGET;
+
+    let mut session = lookup.session.lock().unwrap_or_else(|e| e.into_inner());
+    if session.is_none() {
+        *session = Some(atspi::Session::connect()?);
+    }
+    let session = session.as_mut().ok_or("No accessibility bus")?;
+    session.elements_at(pid, &window.title, x, y, deadline)
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn find_elements(
+    _lookup: &ElementLookup,
+    _window: &WindowInfo,
+    _x: i32,
+    _y: i32,
+) -> Result<Vec<UiElement>, String> {
+    Ok(Vec::new())
+}
+
+#[cfg(target_os = "linux")]
+mod atspi {
+    use super::{UiElement, MAX_DEPTH};
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+    use zbus::blocking::Connection;
+    use zbus::zvariant::{DynamicType, OwnedObjectPath, OwnedValue, Type};
+
+    /// Unresponsive applications must not stall the overlay
+    const TIMEOUT: Duration = Duration::from_millis(300);
+
+    /// Time one lookup may spend on the bus. Checked before each call, so a lookup ends
+    /// within this plus one call's `TIMEOUT`.
+    pub const LOOKUP_BUDGET: Duration = Duration::from_millis(500);
+
+    const REGISTRY: &str = "org.a11y.atspi.Registry";
+    const ROOT_PATH: &str = "/org/a11y/atspi/accessible/root";
+    const NULL_PATH: &str = "/org/a11y/atspi/null";
+    const ACCESSIBLE: &str = "org.a11y.atspi.Accessible";
+    const COMPONENT: &str = "org.a11y.atspi.Component";
+
+    /// Coordinates relative to the screen
+    const SCREEN: u32 = 0;
+
+    /// An accessible object: bus name of its application and object path
+    type ObjectRef = (String, OwnedObjectPath);
+
+    /// Connection to the accessibility bus and what earlier lookups learned about it
+    pub struct Session {
+        conn: Connection,
+        /// Applications in the registry, as listed the last time a process was not among them
+        apps: Vec<ObjectRef>,
+        /// Process id of each application asked about so far, by bus name
+        pids: HashMap<String, Option<u32>>,
+        /// When the current lookup gives up
+        deadline: Instant,
+    }
+
+    impl Session {
+        /// Connect to the accessibility bus, whose address the session bus hands out
+        pub fn connect() -> Result<Self, String> {
+            let session = zbus::blocking::connection::Builder::session()
+                .map(|builder| builder.method_timeout(TIMEOUT))
+                .and_then(|builder| builder.build())
+                .map_err(|e| e.to_string())?;
+            let address: String = session
+                .call_method(
+                    Some("org.a11y.Bus"),
+                    "/org/a11y/bus",
+                    Some("org.a11y.Bus"),
+                    "GetAddress",
+                    &(),
+                )
+                .and_then(|reply| reply.body().deserialize())
+                .map_err(|e| format!("No accessibility bus: {}", e))?;
+
+            let conn = zbus::blocking::connection::Builder::address(address.as_str())
+                .map(|builder| builder.method_timeout(TIMEOUT))
+                .and_then(|builder| builder.build())
+                .map_err(|e| format!("Failed to connect to the accessibility bus: {}", e))?;
+
+            Ok(Self {
+                conn,
+                apps: Vec::new(),
+                pids: HashMap::new(),
+                deadline: Instant::now(),
+            })
+        }
+
+        /// Elements under the point in the accessible window of process `pid`, outermost
+        /// first. Gives up once `deadline` has passed.
+        pub fn elements_at(
+            &mut self,
+            pid: u32,
+            title: &str,
+            x: i32,
+            y: i32,
+            deadline: Instant,
+        ) -> Result<Vec<UiElement>, String> {
+            self.deadline = deadline;
+            let app = self.application(pid)?;
+
+            // Top-level frames of the application; prefer the one named like the window
+            let mut frames: Vec<ObjectRef> = self
+                .children(&app)?
+                .into_iter()
+                .filter(|frame| {
+                    self.call::<bool, _>(frame, COMPONENT, "Contains", &(x, y, SCREEN))
+                        .unwrap_or(false)
+                })
+                .collect();
+            frames.sort_by_key(|frame| self.name(frame) != title);
+            let mut current = frames
+                .into_iter()
+                .next()
+                .ok_or("No accessible frame under the point")?;
+
+            let mut elements = Vec::new();
+            for _ in 0..MAX_DEPTH {
+                let child: ObjectRef =
+                    self.call(&current, COMPONENT, "GetAccessibleAtPoint", &(x, y, SCREEN))?;
+                if child.1.as_str() == NULL_PATH || child == current {
+                    break;
+                }
+                elements.push(self.describe(&child)?);
+                current = child;
+            }
+            Ok(elements)
+        }
+
+        /// The application of process `pid`. Each application's process id is asked for once,
+        /// and the registry is listed again only when `pid` is not among the applications
+        /// already known (it may have started since).
+        fn application(&mut self, pid: u32) -> Result<ObjectRef, String> {
+            if let Some(app) = self.known_application(pid) {
+                return Ok(app);
+            }
+
+            let root_path = OwnedObjectPath::try_from(ROOT_PATH).map_err(|e| e.to_string())?;
+            self.apps = self.children(&(REGISTRY.to_string(), root_path))?;
+            for app in self.apps.clone() {
+                if self.pids.contains_key(&app.0) {
+                    continue;
+                }
+                let app_pid = self.process_id(&app.0)?;
+                self.pids.insert(app.0.clone(), app_pid);
+                if app_pid == Some(pid) {
+                    return Ok(app);
+                }
+            }
+            Err(format!("Process {} is not accessible", pid))
+        }
+
+        fn known_application(&self, pid: u32) -> Option<ObjectRef> {
+            self.apps
+                .iter()
+                .find(|app| self.pids.get(&app.0) == Some(&Some(pid)))
+                .cloned()
+        }
+
+        fn within_budget(&self) -> Result<(), String> {
+            if Instant::now() >= self.deadline {
+                return Err("Accessibility lookup took too long".to_string());
+            }
+            Ok(())
+        }
+
+        fn call<R, B>(
+            &self,
+            object: &ObjectRef,
+            interface: &str,
+            method: &str,
+            body: &B,
+        ) -> Result<R, String>
+        where
+            R: serde::de::DeserializeOwned + Type,
+            B: serde::Serialize + DynamicType,
+        {
+            self.within_budget()?;
+            self.conn
+                .call_method(
+                    Some(object.0.as_str()),
+                    object.1.as_str(),
+                    Some(interface),
+                    method,
+                    body,
+                )
+                .and_then(|reply| reply.body().deserialize())
+                .map_err(|e| format!("{}.{} failed: {}", interface, method, e))
+        }
+
+        fn children(&self, object: &ObjectRef) -> Result<Vec<ObjectRef>, String> {
+            self.call(object, ACCESSIBLE, "GetChildren", &())
+        }
+
+        /// Process id of the application at `bus_name`, if the bus knows it
+        fn process_id(&self, bus_name: &str) -> Result<Option<u32>, String> {
+            self.within_budget()?;
+            Ok(self
+                .conn
+                .call_method(
+                    Some("org.freedesktop.DBus"),
+                    "/org/freedesktop/DBus",
+                    Some("org.freedesktop.DBus"),
+                    "GetConnectionUnixProcessID",
+                    &(bus_name,),
+                )
+                .and_then(|reply| reply.body().deserialize())
+                .ok())
+        }
+
+        fn name(&self, object: &ObjectRef) -> String {
+            self.call::<OwnedValue, _>(
+                object,
+                "org.freedesktop.DBus.Properties",
+                "Get",
+                &(ACCESSIBLE, "Name"),
+            )
+            .ok()
+            .and_then(|value| String::try_from(value).ok())
+            .unwrap_or_default()
+        }
+
+        fn describe(&self, object: &ObjectRef) -> Result<UiElement, String> {
+            let (x, y, width, height): (i32, i32, i32, i32) =
+                self.call(object, COMPONENT, "GetExtents", &(SCREEN,))?;
+            Ok(UiElement {
+                x,
+                y,
+                width: width.max(0) as u32,
+                height: height.max(0) as u32,
+                role: self
+                    .call(object, ACCESSIBLE, "GetRoleName", &())
+                    .unwrap_or_default(),
+                name: self.name(object),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(role: &str, x: i32, y: i32, width: u32, height: u32) -> UiElement {
+        UiElement {
+            x,
+            y,
+            width,
+            height,
+            role: role.to_string(),
+            name: String::new(),
+        }
+    }
+
+    fn window() -> WindowInfo {
+        WindowInfo {
+            id: 1,
+            title: "Editor".to_string(),
+            app_name: "code".to_string(),
+            x: 0,
+            y: 0,
+            width: 1000,
+            height: 800,
+            z_order: 0,
+            monitor_id: None,
+            pid: Some(7),
+            is_minimized: false,
+            is_visible: true,
+            visible_regions: Vec::new(),
+            visible_ratio: 1.0,
+        }
+    }
+
+    fn roles(elements: &[UiElement]) -> Vec<&str> {
+        elements
+            .iter()
+            .map(|element| element.role.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn nests_elements_inside_the_window() {
+        let found = vec![
+            // Fills the window: nothing new to snap to
+            element("filler", 0, 0, 1000, 800),
+            element("panel", 0, 700, 1000, 100),
+            // Sticks out of the panel: clipped to it
+            element("toolbar", -20, 700, 300, 50),
+            element("button", 10, 710, 40, 30),
+        ];
+
+        let elements = nest(&window(), found);
+        assert_eq!(roles(&elements), ["panel", "toolbar", "button"]);
+        assert_eq!(
+            elements[1].region(),
+            CaptureRegion {
+                x: 0,
+                y: 700,
+                width: 280,
+                height: 50,
+            }
+        );
+    }
+
+    #[test]
+    fn stops_at_empty_or_stray_elements() {
+        let found = vec![
+            element("panel", 100, 100, 200, 200),
+            element("stray", 500, 500, 50, 50),
+            element("button", 120, 120, 20, 20),
+        ];
+        assert_eq!(roles(&nest(&window(), found)), ["panel"]);
+
+        let found = vec![
+            element("label", 100, 100, 0, 20),
+            element("button", 100, 100, 20, 20),
+        ];
+        assert!(nest(&window(), found).is_empty());
+    }
+
+    /// Registers an application with the session's accessibility registry, so it only runs on
+    /// request in a throwaway session (needs at-spi2-core):
+    /// `JUSTSNAP_ATSPI_TEST=1 dbus-run-session -- cargo test`
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn walks_the_accessibility_tree_of_an_application() {
+        if std::env::var("JUSTSNAP_ATSPI_TEST").map_or(true, |value| value != "1") {
+            return;
+        }
+        let _app = fake_atspi::register().unwrap();
+
+        let lookup = ElementLookup::default();
+        let window = WindowInfo {
+            pid: Some(std::process::id()),
+            ..window()
+        };
+        for _ in 0..2 {
+            let stack = lookup.elements_at_point(window.clone(), 20, 750);
+            assert_eq!(roles(&stack.elements), ["panel", "push button"]);
+            assert_eq!(stack.elements[1].name, "OK");
+        }
+
+        lookup.reset();
+        let stack = lookup.elements_at_point(window.clone(), 20, 710);
+        assert_eq!(roles(&stack.elements), ["panel"]);
+
+        let stranger = WindowInfo {
+            pid: Some(1),
+            ..window
+        };
+        assert!(lookup
+            .elements_at_point(stranger, 20, 750)
+            .elements
+            .is_empty());
+    }
+
+    /// An application with a frame holding a panel holding a button
+    #[cfg(target_os = "linux")]
+    mod fake_atspi {
+        use zbus::blocking::Connection;
+        use zbus::interface;
+        use zbus::zvariant::OwnedObjectPath;
+
+        const ROOT_PATH: &str = "/org/a11y/atspi/accessible/root";
+
+        type Extents = (i32, i32, i32, i32);
+
+        /// Path, role, name and extents, each element inside the one before it
+        const TREE: [(&str, &str, &str, Extents); 3] = [
+            ("/frame", "frame", "Editor", (0, 0, 1000, 800)),
+            ("/panel", "panel", "Status", (0, 700, 1000, 100)),
+            ("/button", "push button", "OK", (10, 740, 40, 30)),
+        ];
+
+        fn object(bus_name: &str, path: &str) -> (String, OwnedObjectPath) {
+            (
+                bus_name.to_string(),
+                OwnedObjectPath::try_from(path).unwrap(),
+            )
+        }
+
+        fn contains((left, top, width, height): Extents, x: i32, y: i32) -> bool {
+            x >= left && y >= top && x < left + width && y < top + height
+        }
+
+        struct Accessible {
+            bus_name: String,
+            /// Index into `TREE`; `None` for the application itself
+            index: Option<usize>,
+        }
+
+        #[interface(name = "org.a11y.atspi.Accessible")]
+        impl Accessible {
+            fn get_children(&self) -> Vec<(String, OwnedObjectPath)> {
+                match self.index {
+                    None => vec![object(&self.bus_name, TREE[0].0)],
+                    Some(_) => Vec::new(),
+                }
+            }
+
+            fn get_role_name(&self) -> String {
+                self.index
+                    .map_or("application", |index| TREE[index].1)
+                    .to_string()
+            }
+
+            #[zbus(property)]
+            fn name(&self) -> String {
+                self.index
+                    .map_or("justsnap", |index| TREE[index].2)
+                    .to_string()
+            }
+        }
+
+        struct Component {
+            bus_name: String,
+            index: usize,
+        }
+
+        #[interface(name = "org.a11y.atspi.Component")]
+        impl Component {
+            fn contains(&self, x: i32, y: i32, _coord_type: u32) -> bool {
+                contains(TREE[self.index].3, x, y)
+            }
+
+            fn get_accessible_at_point(
+                &self,
+                x: i32,
+                y: i32,
+                _coord_type: u32,
+            ) -> (String, OwnedObjectPath) {
+                match TREE.get(self.index + 1) {
+                    Some(child) if contains(child.3, x, y) => object(&self.bus_name, child.0),
+                    _ => object("", "/org/a11y/atspi/null"),
+                }
+            }
+
+            fn get_extents(&self, _coord_type: u32) -> Extents {
+                TREE[self.index].3
+            }
+        }
+
+        /// Serve the tree on the accessibility bus and embed it in the registry. The
+        /// application stays registered while the connection is alive.
+        pub fn register() -> zbus::Result<Connection> {
+            let address: String = Connection::session()?
+                .call_method(
+                    Some("org.a11y.Bus"),
+                    "/org/a11y/bus",
+                    Some("org.a11y.Bus"),
+                    "GetAddress",
+                    &(),
+                )?
+                .body()
+                .deserialize()?;
+            let conn = zbus::blocking::connection::Builder::address(address.as_str())?.build()?;
+            let bus_name = conn
+                .unique_name()
+                .map(|name| name.to_string())
+                .unwrap_or_default();
+
+            {
+                let server = conn.object_server();
+                let accessible = |index| Accessible {
+                    bus_name: bus_name.clone(),
+                    index,
+                };
+                server.at(ROOT_PATH, accessible(None))?;
+                for (index, (path, ..)) in TREE.iter().enumerate() {
+                    server.at(*path, accessible(Some(index)))?;
+                    server.at(
+                        *path,
+                        Component {
+                            bus_name: bus_name.clone(),
+                            index,
+                        },
+                    )?;
+                }
+            }
+
+            conn.call_method(
+                Some("org.a11y.atspi.Registry"),
+                ROOT_PATH,
+                Some("org.a11y.atspi.Socket"),
+                "Embed",
+                &(object(&bus_name, ROOT_PATH),),
+            )?;
+            Ok(conn)
+        }
+    }
+}
@@ -0,0 +1,399 @@
+// JustSnap - Window Capture Module
+// Grabs one window's own pixels, so windows stacked over it do not show up in the capture
+
+use crate::screen_capture::{crop_image, CaptureRegion};
+use image::RgbaImage;
+
+/// Which parts around a window's content to keep
+#[derive(serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct WindowCaptureOptions {
+    /// Title bar and borders. Leaving them out fails for windows whose title bar is drawn as
+    /// part of the content (client-side decorations, macOS).
+    pub include_frame: bool,
+    /// Drop shadow, where the platform draws it as part of the window (only with the frame)
+    pub include_shadow: bool,
+}
+
+impl Default for WindowCaptureOptions {
+    fn default() -> Self {
+        Self {
+            include_frame: true,
+            include_shadow: false,
+        }
+    }
+}
+
+/// Where the parts of a window lie in its captured image (image pixels).
+/// The image itself is everything drawn for the window, shadow included.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowLayout {
+    /// The window as the user sees it: content plus title bar and borders
+    pub frame: CaptureRegion,
+    /// Content only; None when the title bar is drawn as part of the content and cannot be
+    /// told apart (client-side decorations, macOS)
+    pub client: Option<CaptureRegion>,
+}
+
+impl WindowLayout {
+    /// A window whose image is all frame and content (no shadow, no separate frame)
+    pub fn whole(image: &RgbaImage) -> Self {
+        let region = CaptureRegion {
+            x: 0,
+            y: 0,
+            width: image.width() as i32,
+            height: image.height() as i32,
+        };
+        Self {
+            frame: region,
+            client: Some(region),
+        }
+    }
+}
+
+/// Capture the window with the given id (as reported by `get_window_at_point`).
+/// Areas outside the window shape (rounded corners, kept shadow) are transparent.
+/// On X11 without a compositing manager, parts covered by other windows or off screen come
+/// back undefined (usually whatever is on top), since the server keeps no contents for them.
+pub fn capture_window(id: u32, options: &WindowCaptureOptions) -> Result<RgbaImage, String> {
+    let (image, layout) = capture(id)?;
+    trim(image, &layout, options)
+}
+
+/// Crop a window image to the parts `options` asks for
+pub fn trim(
+    image: RgbaImage,
+    layout: &WindowLayout,
+    options: &WindowCaptureOptions,
+) -> Result<RgbaImage, String> {
+    let keep = match (options.include_frame, options.include_shadow) {
+        (true, true) => return Ok(image),
+        (true, false) => layout.frame,
+        (false, _) => layout.client.ok_or_else(|| {
+            "Capturing without the title bar is not supported for this window".to_string()
+        })?,
+    };
+    if (keep.x, keep.y) == (0, 0) && (keep.width as u32, keep.height as u32) == image.dimensions() {
+        return Ok(image);
+    }
+    crop_image(&image, keep)
+}
+
+/// Bounding box of the fully opaque pixels. Shadows are translucent, so this is the window
+/// itself when the platform captures it with its shadow.
+#[cfg(not(windows))]
+pub fn opaque_bounds(image: &RgbaImage) -> Option<CaptureRegion> {
+    let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel[3] == u8::MAX {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x + 1);
+            bottom = bottom.max(y + 1);
+        }
+    }
+
+    (right > left).then(|| CaptureRegion {
+        x: left as i32,
+        y: top as i32,
+        width: (right - left) as i32,
+        height: (bottom - top) as i32,
+    })
+}
+
+fn xcap_window(id: u32) -> Result<xcap::Window, String> {
+    let window = xcap::Window::all()
+        .map_err(|e| format!("Failed to list windows: {}", e))?
+        .into_iter()
+        .find(|window| window.id().ok() == Some(id))
+        .ok_or_else(|| format!("Window {} not found", id))?;
+
+    if window.is_minimized().unwrap_or(false) {
+        return Err(format!("Window {} is minimized", id));
+    }
+    Ok(window)
+}
+
+// ============================================
+// Platform Captures
+// ============================================
+
+/// X11: a window the window manager reparented is grabbed with its frame window, the client
+/// sitting inside it and `_NET_FRAME_EXTENTS` giving the visible frame. Otherwise xcap grabs the
+/// client window; client-side decorations (GTK) are part of it, with their shadow margins
+/// published as `_GTK_FRAME_EXTENTS` and the title bar inside the content.
+#[cfg(target_os = "linux")]
+fn capture(id: u32) -> Result<(RgbaImage, WindowLayout), String> {
+    use crate::x11_windows::{window_frame, window_image};
+
+    // Fails for unknown and minimized windows
+    let window = xcap_window(id)?;
+
+    if let Ok(Some(framed)) = window_frame(id) {
+        match window_image(framed.frame) {
+            Ok(image) => {
+                let bounds = WindowLayout::whole(&image).frame;
+                let [left, right, top, bottom] = framed.extents;
+                let client = framed.client;
+                let frame = CaptureRegion {
+                    x: client.x - left as i32,
+                    y: client.y - top as i32,
+                    width: client.width + (left + right) as i32,
+                    height: client.height + (top + bottom) as i32,
+                };
+                let layout = WindowLayout {
+                    frame: frame.intersection(&bounds).unwrap_or(bounds),
+                    client: client.intersection(&bounds),
+                };
+                return Ok((image, layout));
+            }
+            // The client alone is still worth having
+            Err(e) => {
+                if cfg!(debug_assertions) {
+                    println!("[WindowCapture] No frame for window {}: {}", id, e);
+                }
+            }
+        }
+    }
+
+    let image = window
+        .capture_image()
+        .map_err(|e| format!("Failed to capture window: {}", e))?;
+
+    let frame = match crate::x11_windows::shadow_extents(id) {
+        Ok([left, right, top, bottom]) if left + right + top + bottom > 0 => Some(CaptureRegion {
+            x: left as i32,
+            y: top as i32,
+            width: image.width().saturating_sub(left + right).max(1) as i32,
+            height: image.height().saturating_sub(top + bottom).max(1) as i32,
+        }),
+        _ => None,
+    };
+
+    let mut layout = WindowLayout::whole(&image);
+    match frame {
+        Some(frame) => {
+            layout.frame = frame;
+            layout.client = None;
+        }
+        // Undecorated, or decorated where the frame could not be read
+        None => {
+            if let Some(bounds) = opaque_bounds(&image) {
+                layout.frame = bounds;
+                layout.client = Some(bounds);
+            }
+        }
+    }
+    Ok((image, layout))
+}
+
+/// Windows: xcap only returns the client area, so the whole window is printed here and split
+/// with the DWM frame bounds. DWM draws the shadow outside the window's own pixels, so there
+/// is none to include.
+#[cfg(windows)]
+fn capture(id: u32) -> Result<(RgbaImage, WindowLayout), String> {
+    use windows::Win32::Foundation::{HWND, POINT, RECT};
+    use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS};
+    use windows::Win32::Graphics::Gdi::ClientToScreen;
+    use windows::Win32::UI::WindowsAndMessaging::{GetClientRect, GetWindowRect};
+
+    // Fails for unknown and minimized windows
+    xcap_window(id)?;
+    let hwnd = HWND(id as usize as *mut std::ffi::c_void);
+
+    unsafe {
+        let mut window = RECT::default();
+        GetWindowRect(hwnd, &mut window).map_err(|e| e.to_string())?;
+
+        let mut frame = window;
+        let _ = DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            &mut frame as *mut RECT as *mut std::ffi::c_void,
+            std::mem::size_of::<RECT>() as u32,
+        );
+
+        let mut client = RECT::default();
+        GetClientRect(hwnd, &mut client).map_err(|e| e.to_string())?;
+        let mut origin = POINT::default();
+        let _ = ClientToScreen(hwnd, &mut origin);
+
+        let relative = |left: i32, top: i32, right: i32, bottom: i32| CaptureRegion {
+            x: left - window.left,
+            y: top - window.top,
+            width: (right - left).max(1),
+            height: (bottom - top).max(1),
+        };
+        let mut image =
+            win32::print_window(hwnd, window.right - window.left, window.bottom - window.top)?;
+
+        let mut layout = WindowLayout::whole(&image);
+        layout.frame = relative(frame.left, frame.top, frame.right, frame.bottom);
+        layout.client = Some(relative(
+            origin.x,
+            origin.y,
+            origin.x + client.right,
+            origin.y + client.bottom,
+        ));
+
+        // GDI leaves alpha undefined: the frame is opaque, the invisible resize borders are not
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            let inside = x as i32 >= layout.frame.x
+                && y as i32 >= layout.frame.y
+                && (x as i32) < layout.frame.x + layout.frame.width
+                && (y as i32) < layout.frame.y + layout.frame.height;
+            pixel[3] = if inside { u8::MAX } else { 0 };
+        }
+        Ok((image, layout))
+    }
+}
+
+/// macOS and others: xcap captures the window with its shadow, which is translucent. The
+/// title bar is drawn into the window, so the content cannot be captured on its own.
+#[cfg(not(any(target_os = "linux", windows)))]
+fn capture(id: u32) -> Result<(RgbaImage, WindowLayout), String> {
+    let image = xcap_window(id)?
+        .capture_image()
+        .map_err(|e| format!("Failed to capture window: {}", e))?;
+
+    let mut layout = WindowLayout::whole(&image);
+    layout.client = None;
+    if let Some(frame) = opaque_bounds(&image) {
+        layout.frame = frame;
+    }
+    Ok((image, layout))
+}
+
+#[cfg(windows)]
+mod win32 {
+    use image::RgbaImage;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Gdi::{
+        CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDIBits, GetWindowDC,
+        ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+    };
+    use windows::Win32::Storage::Xps::{PrintWindow, PRINT_WINDOW_FLAGS};
+
+    /// `PW_RENDERFULLCONTENT`: ask DWM for the content, which also works for covered and
+    /// GPU-rendered windows
+    const PW_RENDERFULLCONTENT: PRINT_WINDOW_FLAGS = PRINT_WINDOW_FLAGS(2);
+
+    /// Render the whole window (frame included) into an image
+    pub unsafe fn print_window(hwnd: HWND, width: i32, height: i32) -> Result<RgbaImage, String> {
+        if width <= 0 || height <= 0 {
+            return Err("Window has no size".to_string());
+        }
+
+        let window_dc = GetWindowDC(hwnd);
+        let memory_dc = CreateCompatibleDC(window_dc);
+        let bitmap = CreateCompatibleBitmap(window_dc, width, height);
+        let previous = SelectObject(memory_dc, bitmap);
+
+        let printed = PrintWindow(hwnd, memory_dc, PW_RENDERFULLCONTENT).as_bool();
+        SelectObject(memory_dc, previous);
+
+        // Top-down 32-bit BGRA rows
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        let lines = GetDIBits(
+            memory_dc,
+            bitmap,
+            0,
+            height as u32,
+            Some(pixels.as_mut_ptr() as *mut std::ffi::c_void),
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+
+        let _ = DeleteObject(bitmap);
+        let _ = DeleteDC(memory_dc);
+        ReleaseDC(hwnd, window_dc);
+
+        if !printed || lines == 0 {
+            return Err("Failed to print window".to_string());
+        }
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        RgbaImage::from_raw(width as u32, height as u32, pixels)
+            .ok_or_else(|| "Failed to read window pixels".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// 10x10 window with a 2px translucent shadow and a 3px title bar
+    fn window_image() -> (RgbaImage, WindowLayout) {
+        let image = RgbaImage::from_fn(14, 14, |x, y| match (x, y) {
+            (2..=11, 2..=11) => Rgba([200, 200, 200, 255]),
+            _ => Rgba([0, 0, 0, 60]),
+        });
+        let layout = WindowLayout {
+            frame: CaptureRegion {
+                x: 2,
+                y: 2,
+                width: 10,
+                height: 10,
+            },
+            client: Some(CaptureRegion {
+                x: 2,
+                y: 5,
+                width: 10,
+                height: 7,
+            }),
+        };
+        (image, layout)
+    }
+
+    #[test]
+    fn trims_to_the_requested_parts() {
+        let (image, layout) = window_image();
+        let trimmed = |include_frame, include_shadow| {
+            let options = WindowCaptureOptions {
+                include_frame,
+                include_shadow,
+            };
+            trim(image.clone(), &layout, &options).unwrap().dimensions()
+        };
+
+        assert_eq!(trimmed(true, true), (14, 14));
+        assert_eq!(trimmed(true, false), (10, 10));
+        assert_eq!(trimmed(false, false), (10, 7));
+        // The shadow only comes with the frame
+        assert_eq!(trimmed(false, true), (10, 7));
+
+        // A title bar drawn into the content cannot be left out
+        let merged = WindowLayout {
+            client: None,
+            ..layout
+        };
+        let options = WindowCaptureOptions {
+            include_frame: false,
+            include_shadow: false,
+        };
+        assert!(trim(image.clone(), &merged, &options).is_err());
+        assert!(trim(image, &merged, &WindowCaptureOptions::default()).is_ok());
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn finds_the_window_inside_its_shadow() {
+        let (image, layout) = window_image();
+        assert_eq!(opaque_bounds(&image), Some(layout.frame));
+        assert_eq!(opaque_bounds(&RgbaImage::new(4, 4)), None);
+    }
+}
@@ -0,0 +1,401 @@
+// JustSnap - Window List Module
+// Top-level windows front to back, filtered for the window picker, Smart Select and scripts
+
+use crate::screen_capture::CaptureRegion;
+use regex::Regex;
+
+/// A top-level window (physical pixels)
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
+pub struct WindowInfo {
+    pub id: u32,
+    pub title: String,
+    pub app_name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub z_order: i32, // Lower number = closer to user (top-most)
+    /// Monitor showing most of the window
+    #[serde(default)]
+    pub monitor_id: Option<u32>,
+    #[serde(default)]
+    pub pid: Option<u32>,
+    #[serde(default)]
+    pub is_minimized: bool,
+    /// On screen: not minimized and not empty
+    #[serde(default)]
+    pub is_visible: bool,
+    /// Parts of the window not covered by windows above it
+    #[serde(default)]
+    pub visible_regions: Vec<CaptureRegion>,
+    /// Share of the window's area that is visible (0-1)
+    #[serde(default)]
+    pub visible_ratio: f64,
+}
+
+/// Why the window under a point could not be looked up
+///
+/// Serialized as `{ "kind": "unsupported", "message": "..." }`.
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WindowLookupError {
+    /// The session does not expose window positions (Wayland); asking again will not help
+    Unsupported {
+        message: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for WindowLookupError {
+    fn from(message: String) -> Self {
+        WindowLookupError::Failed { message }
+    }
+}
+
+impl WindowInfo {
+    pub fn region(&self) -> CaptureRegion {
+        CaptureRegion {
+            x: self.x,
+            y: self.y,
+            width: self.width as i32,
+            height: self.height as i32,
+        }
+    }
+}
+
+/// Which windows `list_windows` returns
+#[derive(serde::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct WindowFilter {
+    /// Regex the app name must match (e.g. "(?i)^code$")
+    pub app_name: Option<String>,
+    /// Regex the title must match
+    pub title: Option<String>,
+    pub include_minimized: bool,
+}
+
+/// Windows no one means to pick: JustSnap's own (overlay, editor, stickies), told apart by
+/// process id, and shell windows such as the desktop
+pub fn is_own_or_system(title: &str, pid: Option<u32>) -> bool {
+    pid == Some(std::process::id())
+        || title.eq_ignore_ascii_case("program manager")
+        || title.eq_ignore_ascii_case("default ime")
+}
+
+/// Keep the windows matching `filter`, front to back
+pub fn filter_windows(
+    windows: Vec<WindowInfo>,
+    filter: &WindowFilter,
+) -> Result<Vec<WindowInfo>, String> {
+    let compile = |pattern: &Option<String>| {
+        pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("Invalid window pattern: {}", e))
+    };
+    let app_name = compile(&filter.app_name)?;
+    let title = compile(&filter.title)?;
+
+    Ok(windows
+        .into_iter()
+        .filter(|window| !is_own_or_system(&window.title, window.pid))
+        .filter(|window| filter.include_minimized || !window.is_minimized)
+        .filter(|window| {
+            app_name
+                .as_ref()
+                .map_or(true, |r| r.is_match(&window.app_name))
+        })
+        .filter(|window| title.as_ref().map_or(true, |r| r.is_match(&window.title)))
+        .collect())
+}
+
+// ============================================
+// Occlusion
+// ============================================
+
+/// Parts of `region` not covered by any of `covers`, as non-overlapping rectangles
+pub fn subtract_regions(region: CaptureRegion, covers: &[CaptureRegion]) -> Vec<CaptureRegion> {
+    let mut parts = vec![region];
+    for cover in covers {
+        if parts.is_empty() {
+            break;
+        }
+        parts = parts
+            .into_iter()
+            .flat_map(|part| subtract_region(part, cover))
+            .collect();
+    }
+    parts
+}
+
+/// Split what is left of `region` around `cover`: full-width bands above and below the hole,
+/// then the pieces beside it
+fn subtract_region(region: CaptureRegion, cover: &CaptureRegion) -> Vec<CaptureRegion> {
+    let Some(hole) = region.intersection(cover) else {
+        return vec![region];
+    };
+    let (right, bottom) = (region.x + region.width, region.y + region.height);
+    let (hole_right, hole_bottom) = (hole.x + hole.width, hole.y + hole.height);
+
+    [
+        CaptureRegion {
+            height: hole.y - region.y,
+            ..region
+        },
+        CaptureRegion {
+            y: hole_bottom,
+            height: bottom - hole_bottom,
+            ..region
+        },
+        CaptureRegion {
+            x: region.x,
+            width: hole.x - region.x,
+            ..hole
+        },
+        CaptureRegion {
+            x: hole_right,
+            width: right - hole_right,
+            ..hole
+        },
+    ]
+    .into_iter()
+    .filter(|part| part.area() > 0)
+    .collect()
+}
+
+/// Fill in what is visible of each window, given windows front to back.
+/// JustSnap's own windows (e.g. the snipping overlay) and minimized ones cover nothing.
+pub fn with_visibility(mut windows: Vec<WindowInfo>) -> Vec<WindowInfo> {
+    let mut covers: Vec<CaptureRegion> = Vec::new();
+
+    for window in &mut windows {
+        let region = window.region();
+        window.visible_regions = subtract_regions(region, &covers);
+        let visible: i64 = window.visible_regions.iter().map(CaptureRegion::area).sum();
+        window.visible_ratio = match region.area() {
+            0 => 0.0,
+            area => visible as f64 / area as f64,
+        };
+
+        if window.is_visible && !is_own_or_system(&window.title, window.pid) {
+            covers.push(region);
+        }
+    }
+    windows
+}
+
+/// Topmost pickable window containing the point, given windows front to back
+pub fn window_at_point(windows: Vec<WindowInfo>, x: i32, y: i32) -> Option<WindowInfo> {
+    windows.into_iter().find(|window| {
+        window.is_visible
+            && window.region().contains(x, y)
+            && !is_own_or_system(&window.title, window.pid)
+    })
+}
+
+// ============================================
+// Listing
+// ============================================
+
+/// Every top-level window xcap sees, front to back
+pub fn all_windows() -> Result<Vec<WindowInfo>, String> {
+    let windows = xcap::Window::all().map_err(|e| format!("Failed to list windows: {}", e))?;
+
+    // xcap's z grows towards the top
+    let mut stacked: Vec<(i32, WindowInfo)> = windows
+        .iter()
+        .filter_map(|window| {
+            let (width, height) = (window.width().ok()?, window.height().ok()?);
+            let is_minimized = window.is_minimized().unwrap_or(false);
+            let info = WindowInfo {
+                id: window.id().ok()?,
+                title: window.title().unwrap_or_default(),
+                app_name: window.app_name().unwrap_or_default(),
+                x: window.x().unwrap_or(0),
+                y: window.y().unwrap_or(0),
+                width,
+                height,
+                z_order: 0,
+                monitor_id: window.current_monitor().and_then(|m| m.id()).ok(),
+                pid: window.pid().ok(),
+                is_minimized,
+                is_visible: !is_minimized && width > 0 && height > 0,
+                visible_regions: Vec::new(),
+                visible_ratio: 0.0,
+            };
+            Some((window.z().unwrap_or(i32::MIN), info))
+        })
+        .collect();
+    stacked.sort_by_key(|(z, _)| std::cmp::Reverse(*z));
+
+    Ok(with_visibility(
+        stacked
+            .into_iter()
+            .enumerate()
+            .map(|(z_order, (_, info))| WindowInfo {
+                z_order: z_order as i32,
+                ..info
+            })
+            .collect(),
+    ))
+}
+
+/// Windows matching `filter`, front to back
+pub fn list_windows(filter: &WindowFilter) -> Result<Vec<WindowInfo>, String> {
+    filter_windows(all_windows()?, filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(z_order: i32, title: &str, app_name: &str, is_minimized: bool) -> WindowInfo {
+        WindowInfo {
+            id: z_order as u32 + 1,
+            title: title.to_string(),
+            app_name: app_name.to_string(),
+            x: 0,
+            y: 0,
+            width: 800,
+            height: 600,
+            z_order,
+            monitor_id: Some(1),
+            pid: Some(1000 + z_order as u32),
+            is_minimized,
+            is_visible: !is_minimized,
+            visible_regions: Vec::new(),
+            visible_ratio: 0.0,
+        }
+    }
+
+    /// A window of this process, like the snipping overlay
+    fn own(mut window: WindowInfo) -> WindowInfo {
+        window.pid = Some(std::process::id());
+        window
+    }
+
+    fn region(x: i32, y: i32, width: i32, height: i32) -> CaptureRegion {
+        CaptureRegion {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn at(mut window: WindowInfo, region: CaptureRegion) -> WindowInfo {
+        window.x = region.x;
+        window.y = region.y;
+        window.width = region.width as u32;
+        window.height = region.height as u32;
+        window
+    }
+
+    fn titles(windows: &[WindowInfo]) -> Vec<&str> {
+        windows.iter().map(|window| window.title.as_str()).collect()
+    }
+
+    #[test]
+    fn filters_by_app_title_and_state() {
+        let windows = vec![
+            own(window(0, "JustSnap Overlay", "justsnap", false)),
+            window(1, "overlay.rs - Visual Studio Code", "Code", false),
+            window(2, "Inbox - Mail", "Thunderbird", true),
+            window(3, "Program Manager", "explorer", false),
+            window(4, "README.md - Visual Studio Code", "Code", false),
+            window(5, "JustSnap releases - Firefox", "firefox", false),
+        ];
+
+        // Other applications' windows stay, whatever their titles mention
+        let all = filter_windows(windows.clone(), &WindowFilter::default()).unwrap();
+        assert_eq!(
+            titles(&all),
+            [
+                "overlay.rs - Visual Studio Code",
+                "README.md - Visual Studio Code",
+                "JustSnap releases - Firefox"
+            ]
+        );
+
+        let filter = WindowFilter {
+            app_name: Some("(?i)^code$".to_string()),
+            title: Some(r"\.md ".to_string()),
+            ..WindowFilter::default()
+        };
+        let matched = filter_windows(windows.clone(), &filter).unwrap();
+        assert_eq!(titles(&matched), ["README.md - Visual Studio Code"]);
+        // The real stacking position is kept
+        assert_eq!(matched[0].z_order, 4);
+
+        let filter = WindowFilter {
+            include_minimized: true,
+            title: Some("Mail".to_string()),
+            ..WindowFilter::default()
+        };
+        assert_eq!(
+            titles(&filter_windows(windows, &filter).unwrap()),
+            ["Inbox - Mail"]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        let filter = WindowFilter {
+            title: Some("(".to_string()),
+            ..WindowFilter::default()
+        };
+        assert!(filter_windows(Vec::new(), &filter).is_err());
+    }
+
+    #[test]
+    fn subtracts_covering_regions() {
+        let window = region(0, 0, 100, 100);
+
+        // A widget in the middle leaves a frame of four parts
+        let parts = subtract_regions(window, &[region(40, 40, 20, 20)]);
+        assert_eq!(parts.len(), 4);
+        assert_eq!(
+            parts.iter().map(CaptureRegion::area).sum::<i64>(),
+            100 * 100 - 20 * 20
+        );
+        for (i, a) in parts.iter().enumerate() {
+            assert!(parts[i + 1..].iter().all(|b| a.intersection(b).is_none()));
+        }
+
+        // Overlapping covers are not counted twice; a full cover hides everything
+        let parts = subtract_regions(window, &[region(-10, -10, 60, 120), region(40, 0, 20, 100)]);
+        assert_eq!(parts, vec![region(60, 0, 40, 100)]);
+        assert!(subtract_regions(window, &[region(-5, -5, 200, 200)]).is_empty());
+    }
+
+    #[test]
+    fn reports_how_much_of_each_window_shows() {
+        let windows = with_visibility(vec![
+            // Full-screen overlay and a minimized window cover nothing
+            at(
+                own(window(0, "JustSnap Overlay", "justsnap", false)),
+                region(0, 0, 1000, 1000),
+            ),
+            at(
+                window(1, "Clock", "widget", false),
+                region(900, 0, 100, 100),
+            ),
+            at(window(2, "Old", "app", true), region(0, 0, 1000, 1000)),
+            at(window(3, "Editor", "code", false), region(0, 0, 1000, 1000)),
+        ]);
+
+        let editor = &windows[3];
+        assert!((editor.visible_ratio - 0.99).abs() < 1e-9);
+        assert_eq!(editor.visible_regions.len(), 2);
+        assert_eq!(windows[1].visible_ratio, 1.0);
+
+        // The overlay is looked through; the widget wins where it is
+        let hit = |x, y| window_at_point(windows.clone(), x, y).map(|window| window.title);
+        assert_eq!(hit(950, 50).as_deref(), Some("Clock"));
+        assert_eq!(hit(500, 500).as_deref(), Some("Editor"));
+        assert_eq!(hit(2000, 0), None);
+    }
+}
@@ -0,0 +1,485 @@
+// JustSnap - X11 Windows Module
+// Top-level windows of an X11 session in stacking order, for Smart Select on Linux
+
+use crate::screen_capture::CaptureRegion;
+use image::RgbaImage;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, ImageFormat, ImageOrder, MapState, Window};
+
+/// Returned instead of window lists on Wayland, where only the compositor knows window positions
+pub const UNSUPPORTED_ON_WAYLAND: &str =
+    "Smart Select is unsupported on Wayland: the compositor does not expose window positions";
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        _NET_CLIENT_LIST_STACKING,
+        _NET_CLIENT_LIST,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_STATE,
+        _NET_WM_STATE_HIDDEN,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_DESKTOP,
+        _NET_FRAME_EXTENTS,
+        _GTK_FRAME_EXTENTS,
+        UTF8_STRING,
+    }
+}
+
+/// A visible top-level client window
+#[derive(Clone, Debug, PartialEq)]
+pub struct X11Window {
+    pub id: u32,
+    pub title: String,
+    /// Class part of `WM_CLASS` (e.g. "firefox")
+    pub app_name: String,
+    pub pid: Option<u32>,
+    /// Root window pixels, including the window manager's frame but not client-side shadows
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// True in Wayland sessions. X11 there only sees XWayland clients, so any answer would
+/// ignore the native windows stacked over them.
+pub fn is_wayland_session() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|session| session == "wayland")
+}
+
+/// Visible client windows of the X server in `$DISPLAY`, topmost first
+pub fn client_windows() -> Result<Vec<X11Window>, String> {
+    if is_wayland_session() {
+        return Err(UNSUPPORTED_ON_WAYLAND.to_string());
+    }
+
+    let (conn, screen) =
+        x11rb::connect(None).map_err(|e| format!("Failed to connect to the X server: {}", e))?;
+    let root = conn.setup().roots[screen].root;
+    list_windows(&conn, root)
+}
+
+/// Client windows below `root`, topmost first. Uses the window manager's stacking list
+/// (`_NET_CLIENT_LIST_STACKING`), falling back to the children of the root window.
+pub fn list_windows(conn: &impl Connection, root: Window) -> Result<Vec<X11Window>, String> {
+    let atoms = Atoms::new(conn)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+
+    let mut stacking = window_list(conn, root, atoms._NET_CLIENT_LIST_STACKING);
+    if stacking.is_empty() {
+        stacking = window_list(conn, root, atoms._NET_CLIENT_LIST);
+    }
+    if stacking.is_empty() {
+        // No EWMH window manager: the root's children are already bottom to top
+        stacking = conn
+            .query_tree(root)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?
+            .children;
+    }
+
+    // Windows can disappear while we look at them; skip those
+    Ok(stacking
+        .into_iter()
+        .rev()
+        .filter_map(|window| describe(conn, root, window, &atoms))
+        .collect())
+}
+
+/// Shadow margins (left, right, top, bottom) that client-side decorated windows (GTK) draw
+/// inside their own window. Zero for other windows.
+pub fn shadow_extents(window: Window) -> Result<[u32; 4], String> {
+    if is_wayland_session() {
+        return Err(UNSUPPORTED_ON_WAYLAND.to_string());
+    }
+
+    let (conn, _) =
+        x11rb::connect(None).map_err(|e| format!("Failed to connect to the X server: {}", e))?;
+    let atoms = Atoms::new(&conn)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    Ok(extents(&conn, window, atoms._GTK_FRAME_EXTENTS))
+}
+
+/// A client window inside the frame its window manager drew around it
+pub struct FramedWindow {
+    /// Ancestor of the client right below the root window
+    pub frame: Window,
+    /// The client inside the frame window
+    pub client: CaptureRegion,
+    /// Visible frame around the client (`_NET_FRAME_EXTENTS`: left, right, top, bottom)
+    pub extents: [u32; 4],
+}
+
+/// The frame of a window reparented by the window manager; None for windows shown without one
+/// (no window manager, or client-side decorations)
+pub fn window_frame(window: Window) -> Result<Option<FramedWindow>, String> {
+    if is_wayland_session() {
+        return Err(UNSUPPORTED_ON_WAYLAND.to_string());
+    }
+
+    let (conn, screen) =
+        x11rb::connect(None).map_err(|e| format!("Failed to connect to the X server: {}", e))?;
+    let root = conn.setup().roots[screen].root;
+    let atoms = Atoms::new(&conn)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+
+    let mut frame = window;
+    loop {
+        let tree = conn
+            .query_tree(frame)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| format!("Window {} not found: {}", window, e))?;
+        if tree.parent == root || tree.parent == x11rb::NONE {
+            break;
+        }
+        frame = tree.parent;
+    }
+    if frame == window {
+        return Ok(None);
+    }
+
+    let geometry = conn
+        .get_geometry(window)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    let offset = conn
+        .translate_coordinates(window, frame, 0, 0)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(FramedWindow {
+        frame,
+        client: CaptureRegion {
+            x: offset.dst_x as i32,
+            y: offset.dst_y as i32,
+            width: geometry.width as i32,
+            height: geometry.height as i32,
+        },
+        extents: extents(&conn, window, atoms._NET_FRAME_EXTENTS),
+    }))
+}
+
+/// Pixels of a window as the X server holds them, like xcap's capture of client windows.
+/// Only the usual 24/32-bit TrueColor layout (4 bytes per pixel, blue first) is read.
+/// Depth-32 windows (ARGB visuals, e.g. translucent terminals) keep their alpha.
+pub fn window_image(window: Window) -> Result<RgbaImage, String> {
+    let (conn, _) =
+        x11rb::connect(None).map_err(|e| format!("Failed to connect to the X server: {}", e))?;
+    let geometry = conn
+        .get_geometry(window)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    let image = conn
+        .get_image(
+            ImageFormat::Z_PIXMAP,
+            window,
+            0,
+            0,
+            geometry.width,
+            geometry.height,
+            u32::MAX,
+        )
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| format!("Failed to capture window: {}", e))?;
+
+    let setup = conn.setup();
+    let bits_per_pixel = setup
+        .pixmap_formats
+        .iter()
+        .find(|format| format.depth == image.depth)
+        .map(|format| format.bits_per_pixel);
+    if bits_per_pixel != Some(32) || setup.image_byte_order != ImageOrder::LSB_FIRST {
+        return Err(format!("Unsupported {}-bit window format", image.depth));
+    }
+
+    let has_alpha = image.depth == 32;
+    let pixels = image
+        .data
+        .chunks_exact(4)
+        .flat_map(|pixel| rgba_pixel(pixel, has_alpha))
+        .collect();
+    RgbaImage::from_raw(geometry.width.into(), geometry.height.into(), pixels)
+        .ok_or_else(|| "Failed to read window pixels".to_string())
+}
+
+/// One blue-first pixel as RGBA. Without alpha the fourth byte is padding; with it the
+/// colour is premultiplied, as the Render extension stores ARGB visuals, and is undone here.
+fn rgba_pixel(pixel: &[u8], has_alpha: bool) -> [u8; 4] {
+    let (blue, green, red) = (pixel[0], pixel[1], pixel[2]);
+    if !has_alpha {
+        return [red, green, blue, u8::MAX];
+    }
+    let alpha = pixel[3];
+    if alpha == 0 {
+        return [0, 0, 0, 0];
+    }
+    let straight = |channel: u8| {
+        let value = (u32::from(channel) * 255 + u32::from(alpha) / 2) / u32::from(alpha);
+        value.min(255) as u8
+    };
+    [straight(red), straight(green), straight(blue), alpha]
+}
+
+fn window_list(conn: &impl Connection, root: Window, property: u32) -> Vec<Window> {
+    property_u32(conn, root, property, AtomEnum::WINDOW.into())
+}
+
+fn property_u32(conn: &impl Connection, window: Window, property: u32, kind: u32) -> Vec<u32> {
+    conn.get_property(false, window, property, kind, 0, u32::MAX)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .and_then(|reply| reply.value32().map(|values| values.collect()))
+        .unwrap_or_default()
+}
+
+/// A left, right, top, bottom extents property (all zero when missing)
+fn extents(conn: &impl Connection, window: Window, property: u32) -> [u32; 4] {
+    let values = property_u32(conn, window, property, AtomEnum::CARDINAL.into());
+    <[u32; 4]>::try_from(values).unwrap_or_default()
+}
+
+fn property_string(conn: &impl Connection, window: Window, property: u32, kind: u32) -> String {
+    conn.get_property(false, window, property, kind, 0, u32::MAX)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .map(|reply| String::from_utf8_lossy(&reply.value).into_owned())
+        .unwrap_or_default()
+}
+
+/// Position and names of a mapped, non-minimized, non-desktop window
+fn describe(
+    conn: &impl Connection,
+    root: Window,
+    window: Window,
+    atoms: &Atoms,
+) -> Option<X11Window> {
+    let attributes = conn.get_window_attributes(window).ok()?.reply().ok()?;
+    if attributes.map_state != MapState::VIEWABLE {
+        return None;
+    }
+
+    let state = property_u32(conn, window, atoms._NET_WM_STATE, AtomEnum::ATOM.into());
+    let kind = property_u32(
+        conn,
+        window,
+        atoms._NET_WM_WINDOW_TYPE,
+        AtomEnum::ATOM.into(),
+    );
+    if state.contains(&atoms._NET_WM_STATE_HIDDEN)
+        || kind.contains(&atoms._NET_WM_WINDOW_TYPE_DESKTOP)
+    {
+        return None;
+    }
+
+    let geometry = conn.get_geometry(window).ok()?.reply().ok()?;
+    let origin = conn
+        .translate_coordinates(window, root, 0, 0)
+        .ok()?
+        .reply()
+        .ok()?;
+
+    // Grow by the window manager's frame, shrink by a client-side shadow
+    let [left, right, top, bottom] = extents(conn, window, atoms._NET_FRAME_EXTENTS);
+    let [shadow_left, shadow_right, shadow_top, shadow_bottom] =
+        extents(conn, window, atoms._GTK_FRAME_EXTENTS);
+
+    let mut title = property_string(conn, window, atoms._NET_WM_NAME, atoms.UTF8_STRING);
+    if title.is_empty() {
+        title = property_string(
+            conn,
+            window,
+            AtomEnum::WM_NAME.into(),
+            AtomEnum::STRING.into(),
+        );
+    }
+    // WM_CLASS holds "instance\0class\0"
+    let class = property_string(
+        conn,
+        window,
+        AtomEnum::WM_CLASS.into(),
+        AtomEnum::STRING.into(),
+    );
+    let app_name = class
+        .split('\0')
+        .rfind(|part| !part.is_empty())
+        .unwrap_or_default()
+        .to_string();
+    let pid = property_u32(conn, window, atoms._NET_WM_PID, AtomEnum::CARDINAL.into())
+        .first()
+        .copied();
+
+    Some(X11Window {
+        id: window,
+        title,
+        app_name,
+        pid,
+        x: origin.dst_x as i32 - left as i32 + shadow_left as i32,
+        y: origin.dst_y as i32 - top as i32 + shadow_top as i32,
+        width: (geometry.width as u32 + left + right).saturating_sub(shadow_left + shadow_right),
+        height: (geometry.height as u32 + top + bottom).saturating_sub(shadow_top + shadow_bottom),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x11rb::protocol::xproto::{Atom, CreateWindowAux, PropMode, WindowClass};
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+
+    /// Puts back the root window's stacking list and removes the test windows, also when an
+    /// assertion fails
+    struct Restore<'a> {
+        conn: &'a RustConnection,
+        root: Window,
+        stacking: Atom,
+        previous: Option<Vec<u32>>,
+        windows: Vec<Window>,
+    }
+
+    impl Drop for Restore<'_> {
+        fn drop(&mut self) {
+            let _ = match self.previous {
+                Some(ref previous) => self.conn.change_property32(
+                    PropMode::REPLACE,
+                    self.root,
+                    self.stacking,
+                    AtomEnum::WINDOW,
+                    previous,
+                ),
+                None => self.conn.delete_property(self.root, self.stacking),
+            };
+            for window in &self.windows {
+                let _ = self.conn.destroy_window(*window);
+            }
+            let _ = self.conn.sync();
+        }
+    }
+
+    /// Replaces the root window's stacking list, which belongs to the window manager, so it only
+    /// runs on request against a throwaway X server:
+    /// `JUSTSNAP_X11_TEST=1 xvfb-run cargo test`
+    #[test]
+    fn lists_windows_of_an_x_server_in_stacking_order() {
+        if std::env::var("JUSTSNAP_X11_TEST").map_or(true, |value| value != "1") {
+            return;
+        }
+        let (conn, screen) = x11rb::connect(None).unwrap();
+        let root = conn.setup().roots[screen].root;
+        let atoms = Atoms::new(&conn).unwrap().reply().unwrap();
+        let previous = conn
+            .get_property(
+                false,
+                root,
+                atoms._NET_CLIENT_LIST_STACKING,
+                AtomEnum::WINDOW,
+                0,
+                u32::MAX,
+            )
+            .unwrap()
+            .reply()
+            .unwrap()
+            .value32()
+            .map(Iterator::collect);
+        let mut restore = Restore {
+            conn: &conn,
+            root,
+            stacking: atoms._NET_CLIENT_LIST_STACKING,
+            previous,
+            windows: Vec::new(),
+        };
+
+        let create = |x: i16, y: i16, size: u16, name: &str| {
+            let id = conn.generate_id().unwrap();
+            conn.create_window(
+                0,
+                id,
+                root,
+                x,
+                y,
+                size,
+                size,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                0,
+                &CreateWindowAux::new(),
+            )
+            .unwrap();
+            conn.change_property8(
+                PropMode::REPLACE,
+                id,
+                atoms._NET_WM_NAME,
+                atoms.UTF8_STRING,
+                name.as_bytes(),
+            )
+            .unwrap();
+            conn.change_property8(
+                PropMode::REPLACE,
+                id,
+                AtomEnum::WM_CLASS,
+                AtomEnum::STRING,
+                b"test\0JustSnapTest\0",
+            )
+            .unwrap();
+            conn.map_window(id).unwrap();
+            id
+        };
+        let below = create(10, 10, 200, "below");
+        let above = create(50, 50, 100, "above");
+        let hidden = create(0, 0, 300, "hidden");
+        restore.windows = vec![below, above, hidden];
+        conn.change_property32(
+            PropMode::REPLACE,
+            hidden,
+            atoms._NET_WM_STATE,
+            AtomEnum::ATOM,
+            &[atoms._NET_WM_STATE_HIDDEN],
+        )
+        .unwrap();
+
+        // Without a window manager, stand in for one: publish the stacking order (bottom first)
+        conn.change_property32(
+            PropMode::REPLACE,
+            root,
+            atoms._NET_CLIENT_LIST_STACKING,
+            AtomEnum::WINDOW,
+            &[below, above, hidden],
+        )
+        .unwrap();
+        conn.sync().unwrap();
+
+        let windows = list_windows(&conn, root).unwrap();
+        let ids: Vec<u32> = windows.iter().map(|w| w.id).collect();
+        assert_eq!(ids, vec![above, below]);
+        assert_eq!(windows[0].title, "above");
+        assert_eq!(windows[0].app_name, "JustSnapTest");
+        assert_eq!(
+            (windows[1].x, windows[1].y, windows[1].width),
+            (10, 10, 200)
+        );
+    }
+
+    #[test]
+    fn window_pixels_keep_alpha_only_at_depth_32() {
+        // Padding byte of a 24-bit window is ignored
+        assert_eq!(rgba_pixel(&[10, 20, 30, 0], false), [30, 20, 10, 255]);
+        // Half-transparent premultiplied red becomes straight red
+        assert_eq!(rgba_pixel(&[0, 0, 128, 128], true), [255, 0, 0, 128]);
+        assert_eq!(rgba_pixel(&[0, 32, 64, 128], true), [128, 64, 0, 128]);
+        assert_eq!(rgba_pixel(&[1, 2, 3, 0], true), [0, 0, 0, 0]);
+        assert_eq!(rgba_pixel(&[40, 50, 60, 255], true), [60, 50, 40, 255]);
+    }
+}
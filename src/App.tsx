@@ -1,9 +1,20 @@
 // JustSnap - Main Application Component
 import { useEffect, useState, lazy, Suspense } from 'react';
 import { useAppStore } from './store/appStore';
-import { convertFileSrc } from '@tauri-apps/api/core';
 import { SnipOverlay } from './components/snipping/SnipOverlay';
 import { ErrorBoundary } from './components/window/ErrorBoundary';
+import {
+  captureUrl,
+  copyCaptureToClipboard,
+  openStartupFiles,
+  releaseCapture,
+} from './services/ipc.service';
+import type {
+  HotkeyAction,
+  HotkeyActionEvent,
+  OpenInEditorEvent,
+  SelectionTarget,
+} from './types';
 
 
 // Lazy load ScreenshotEditor to prevent initialization issues
@@ -17,7 +28,26 @@ function App() {
   const isActive = useAppStore((state) => state.isOverlayActive);
   const currentScreenshot = useAppStore((state) => state.currentScreenshot);
   
-  const [imgSrc, setImgSrc] = useState<string | null>(null);
+  // Capture of the screen behind the overlay, shown as its background
+  const [backgroundCaptureId, setBackgroundCaptureId] = useState<string | null>(null);
+  const imgSrc = backgroundCaptureId ? captureUrl(backgroundCaptureId) : null;
+
+  // Free the background capture once it is replaced
+  useEffect(() => {
+    if (!backgroundCaptureId) return;
+    return () => {
+      releaseCapture(backgroundCaptureId).catch((err) => {
+        console.error('[App] Failed to release background capture:', err);
+      });
+    };
+  }, [backgroundCaptureId]);
+
+  // ...or once neither the overlay nor the editor shows it
+  useEffect(() => {
+    if (!isActive && !currentScreenshot) {
+      setBackgroundCaptureId(null);
+    }
+  }, [isActive, currentScreenshot]);
 
   // Preload ScreenshotEditor in background
   useEffect(() => {
@@ -52,60 +82,8 @@ function App() {
 
            const state = useAppStore.getState();
 
-           // STEP 0: Rehydrate store from localStorage (sync settings from other windows)
-           try {
-             const stored = localStorage.getItem('app-storage');
-             if (stored) {
-               const parsed = JSON.parse(stored);
-               if (parsed.state?.toolbarConfig) {
-                 useAppStore.setState({ toolbarConfig: parsed.state.toolbarConfig });
-                 console.log('[App] Rehydrated toolbarConfig from localStorage');
-               }
-               if (parsed.state?.defaultTool) {
-                 useAppStore.setState({ currentTool: parsed.state.defaultTool });
-                 console.log('[App] Set default tool:', parsed.state.defaultTool);
-               }
-               if (parsed.state?.colorPalette) {
-                 useAppStore.setState({ colorPalette: parsed.state.colorPalette });
-                 console.log('[App] Rehydrated colorPalette from localStorage');
-               }
-               if (typeof parsed.state?.autoCloseAfterCopy === 'boolean') {
-                 useAppStore.setState({ autoCloseAfterCopy: parsed.state.autoCloseAfterCopy });
-               }
-               if (typeof parsed.state?.autoCloseAfterSave === 'boolean') {
-                 useAppStore.setState({ autoCloseAfterSave: parsed.state.autoCloseAfterSave });
-               }
-               
-               // Re-register hotkeys if they exist in storage
-               if (parsed.state?.hotkeys?.capture) {
-                 const savedHotkey = parsed.state.hotkeys.capture;
-                 useAppStore.setState(state => ({ 
-                    hotkeys: { ...state.hotkeys, capture: savedHotkey } 
-                 }));
-                 console.log('[App] Rehydrating hotkey:', savedHotkey);
-                 
-                 // Unregister default and register saved
-                 try {
-                    const { invoke } = await import('@tauri-apps/api/core');
-                    const parts = savedHotkey.split('+');
-                    const key = parts.pop();
-                    const modifiers = parts;
-                    
-                    if (key) {
-                        // First unregister default/all
-                        // We use a safe-guard command if available, or just register the new one.
-                        // Ideally we should unregister the old one first.
-                        await invoke('register_hotkey', { modifiers, key });
-                        console.log('[App] Re-registered custom hotkey:', savedHotkey);
-                    }
-                 } catch (hkErr) {
-                    console.error('[App] Failed to register saved hotkey:', hkErr);
-                 }
-               }
-             }
-           } catch (err) {
-             console.warn('[App] Failed to rehydrate settings:', err);
-           }
+           // STEP 0: Start with the default tool (settings are kept in sync by the settings service)
+           useAppStore.setState({ currentTool: state.defaultTool });
 
            // STEP 1: Reset state (clear any active screenshot/annotations)
            // We do NOT hide the window here because the backend just showed it.
@@ -128,7 +106,7 @@ function App() {
            console.log('[App] Opening fresh overlay...');
 
            // Clear previous image
-           setImgSrc(null);
+           setBackgroundCaptureId(null);
 
            // Setup window for capture
            const { getCurrentWindow } = await import('@tauri-apps/api/window');
@@ -144,39 +122,84 @@ function App() {
              console.error('[App] Window setup failed:', err);
            }
 
-           // Activate overlay
-           state.showOverlay('capture');
+           // Activate overlay, picking a window or text to recognise for those hotkeys
+           const action = e.payload?.action as HotkeyAction | undefined;
+           const target: SelectionTarget =
+             action === 'window_capture' ? 'window' : action === 'ocr' ? 'text' : 'region';
+           state.showOverlay('capture', target);
            console.log('[App] Overlay activated');
         });
 
         await listen('screen-capture-ready', (e: any) => {
            console.log('[App] Received screen-capture-ready', typeof e.payload);
-           try {
-             if (typeof e.payload === 'string') {
-               console.log('[App] Payload length:', e.payload.length);
-               
-               // Detect if payload is a file path (short) or base64 (long)
-               // File paths are typically < 256 chars, Base64 images are > 1000
-               if (e.payload.length < 500) {
-                   const assetUrl = convertFileSrc(e.payload);
-                   console.log('[App] Loading from asset URL:', assetUrl);
-                   setImgSrc(assetUrl);
-               } else {
-                   // Fallback Base64 string (should not trigger with new backend)
-                   setImgSrc(`data:image/jpeg;base64,${e.payload}`);
-               }
-             } else {
-               // Legacy path: byte array
-               const bytes = new Uint8Array(e.payload);
-               const blob = new Blob([bytes], { type: 'image/png' });
-               const url = URL.createObjectURL(blob);
-               setImgSrc(url);
+           // Payload is a capture id; the image is served from the in-memory
+           // capture store through the justsnap:// protocol
+           setBackgroundCaptureId(e.payload);
+        });
+
+        // An image that was not just captured (history, clipboard, files) is opened in the editor;
+        // the backend already showed the window on the monitor under the cursor
+        await listen<OpenInEditorEvent>('open-in-editor', (e) => {
+           const { capture_id, width: imageWidth, height: imageHeight, monitor } = e.payload;
+           console.log('[App] Opening capture in editor', capture_id);
+
+           const state = useAppStore.getState();
+           useAppStore.setState({ currentTool: state.defaultTool });
+           state.clearScreenshot();
+           state.setMonitorOffset({
+             x: monitor.x,
+             y: monitor.y,
+             width: monitor.width,
+             height: monitor.height,
+             scaleFactor: monitor.scale_factor,
+           });
+           setBackgroundCaptureId(null);
+
+           // Centre the image at its original size, scaled down to fit the monitor
+           const scale = window.devicePixelRatio || 1;
+           const fit = Math.min(
+             1,
+             (window.innerWidth * 0.9) / (imageWidth / scale),
+             (window.innerHeight * 0.9) / (imageHeight / scale)
+           );
+           const width = (imageWidth / scale) * fit;
+           const height = (imageHeight / scale) * fit;
+
+           state.setScreenshot({
+             id: crypto.randomUUID(),
+             imageData: captureUrl(capture_id),
+             captureId: capture_id,
+             region: {
+               x: (window.innerWidth - width) / 2,
+               y: (window.innerHeight - height) / 2,
+               width,
+               height,
+             },
+             timestamp: e.payload.captured_at,
+             mode: 'capture',
+             historyId: e.payload.history_id ?? undefined,
+           });
+        });
+
+        // Actions without an overlay of their own (e.g. full-screen capture) report here
+        await listen<HotkeyActionEvent>('hotkey-action', async (e) => {
+           console.log('[App] Hotkey action:', e.payload.action);
+           if (e.payload.action === 'full_screen' && e.payload.capture_id) {
+             try {
+               await copyCaptureToClipboard(e.payload.capture_id);
+             } catch (err) {
+               console.error('[App] Failed to copy full-screen capture:', err);
              }
-           } catch (err) {
-             console.error(`Blob/Image Error: ${err}`);
            }
         });
 
+        // Image files passed on the command line wait until the editor listens
+        try {
+          await openStartupFiles();
+        } catch (err) {
+          console.error('[App] Failed to open startup files:', err);
+        }
+
         // Handle race condition: If hotkey was pressed before we were ready
         try {
           const { getCurrentWindow } = await import('@tauri-apps/api/window');
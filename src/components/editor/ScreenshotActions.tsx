@@ -2,11 +2,23 @@
 // Handles copy, save, stick, and AI code generation actions
 
 import { currentMonitor } from '@tauri-apps/api/window';
-import type { Screenshot, AskFramework } from '../../types';
+import type { Screenshot, AskFramework, ClipboardFlavour } from '../../types';
 import { useAppStore } from '../../store/appStore';
 import * as ipc from '../../services/ipc.service';
 import { hideImmediatelyThenPerform, hidePerformShowFeedback } from '../../utils/windowManager';
 
+// HTML flavour of a copied capture: the image with the recognised text as its alt text
+// (the same fragment the backend offers)
+function imageHtml(dataUrl: string, alt: string): string {
+  const escaped = alt
+    .replace(/&/g, '&amp;')
+    .replace(/</g, '&lt;')
+    .replace(/>/g, '&gt;')
+    .replace(/"/g, '&quot;')
+    .replace(/\n/g, '&#10;');
+  return `<img src="${dataUrl}" alt="${escaped}">`;
+}
+
 interface ScreenshotActionsProps {
   screenshot: Screenshot;
   width: number;
@@ -105,35 +117,66 @@ export function useScreenshotActions({
     return compositeCanvas.toDataURL('image/png');
   };
 
+  // Run an action on a stored capture of what the editor shows. A capture without annotations
+  // is used straight from the capture store; otherwise the composite is stored for the action
+  // (keeping the capture's time, monitor and window) and released afterwards.
+  const withExportedCapture = async <T,>(action: (captureId: string) => Promise<T>): Promise<T> => {
+    if (screenshot.captureId && useAppStore.getState().annotations.length === 0) {
+      return await action(screenshot.captureId);
+    }
+
+    const response = await fetch(await exportCanvasAsDataURL());
+    const bytes = new Uint8Array(await (await response.blob()).arrayBuffer());
+    const captureId = await ipc.storeCapture(bytes, screenshot.captureId);
+    try {
+      return await action(captureId);
+    } finally {
+      await ipc.releaseCapture(captureId);
+    }
+  };
+
   const handleCopy = async () => {
-    const dataURL = await exportCanvasAsDataURL();
-    
     // We perform the copy operation BEFORE hiding the window
     // This is critical because navigator.clipboard requires the window to be focused
     try {
-      const response = await fetch(dataURL);
-      const blob = await response.blob();
       let success = false;
+      let flavours: ClipboardFlavour[] = [];
 
-      // 1. Try Frontend Clipboard (Preferred, supports more formats/metadata if needed)
-      if (navigator.clipboard && window.ClipboardItem) {
-        try {
-          await navigator.clipboard.write([
-            new ClipboardItem({ 'image/png': blob })
-          ]);
-          success = true;
-          console.log('[ScreenshotActions] Frontend copy success');
-        } catch (err) {
-          console.warn('[ScreenshotActions] Frontend copy failed (focus lost?), trying backend:', err);
-        }
+      // 1. Copy through the backend (reliable even without focus), with recognised text
+      //    added as text and HTML flavours and the saved file as a file list
+      const state = useAppStore.getState();
+      const ocrText = state.ocrResult?.text.trim();
+      const savedPath = state.currentScreenshot?.savedPath;
+      try {
+        const extras =
+          ocrText || savedPath
+            ? { text: ocrText || undefined, html: !!ocrText, files: savedPath ? [savedPath] : [] }
+            : undefined;
+        flavours = await withExportedCapture((captureId) =>
+          ipc.copyCaptureToClipboard(captureId, extras)
+        );
+        // Outside Windows the backend offers only the image when it has one; the webview
+        // clipboard can carry the text and HTML alongside it, but not the file
+        success =
+          flavours.includes('image') &&
+          (!ocrText || (flavours.includes('text') && flavours.includes('html'))) &&
+          (!savedPath || flavours.includes('file_list'));
+        console.log('[ScreenshotActions] Backend copy result:', flavours);
+      } catch (err) {
+        console.warn('[ScreenshotActions] Backend copy failed, trying frontend:', err);
       }
 
-      // 2. Fallback to Backend Clipboard (Reliable even without focus)
+      // 2. Fallback to Frontend Clipboard
       if (!success) {
-        const buffer = await blob.arrayBuffer();
-        const bytes = new Uint8Array(buffer);
-        await ipc.copyImageToClipboard(bytes);
-        console.log('[ScreenshotActions] Backend copy success');
+        const dataUrl = await exportCanvasAsDataURL();
+        const response = await fetch(dataUrl);
+        const items: Record<string, Blob> = { 'image/png': await response.blob() };
+        if (ocrText) {
+          items['text/plain'] = new Blob([ocrText], { type: 'text/plain' });
+          items['text/html'] = new Blob([imageHtml(dataUrl, ocrText)], { type: 'text/html' });
+        }
+        await navigator.clipboard.write([new ClipboardItem(items)]);
+        console.log('[ScreenshotActions] Frontend copy success');
       }
 
       // 3. Conditional Close based on settings
@@ -141,7 +184,11 @@ export function useScreenshotActions({
       if (autoClose) {
         onClose();
       }
-      onFeedback('Copied to clipboard');
+      // The webview clipboard cannot carry files, so say when the saved file was left out
+      const fileLeftOut = !!savedPath && !flavours.includes('file_list');
+      onFeedback(
+        fileLeftOut ? 'Copied to clipboard (without the saved file)' : 'Copied to clipboard'
+      );
 
     } catch (error) {
       console.error('Failed to copy capture:', error);
@@ -149,20 +196,44 @@ export function useScreenshotActions({
     }
   };
 
-  const handleSave = async () => {
-    const dataURL = await exportCanvasAsDataURL();
+  // Remember where the capture was saved (offered as a file when copying) and in its history
+  // entry (recorded in the background)
+  const recordSavedPath = (path: string) => {
+    const { currentScreenshot, setScreenshotSavedPath } = useAppStore.getState();
+    if (!currentScreenshot) return;
+    setScreenshotSavedPath(currentScreenshot.id, path);
+    const historyId = currentScreenshot.historyId;
+    if (historyId === undefined) return;
+    ipc.updateHistoryEntry(historyId, { saved_path: path }).catch((error) => {
+      console.error('Failed to record saved path in history:', error);
+    });
+  };
 
-    // Convert to bytes immediately (before hiding)
-    const response = await fetch(dataURL);
-    const blob = await response.blob();
-    const buffer = await blob.arrayBuffer();
-    const bytes = new Uint8Array(buffer);
+  const handleSave = async () => {
+    // Auto-save: name the file from the template instead of asking
+    const { autoSave, savePath } = useAppStore.getState();
+    if (autoSave && savePath) {
+      try {
+        const path = await withExportedCapture((captureId) => ipc.autoSaveCapture(captureId));
+        recordSavedPath(path);
+        onFeedback(`Saved to ${path}`);
+        if (useAppStore.getState().autoCloseAfterSave) {
+          onClose();
+        }
+      } catch (error) {
+        console.error('Failed to auto-save capture:', error);
+        onFeedback('Failed to save image');
+      }
+      return;
+    }
 
     const result = await hidePerformShowFeedback(
       async () => {
         const path = await ipc.openSaveDialog('screenshot.png');
         if (path) {
-          await ipc.saveImage(bytes, path);
+          // Capture time, monitor, window and scale factor are embedded from the capture
+          await withExportedCapture((captureId) => ipc.saveCapture(captureId, path));
+          recordSavedPath(path);
         } else {
           throw new Error('Save cancelled');
         }
@@ -229,8 +300,9 @@ export function useScreenshotActions({
 
     await hideImmediatelyThenPerform(
       async () => {
-        // Position uses screen coordinates, size uses logical dimensions
-        await ipc.createStickyWindow(imageDataURL, annotations, screenX, screenY, width, height, nativeWidth, nativeHeight);
+        // Position uses screen coordinates, size uses logical dimensions.
+        // The sticky takes its own handle on the stored capture.
+        await ipc.createStickyWindow(imageDataURL, annotations, screenX, screenY, width, height, nativeWidth, nativeHeight, screenshot.captureId);
       },
       () => onClose(),
       (error) => {
@@ -12,6 +12,9 @@ export function GeneralSettings() {
   const startAtLogin = useAppStore((state) => state.startAtLogin);
   const setStartAtLogin = useAppStore((state) => state.setStartAtLogin);
 
+  const controlServer = useAppStore((state) => state.controlServer);
+  const setControlServer = useAppStore((state) => state.setControlServer);
+
   // Sync state with plugin on mount
   useEffect(() => {
     isEnabled().then((enabled) => {
@@ -114,6 +117,30 @@ export function GeneralSettings() {
               />
             </button>
           </div>
+
+          {/* Control Server */}
+          <div className="flex items-center justify-between p-4 bg-white/5 rounded-xl border border-white/5 hover:bg-white/10 transition-colors">
+            <div>
+              <div className="font-medium text-white mb-1">Automation socket</div>
+              <div className="text-sm text-gray-400">
+                Let local scripts capture and save over JSON-RPC (token in control.json in the config folder)
+              </div>
+            </div>
+            <button
+              onClick={() => setControlServer(!controlServer)}
+              className={`
+                relative inline-flex h-7 w-12 items-center rounded-full transition-colors focus:outline-none focus:ring-2 focus:ring-blue-500 focus:ring-offset-2 focus:ring-offset-slate-900
+                ${controlServer ? 'bg-blue-600' : 'bg-gray-600'}
+              `}
+            >
+              <span
+                className={`
+                  inline-block h-5 w-5 transform rounded-full bg-white transition-transform
+                  ${controlServer ? 'translate-x-6' : 'translate-x-1'}
+                `}
+              />
+            </button>
+          </div>
         </div>
       </div>
     </div>
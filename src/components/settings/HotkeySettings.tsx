@@ -3,7 +3,8 @@
 import { useState, useEffect, useRef, useCallback } from 'react';
 import { Keyboard, Check, AlertCircle } from 'lucide-react';
 import { useAppStore } from '../../store/appStore';
-import { invoke } from '@tauri-apps/api/core';
+import { parseHotkey, registerHotkey, validateHotkey } from '../../services/ipc.service';
+import type { HotkeyAction, HotkeyError } from '../../types';
 
 interface HotkeyInputProps {
   label: string;
@@ -11,9 +12,10 @@ interface HotkeyInputProps {
   value: string;
   onChange: (hotkey: string) => void;
   status?: 'idle' | 'success' | 'error';
+  error?: string;
 }
 
-function HotkeyInput({ label, description, value, onChange, status = 'idle' }: HotkeyInputProps) {
+function HotkeyInput({ label, description, value, onChange, status = 'idle', error }: HotkeyInputProps) {
   const [isRecording, setIsRecording] = useState(false);
   const [tempValue, setTempValue] = useState('');
   const inputRef = useRef<HTMLButtonElement>(null);
@@ -69,6 +71,7 @@ function HotkeyInput({ label, description, value, onChange, status = 'idle' }: H
           {status === 'error' && <AlertCircle size={16} className="text-red-400" />}
         </div>
         <div className="text-gray-400 text-sm">{description}</div>
+        {error && <div className="text-red-400 text-sm mt-1">{error}</div>}
       </div>
 
       <button
@@ -91,49 +94,80 @@ function HotkeyInput({ label, description, value, onChange, status = 'idle' }: H
   );
 }
 
-// Parse a hotkey string like "Ctrl+Shift+S" into modifiers and key
-function parseHotkey(hotkeyStr: string): { modifiers: string[]; key: string } {
-  const parts = hotkeyStr.split('+');
-  const key = parts[parts.length - 1];
-  const modifiers = parts.slice(0, -1);
-  return { modifiers, key };
+const ACTION_LABELS: Record<HotkeyAction, string> = {
+  capture: 'Capture Screenshot',
+  full_screen: 'Full-Screen Capture',
+  window_capture: 'Window Capture',
+  repeat_region: 'Repeat Last Region',
+  open_last_sticky: 'Open Last Sticky',
+  ocr: 'OCR Selection',
+};
+
+// Explain why Rust refused a binding
+function describeHotkeyError(error: unknown): string {
+  if (typeof error !== 'object' || error === null || !('kind' in error)) {
+    return String(error);
+  }
+  const hotkeyError = error as HotkeyError;
+  switch (hotkeyError.kind) {
+    case 'missing_key':
+      return 'Press a key to record a shortcut.';
+    case 'modifier_only':
+      return 'Add a regular key to the modifiers (e.g. Ctrl+Shift+S).';
+    case 'unknown_modifier':
+      return `"${hotkeyError.modifier}" is not a supported modifier.`;
+    case 'unknown_key':
+      return `"${hotkeyError.key}" cannot be used as a global shortcut key.`;
+    case 'no_modifier':
+      return `"${hotkeyError.key}" needs a modifier such as Ctrl or Alt, or it would block normal typing.`;
+    case 'reserved':
+      return `${hotkeyError.shortcut} is reserved by the system or common editing commands.`;
+    case 'duplicate':
+      return `${hotkeyError.shortcut} is already used by "${ACTION_LABELS[hotkeyError.action]}".`;
+    case 'registration_failed':
+      return `${hotkeyError.shortcut} could not be registered, another application may be using it.`;
+  }
 }
 
 export function HotkeySettings() {
   const hotkeys = useAppStore((state) => state.hotkeys);
   const setHotkey = useAppStore((state) => state.setHotkey);
   const [hotkeyStatus, setHotkeyStatus] = useState<Record<string, 'idle' | 'success' | 'error'>>({});
-
-  const hotkeyDefinitions: { action: string; label: string; description: string }[] = [
-    { action: 'capture', label: 'Capture Screenshot', description: 'Open the snipping overlay' },
+  const [hotkeyErrors, setHotkeyErrors] = useState<Record<string, string | undefined>>({});
+
+  const hotkeyDefinitions: { action: HotkeyAction; label: string; description: string }[] = [
+    { action: 'capture', label: ACTION_LABELS.capture, description: 'Open the snipping overlay' },
+    { action: 'full_screen', label: ACTION_LABELS.full_screen, description: 'Copy the monitor under the cursor' },
+    { action: 'window_capture', label: ACTION_LABELS.window_capture, description: 'Pick a window to capture' },
+    { action: 'repeat_region', label: ACTION_LABELS.repeat_region, description: 'Capture the previous region again' },
+    { action: 'open_last_sticky', label: ACTION_LABELS.open_last_sticky, description: 'Reopen the most recent sticky' },
+    { action: 'ocr', label: ACTION_LABELS.ocr, description: 'Select a region and extract its text' },
   ];
 
-  // Handle hotkey change with live registration
-  const handleHotkeyChange = useCallback(async (action: string, newHotkey: string) => {
+  // Handle hotkey change with live registration (only this action's binding changes)
+  const handleHotkeyChange = useCallback(async (action: HotkeyAction, newHotkey: string) => {
+    setHotkeyErrors(prev => ({ ...prev, [action]: undefined }));
     try {
-      // 1. Unregister all existing hotkeys
-      await invoke('unregister_hotkey');
-      
-      // 2. Parse the new hotkey
-      const { modifiers, key } = parseHotkey(newHotkey);
-      
-      // 3. Register the new hotkey
-      await invoke('register_hotkey', {
-        config: { key, modifiers }
-      });
+      // 1. Validate (normalises modifier names like Control/Meta/Win)
+      const normalized = await validateHotkey(newHotkey, action);
+
+      // 2. Register the new hotkey, replacing this action's previous one
+      await registerHotkey(parseHotkey(normalized.shortcut), action);
       
-      // 4. Save to store
-      setHotkey(action, newHotkey);
+      // 3. Save to store
+      setHotkey(action, normalized.shortcut);
       
-      // 5. Show success feedback
+      // 4. Show success feedback
       setHotkeyStatus(prev => ({ ...prev, [action]: 'success' }));
       setTimeout(() => {
         setHotkeyStatus(prev => ({ ...prev, [action]: 'idle' }));
       }, 2000);
       
-      console.log(`[HotkeySettings] Registered new hotkey: ${newHotkey}`);
+      console.log(`[HotkeySettings] Registered ${action} hotkey: ${newHotkey}`);
     } catch (error) {
-      console.error('[HotkeySettings] Failed to register hotkey:', error);
+      // Structured HotkeyError from Rust, e.g. the shortcut is already used by another action
+      console.error(`[HotkeySettings] Failed to register ${action} hotkey:`, error);
+      setHotkeyErrors(prev => ({ ...prev, [action]: describeHotkeyError(error) }));
       setHotkeyStatus(prev => ({ ...prev, [action]: 'error' }));
       setTimeout(() => {
         setHotkeyStatus(prev => ({ ...prev, [action]: 'idle' }));
@@ -167,13 +201,14 @@ export function HotkeySettings() {
             value={hotkeys[def.action] || 'Not set'}
             onChange={(hotkey) => handleHotkeyChange(def.action, hotkey)}
             status={hotkeyStatus[def.action] || 'idle'}
+            error={hotkeyErrors[def.action]}
           />
         ))}
       </div>
 
       <div className="text-gray-500 text-sm">
         <Keyboard size={16} className="inline mr-2" />
-        More shortcuts coming soon...
+        Each shortcut can only be bound to one action
       </div>
     </div>
   );
@@ -3,6 +3,14 @@ import { useState, useRef, useEffect } from 'react';
 import { useAppStore } from '../../store/appStore';
 import type { Region } from '../../types';
 import { extractText } from '../../services/ocr.service';
+import {
+  captureSelection,
+  captureUrl,
+  recordStoredCaptureHistory,
+  updateHistoryEntry,
+  copyTextToClipboard,
+} from '../../services/ipc.service';
+import type { ElementStack, WindowLookupError } from '../../services/ipc.service';
 
 interface RegionSelectorProps {
   onDragStart?: () => void;
@@ -17,6 +25,35 @@ interface WindowInfo {
   width: number;
   height: number;
   z_order: number; // Lower = closer to user (top-most)
+  element?: string; // Role of the part of the window picked with the scroll wheel
+}
+
+// Window under the cursor as the backend reported it (physical pixels), and where it was asked
+interface HoveredWindow {
+  window: WindowInfo;
+  x: number;
+  y: number;
+  scale: number;
+  offsetX: number;
+  offsetY: number;
+}
+
+// The window, then the parts of it holding the cursor, in overlay (CSS pixel) coordinates
+function snapTargets(stack: ElementStack, hovered: HoveredWindow): WindowInfo[] {
+  const toOverlay = (rect: { x: number; y: number; width: number; height: number }) => ({
+    x: (rect.x - hovered.offsetX) / hovered.scale,
+    y: (rect.y - hovered.offsetY) / hovered.scale,
+    width: rect.width / hovered.scale,
+    height: rect.height / hovered.scale,
+  });
+  return [
+    { ...stack.window, ...toOverlay(stack.window) },
+    ...stack.elements.map((element) => ({
+      ...stack.window,
+      ...toOverlay(element),
+      element: element.role || 'element',
+    })),
+  ];
 }
 
 export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
@@ -24,6 +61,7 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
   const updateSelection = useAppStore((state) => state.updateSelection);
   const finishSelection = useAppStore((state) => state.finishSelection);
   const setScreenshot = useAppStore((state) => state.setScreenshot);
+  const setScreenshotHistoryId = useAppStore((state) => state.setScreenshotHistoryId);
   const isProcessing = useAppStore((state) => state.isProcessing);
   const setProcessing = useAppStore((state) => state.setProcessing);
   const setOCRLoading = useAppStore((state) => state.setOCRLoading);
@@ -32,6 +70,7 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
   const setOCRError = useAppStore((state) => state.setOCRError);
   const isSmartSelectActive = useAppStore((state) => state.isSmartSelectActive);
   const monitorOffset = useAppStore((state) => state.monitorOffset);
+  const selectionTarget = useAppStore((state) => state.selectionTarget);
   // const toggleSmartSelect = useAppStore((state) => state.toggleSmartSelect); // Removed from store
 
   const [isMouseDown, setIsMouseDown] = useState(false);
@@ -44,8 +83,13 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
   const [highlightedWindow, setHighlightedWindow] = useState<WindowInfo | null>(null);
   const lastCheckRef = useRef<{ x: number; y: number; time: number } | null>(null);
   const lastWindowIdRef = useRef<number | null>(null);
+  const snapTargetsRef = useRef<WindowInfo[]>([]); // Window under the cursor, then its parts
+  const snapDepthRef = useRef<number>(0); // Index into snapTargetsRef, set with the scroll wheel
+  const hoveredRef = useRef<HoveredWindow | null>(null);
+  const elementsWantedRef = useRef<boolean>(false); // Scroll wheel used over the hovered window
   const callIdRef = useRef<number>(0); // Track API call order to prevent race conditions
   const isCheckingWindowRef = useRef<boolean>(false); // Prevent overlapping window detection calls
+  const smartSelectUnsupportedRef = useRef<boolean>(false); // e.g. Wayland: stop asking the backend
 
   // Handle mouse down - initiate tracking
   // We attach this to the global window as well to catch edge clicks
@@ -93,8 +137,8 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
   const handleMouseMove = (e: React.MouseEvent) => {
     const { clientX, clientY } = e;
 
-    // Logic 1: Mouse is UP - Just hovering (Smart Select Mode)
-    if (!isMouseDown && isSmartSelectActive) {
+    // Logic 1: Mouse is UP - Just hovering (Smart Select Mode, always on for window capture)
+    if (!isMouseDown && (isSmartSelectActive || selectionTarget === 'window')) {
       // Logic 1.1: Check for Edge Hover (Frontend Event - Fast Path)
       const EDGE_THRESHOLD = 20;
       const w = window.innerWidth;
@@ -139,8 +183,8 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
 
       lastCheckRef.current = { x: clientX, y: clientY, time: now };
 
-      // Skip if a window detection is already in progress
-      if (isCheckingWindowRef.current) {
+      // Skip if a window detection is already in progress (or can never succeed here)
+      if (isCheckingWindowRef.current || smartSelectUnsupportedRef.current) {
         return;
       }
 
@@ -180,24 +224,44 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
           }
 
           if (windowAtPoint) {
-            // Scale coordinates back to CSS pixels for display
-            // AND subtract monitor offset to get overlay-relative coordinates
-            const scaledWindow = {
-              ...windowAtPoint,
-              x: (windowAtPoint.x - offsetX) / scale,
-              y: (windowAtPoint.y - offsetY) / scale,
-              width: windowAtPoint.width / scale,
-              height: windowAtPoint.height / scale,
+            const hovered: HoveredWindow = {
+              window: windowAtPoint,
+              x: physicalX,
+              y: physicalY,
+              scale,
+              offsetX,
+              offsetY,
             };
-
+            const sameWindow = windowAtPoint.id === lastWindowIdRef.current;
+            hoveredRef.current = hovered;
             lastWindowIdRef.current = windowAtPoint.id;
-            setHighlightedWindow(scaledWindow);
+
+            if (sameWindow && elementsWantedRef.current) {
+              // Follow the cursor through the parts of the window, keeping the snap depth
+              void loadSnapTargets(hovered);
+            } else {
+              snapDepthRef.current = 0;
+              elementsWantedRef.current = false;
+              snapTargetsRef.current = snapTargets(
+                { window: windowAtPoint, elements: [] },
+                hovered
+              );
+              setHighlightedWindow(snapTargetsRef.current[0]);
+            }
           } else {
+            hoveredRef.current = null;
             lastWindowIdRef.current = null;
+            elementsWantedRef.current = false;
+            snapTargetsRef.current = [];
             setHighlightedWindow(null);
           }
         } catch (e) {
           console.error('[Smart Select] Error:', e);
+          // Structured WindowLookupError from Rust: on Wayland, stop asking
+          const lookupError = e as WindowLookupError | null;
+          if (typeof lookupError === 'object' && lookupError?.kind === 'unsupported') {
+            smartSelectUnsupportedRef.current = true;
+          }
           if (currentCallId === callIdRef.current) {
             setHighlightedWindow(null);
           }
@@ -222,8 +286,8 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
 
       const DRAG_THRESHOLD = 5; // pixels
 
-      // Transition to Dragging Mode if moved enough
-      if (!isDragging && dist > DRAG_THRESHOLD) {
+      // Transition to Dragging Mode if moved enough (window capture only picks windows)
+      if (!isDragging && dist > DRAG_THRESHOLD && selectionTarget !== 'window') {
         setIsDragging(true);
         startSelection(); // Notify store we are really selecting now
         onDragStart?.();
@@ -244,6 +308,56 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
     }
   };
 
+  // Look up the parts of the hovered window. Only done once the scroll wheel asks for them:
+  // walking an accessibility tree costs far more than finding the window.
+  // The window is passed along, so the overlay does not have to let clicks through meanwhile.
+  const loadSnapTargets = async (hovered: HoveredWindow): Promise<WindowInfo[]> => {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      const stack = await invoke<ElementStack | null>('get_elements_at_point', {
+        x: hovered.x,
+        y: hovered.y,
+        window: hovered.window,
+      });
+
+      // The cursor has moved on since
+      if (!stack || hoveredRef.current !== hovered) {
+        return snapTargetsRef.current;
+      }
+
+      const targets = snapTargets(stack, hovered);
+      snapTargetsRef.current = targets;
+      setHighlightedWindow(targets[Math.min(snapDepthRef.current, targets.length - 1)]);
+      return targets;
+    } catch (e) {
+      console.error('[Smart Select] Element lookup failed:', e);
+      return snapTargetsRef.current;
+    }
+  };
+
+  // Handle scroll wheel - snap to the element inside (down) or around (up) the highlighted one
+  const handleWheel = async (e: React.WheelEvent) => {
+    const hovered = hoveredRef.current;
+    if (isMouseDown || !highlightedWindow || highlightedWindow.id === -999 || !hovered) {
+      return;
+    }
+
+    const step = e.deltaY > 0 ? 1 : -1;
+    let targets = snapTargetsRef.current;
+    if (!elementsWantedRef.current) {
+      elementsWantedRef.current = true;
+      targets = await loadSnapTargets(hovered);
+    }
+    if (targets.length < 2) {
+      return;
+    }
+
+    const current = Math.min(snapDepthRef.current, targets.length - 1);
+    const depth = Math.max(0, Math.min(targets.length - 1, current + step));
+    snapDepthRef.current = depth;
+    setHighlightedWindow(targets[depth]);
+  };
+
   // Handle mouse up - finish selection or click-capture
   const handleMouseUp = async () => {
     if (!isMouseDown) return;
@@ -306,7 +420,7 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
           const fullScreenRegion = { x: 0, y: 0, width: w, height: h };
           await captureRegion(fullScreenRegion);
         } else {
-          await captureRegion(region);
+          await captureRegion(region, highlightedWindow ?? undefined);
         }
       } else {
         setCurrentRegion(null);
@@ -317,7 +431,7 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
 
 
   // Capture function
-  const captureRegion = async (inputRegion: Region) => {
+  const captureRegion = async (inputRegion: Region, sourceWindow?: WindowInfo) => {
     try {
       setProcessing(true);
 
@@ -335,9 +449,6 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
       // before the native screenshot is taken. 0ms causes intermittent dark captures.
       await new Promise(resolve => requestAnimationFrame(() => setTimeout(resolve, 30)));
 
-      // Import Tauri API dynamically
-      const { invoke } = await import('@tauri-apps/api/core');
-      
       const scale = window.devicePixelRatio || 1;
       
       // Add monitor offset to convert window coordinates to virtual desktop coordinates
@@ -351,29 +462,42 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
           '(offset:', monitorOffset.x, monitorOffset.y, ')');
       }
       
-      const base64Data = await invoke<string>('capture_screen', {
+      const physicalRegion = {
         x: virtualX,
         y: virtualY,
         width: Math.round(region.width * scale),
         height: Math.round(region.height * scale),
-      });
-
-      // Convert Base64 string to Blob
-      // Note: Backend now returns BMP (uncompressed) for speed, but browsers handle BMP well as image source
-      const res = await fetch(`data:image/bmp;base64,${base64Data}`);
-      const blob = await res.blob();
-      const imageUrl = URL.createObjectURL(blob);
+      };
+      // A picked window is grabbed on its own, so windows above it do not show up.
+      // The capture stays in the capture store; the editor saves, copies and pins it by id.
+      const wholeWindow = !!sourceWindow && !sourceWindow.element;
+      const capture = await captureSelection(physicalRegion, sourceWindow, wholeWindow);
+      const imageUrl = captureUrl(capture.capture_id);
 
       // Update store
       const screenshot = {
         id: crypto.randomUUID(),
         imageData: imageUrl,
+        captureId: capture.capture_id,
         region,
-        timestamp: Date.now(),
+        timestamp: capture.captured_at,
         mode: 'capture' as const,
       };
       setScreenshot(screenshot);
 
+      // Record in the capture history in the background (OCR text is added once ready)
+      const historyEntry = recordStoredCaptureHistory(capture.capture_id, {
+        region: physicalRegion,
+      })
+        .then((entry) => {
+          setScreenshotHistoryId(screenshot.id, entry.id);
+          return entry;
+        })
+        .catch((error) => {
+          console.error('[RegionSelector] Failed to record capture history:', error);
+          return null;
+        });
+
       // 🚀 START BACKGROUND OCR IMMEDIATELY
       console.log('[RegionSelector] Starting OCR...');
       setOCRLoading(true);
@@ -385,6 +509,22 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
         .then((result) => {
           console.log('[RegionSelector] OCR completed:', result);
           setOCRResult(result);
+
+          // OCR hotkey: the recognised text is what the user is after
+          if (selectionTarget === 'text' && result.text.trim()) {
+            copyTextToClipboard(result.text.trim()).catch((error) => {
+              console.error('[RegionSelector] Failed to copy recognised text:', error);
+            });
+          }
+
+          // Make the capture searchable by its text
+          historyEntry.then((entry) => {
+            if (entry && result.text.trim()) {
+              updateHistoryEntry(entry.id, { ocr_text: result.text }).catch((error) => {
+                console.error('[RegionSelector] Failed to save OCR text to history:', error);
+              });
+            }
+          });
         })
         .catch((error) => {
           console.error('[RegionSelector] OCR failed:', error);
@@ -427,6 +567,7 @@ export function RegionSelector({ onDragStart }: RegionSelectorProps = {}) {
       onMouseDown={handleMouseDown}
       onMouseMove={handleMouseMove}
       onMouseUp={handleMouseUp}
+      onWheel={handleWheel}
 
     >
       {/* Dimming Overlay with Hole - COMPLETELY HIDE WHEN PROCESSING */}
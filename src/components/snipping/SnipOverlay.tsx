@@ -8,6 +8,7 @@ import type { CaptureMode } from '../../types';
 
 export function SnipOverlay() {
   const mode = useAppStore((state) => state.currentMode);
+  const selectionTarget = useAppStore((state) => state.selectionTarget);
   const setMode = useAppStore((state) => state.setMode);
   const isProcessing = useAppStore((state) => state.isProcessing);
   const [isSelecting, setIsSelecting] = useState(false);
@@ -66,7 +67,10 @@ export function SnipOverlay() {
       {/* Hint text - Hide when processing */}
       {!isProcessing && (
       <div className="absolute top-24 left-1/2 -translate-x-1/2 text-white text-sm bg-black/60 px-4 py-2 rounded-lg animate-fadeIn">
-        Click and drag to select area • Press ESC to cancel
+        {selectionTarget === 'window' && 'Click a window to capture it • Press ESC to cancel'}
+        {selectionTarget === 'text' &&
+          'Select the text to recognise and copy • Press ESC to cancel'}
+        {selectionTarget === 'region' && 'Click and drag to select area • Press ESC to cancel'}
       </div>
       )}
 
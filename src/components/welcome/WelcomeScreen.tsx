@@ -1,8 +1,9 @@
 // JustSnap - Welcome Screen
 // A friendly first-launch screen that shows users the hotkeys
 import { useState } from 'react';
-import { Settings } from 'lucide-react';
+import { FolderOpen, Settings } from 'lucide-react';
 import { useAppStore } from '../../store/appStore';
+import { openImage } from '../../services/ipc.service';
 import { SettingsPage } from '../settings/SettingsPage';
 
 export function WelcomeScreen() {
@@ -79,6 +80,17 @@ export function WelcomeScreen() {
               </div>
            </div>
 
+           {/* Secondary: Open an existing image (files can also be dropped on this window) */}
+           <button 
+              onClick={() => openImage().catch((err) => console.error('[Welcome] Failed to open image:', err))}
+              className="w-full bg-white/5 border border-white/5 rounded-xl py-3 px-4 flex items-center justify-center gap-2 hover:bg-white/10 hover:border-white/20 transition-all group"
+           >
+              <FolderOpen size={14} className="text-gray-400 group-hover:text-white transition-colors" />
+              <span className="text-sm font-medium text-gray-400 group-hover:text-white transition-colors">
+                 Open Image or Drop It Here
+              </span>
+           </button>
+
            {/* Secondary: Customize Button (Same width, smaller height) */}
            <button 
               onClick={() => setShowSettings(true)}
@@ -279,7 +279,7 @@ export function StickyWindow() {
          try {
              const buffer = await blob.arrayBuffer();
              const bytes = new Uint8Array(buffer);
-             await invoke('copy_image_to_clipboard', { imageData: Array.from(bytes) });
+             await invoke('copy_image_to_clipboard', bytes);
              setFeedback("Copied!");
          } catch (backendErr) {
              console.warn("Backend copy failed, trying frontend fallback", backendErr);
@@ -332,9 +332,8 @@ export function StickyWindow() {
           }
 
           if (path) {
-              await invoke('save_image', {
-                  path,
-                  imageData: Array.from(bytes)
+              await invoke('save_image', bytes, {
+                  headers: { 'x-justsnap-path': encodeURIComponent(path) }
               });
               setFeedback("Saved!");
           }
@@ -3,6 +3,7 @@ import { createRoot } from 'react-dom/client'
 import './index.css'
 import App from './App.tsx'
 import { getCurrentWindow } from '@tauri-apps/api/window'
+import { startSettingsSync } from './services/settings.service'
 
 // CRITICAL DEBUG: Global Error Handler
 window.onerror = function(message, source, lineno, _colno, _error) {
@@ -13,6 +14,11 @@ window.onunhandledrejection = function(event) {
   alert(`Unhandled Promise Rejection: ${event.reason}`);
 };
 
+// Settings are owned by Rust; every window loads them and follows changes
+if (window.__TAURI_INTERNALS__) {
+  startSettingsSync();
+}
+
 // Lazy load window components - each window only loads its necessary code
 const StickyWindow = lazy(() => import('./components/window/StickyWindow.tsx').then(m => ({ default: m.StickyWindow })));
 const AIPanelWindow = lazy(() => import('./components/window/AIPanelWindow.tsx').then(m => ({ default: m.AIPanelWindow })));
@@ -1,18 +1,49 @@
 // JustSnap IPC Service - Tauri Command Wrapper
 // Provides type-safe communication between React and Rust
 
-import { invoke } from '@tauri-apps/api/core';
+import { convertFileSrc, invoke } from '@tauri-apps/api/core';
 import type {
   TauriCaptureRequest,
+  ClipboardExtras,
+  ClipboardFlavour,
   Region,
+  HotkeyAction,
+  HotkeyBinding,
   HotkeyConfig,
+  HotkeyRegistration,
+  ExportMetadata,
+  HistoryEntry,
+  HistoryQuery,
+  HistoryRegion,
+  NormalizedHotkey,
+  OpenedImage,
+  OpenTarget,
+  RecentRegion,
+  RepeatedCapture,
+  Settings,
+  WindowCaptureOptions,
 } from '../types';
 
 /**
  * Screen Capture Commands
  */
 
-export async function captureScreen(region: Region): Promise<Uint8Array> {
+/**
+ * Output encoding for capture commands (mirrors Rust `OutputFormat`).
+ * `rgba` returns raw pixels prefixed by width/height as little-endian u32.
+ */
+export type OutputFormat =
+  | { format: 'bmp' }
+  | { format: 'png'; level?: number }
+  | { format: 'jpeg'; quality?: number }
+  | { format: 'webp'; lossless?: boolean; quality?: number }
+  | { format: 'qoi' }
+  | { format: 'rgba' };
+
+export async function captureScreen(
+  region: Region,
+  format?: OutputFormat
+): Promise<Uint8Array> {
   const request: TauriCaptureRequest = {
     x: region.x,
     y: region.y,
@@ -20,41 +51,181 @@ export async function captureScreen(region: Region): Promise<Uint8Array> {
     height: region.height,
   };
 
-  const base64 = await invoke<string>('capture_screen', request as any);
+  // Backend replies with a raw binary payload (no Base64)
+  const buffer = await invoke<ArrayBuffer>('capture_screen', { ...request, format } as any);
+  return new Uint8Array(buffer);
+}
 
-  // Convert Base64 string to Uint8Array
-  const binaryString = atob(base64);
-  const len = binaryString.length;
-  const bytes = new Uint8Array(len);
-  for (let i = 0; i < len; i++) {
-    bytes[i] = binaryString.charCodeAt(i);
-  }
-  return bytes;
+/**
+ * A selection captured into the capture store (mirrors Rust `SelectionCapture`)
+ */
+export interface SelectionCapture {
+  capture_id: string;
+  width: number;
+  height: number;
+  captured_at: number; // Unix time in milliseconds
+}
+
+/**
+ * Capture an overlay selection (virtual desktop pixels) into the capture store.
+ * With `wholeWindow`, `window` is grabbed on its own so windows covering it do not show up.
+ * The capture remembers its monitor and window; release it once it is no longer shown.
+ */
+export async function captureSelection(
+  region: Region,
+  window?: WindowInfo,
+  wholeWindow = false
+): Promise<SelectionCapture> {
+  return await invoke<SelectionCapture>('capture_selection', {
+    selection: { ...region, window, whole_window: wholeWindow },
+  });
 }
 
-export async function captureFullScreen(): Promise<Uint8Array> {
-  const base64 = await invoke<string>('capture_full_screen');
+/**
+ * Capture the most recent `captureScreen` region again without the overlay.
+ * Goes to the auto-save folder when auto-save is on, to the clipboard otherwise.
+ */
+export async function repeatLastRegion(): Promise<RepeatedCapture> {
+  return await invoke<RepeatedCapture>('repeat_last_region');
+}
 
-  // Convert Base64 string to Uint8Array
-  const binaryString = atob(base64);
-  const len = binaryString.length;
-  const bytes = new Uint8Array(len);
-  for (let i = 0; i < len; i++) {
-    bytes[i] = binaryString.charCodeAt(i);
-  }
-  return bytes;
+/**
+ * Regions captured with `captureScreen`, newest first (the last 10 are kept)
+ */
+export async function getRecentRegions(): Promise<RecentRegion[]> {
+  return await invoke<RecentRegion[]>('get_recent_regions');
+}
+
+/**
+ * Capture the full screen. With `delaySecs` the capture waits for a countdown
+ * (`capture-countdown` events) and rejects if it is cancelled; it then captures the monitor
+ * under the cursor and opens the overlay on it, like the capture hotkey.
+ */
+export async function captureFullScreen(
+  format?: OutputFormat,
+  delaySecs?: number
+): Promise<Uint8Array> {
+  const buffer = await invoke<ArrayBuffer>('capture_full_screen', { format, delaySecs });
+  return new Uint8Array(buffer);
+}
+
+/**
+ * Placement of a monitor inside a virtual desktop capture (mirrors Rust `MonitorLayout`)
+ */
+export interface MonitorLayout {
+  id: number;
+  name: string;
+  offset_x: number;
+  offset_y: number;
+  width: number;
+  height: number;
+  scale_factor: number;
+  is_primary: boolean;
+}
+
+export interface DesktopLayout {
+  origin_x: number;
+  origin_y: number;
+  width: number;
+  height: number;
+  monitors: MonitorLayout[];
+}
+
+export interface VirtualDesktopCapture {
+  image: Uint8Array;
+  layout: DesktopLayout;
+}
+
+/**
+ * Capture the whole virtual desktop. The response carries the layout it was taken with:
+ * the layout JSON's length (little-endian u32), the layout JSON, then the image.
+ */
+export async function captureAllMonitors(
+  fill?: [number, number, number, number],
+  format?: OutputFormat
+): Promise<VirtualDesktopCapture> {
+  const buffer = await invoke<ArrayBuffer>('capture_all_monitors', { fill, format });
+  const layoutLength = new DataView(buffer).getUint32(0, true);
+  const layoutJson = new TextDecoder().decode(new Uint8Array(buffer, 4, layoutLength));
+  return {
+    image: new Uint8Array(buffer, 4 + layoutLength),
+    layout: JSON.parse(layoutJson) as DesktopLayout,
+  };
 }
 
 /**
  * Hotkey Commands
  */
 
-export async function registerHotkey(config: HotkeyConfig): Promise<void> {
-  await invoke('register_hotkey', { config });
+export async function registerHotkey(
+  config: HotkeyConfig,
+  action: HotkeyAction = 'capture'
+): Promise<void> {
+  await invoke('register_hotkey', { config, action });
+}
+
+/**
+ * Parse a hotkey string like "Ctrl+Shift+S" into modifiers and key
+ */
+export function parseHotkey(hotkey: string): HotkeyConfig {
+  const parts = hotkey.split('+');
+  const key = parts[parts.length - 1];
+  const modifiers = parts.slice(0, -1);
+  return { key, modifiers };
+}
+
+/**
+ * Check a hotkey string without binding it. Rejects with a `HotkeyError`
+ * (e.g. `{ kind: 'duplicate', ... }`) when the binding would be refused.
+ */
+export async function validateHotkey(
+  hotkey: string,
+  action?: HotkeyAction
+): Promise<NormalizedHotkey> {
+  return await invoke<NormalizedHotkey>('validate_hotkey', {
+    config: parseHotkey(hotkey),
+    action,
+  });
+}
+
+/**
+ * Bind hotkey strings per action, e.g. `{ capture: 'Ctrl+Shift+S' }`.
+ * Conflicts are reported per action rather than failing the whole call.
+ */
+export async function registerHotkeys(
+  hotkeys: Partial<Record<HotkeyAction, string>>
+): Promise<HotkeyRegistration[]> {
+  const configs = Object.fromEntries(
+    Object.entries(hotkeys)
+      .filter(([, hotkey]) => !!hotkey)
+      .map(([action, hotkey]) => [action, parseHotkey(hotkey as string)])
+  );
+  return await invoke<HotkeyRegistration[]>('register_hotkeys', { hotkeys: configs });
+}
+
+/**
+ * Unbind one action's hotkey, or all hotkeys when no action is given
+ */
+export async function unregisterHotkey(action?: HotkeyAction): Promise<void> {
+  await invoke('unregister_hotkey', { action });
+}
+
+export async function getHotkeys(): Promise<HotkeyBinding[]> {
+  return await invoke<HotkeyBinding[]>('get_hotkeys');
+}
+
+/**
+ * Timed capture of the monitor under the cursor; the result arrives via `screen-capture-ready`
+ */
+export async function startDelayedCapture(
+  delaySecs: number,
+  action: HotkeyAction = 'capture'
+): Promise<void> {
+  await invoke('start_delayed_capture', { delaySecs, action });
 }
 
-export async function unregisterHotkey(): Promise<void> {
-  await invoke('unregister_hotkey');
+export async function cancelDelayedCapture(): Promise<boolean> {
+  return await invoke<boolean>('cancel_delayed_capture');
 }
 
 /**
@@ -73,14 +244,22 @@ export async function hideOverlay(): Promise<void> {
  * File System Commands
  */
 
+/**
+ * Save encoded image bytes, converted to the format of the path's extension
+ * (png, jpg, webp, tiff, bmp, qoi, raw, pdf). `metadata` is embedded in PNG,
+ * JPEG, WebP and PDF files.
+ */
 export async function saveImage(
   imageData: Uint8Array,
-  path: string
+  path: string,
+  metadata?: ExportMetadata
 ): Promise<void> {
-  await invoke('save_image', {
-    imageData: Array.from(imageData),
-    path,
-  });
+  // Bytes travel as the raw request body, the path and metadata as headers
+  const headers: Record<string, string> = { 'x-justsnap-path': encodeURIComponent(path) };
+  if (metadata) {
+    headers['x-justsnap-metadata'] = encodeURIComponent(JSON.stringify(metadata));
+  }
+  await invoke('save_image', imageData, { headers });
 }
 
 export async function saveText(content: string, fileName: string): Promise<string> {
@@ -98,9 +277,21 @@ export async function openSaveDialog(defaultName: string): Promise<string | null
  */
 
 export async function copyImageToClipboard(imageData: Uint8Array): Promise<void> {
-  await invoke('copy_image_to_clipboard', {
-    imageData: Array.from(imageData),
-  });
+  await invoke('copy_image_to_clipboard', imageData);
+}
+
+/**
+ * Copy an image (encoded bytes, may be empty) together with plain text, HTML and a file list,
+ * so paste targets can pick the richest format they understand.
+ * Windows receives every flavour; other platforms get the image, else HTML + text.
+ * Check the returned flavours: whatever is missing from them was not put on the clipboard.
+ */
+export async function copyToClipboard(
+  imageData: Uint8Array,
+  extras: ClipboardExtras = {}
+): Promise<ClipboardFlavour[]> {
+  const headers = { 'x-justsnap-clipboard': encodeURIComponent(JSON.stringify(extras)) };
+  return await invoke<ClipboardFlavour[]>('copy_to_clipboard', imageData, { headers });
 }
 
 export async function copyTextToClipboard(text: string): Promise<void> {
@@ -108,12 +299,189 @@ export async function copyTextToClipboard(text: string): Promise<void> {
 }
 
 export async function saveTempImage(imageData: Uint8Array): Promise<string> {
-  const path = await invoke<string>('save_temp_image', {
-    imageData: Array.from(imageData),
-  });
+  const path = await invoke<string>('save_temp_image', imageData);
   return path;
 }
 
+/**
+ * Capture Store Commands
+ * Captures live in memory on the Rust side and are referenced by id
+ */
+
+export function captureUrl(captureId: string): string {
+  return convertFileSrc(captureId, 'justsnap');
+}
+
+export async function getCapture(captureId: string, format?: OutputFormat): Promise<Uint8Array> {
+  const buffer = await invoke<ArrayBuffer>('get_capture', { captureId, format });
+  return new Uint8Array(buffer);
+}
+
+/**
+ * Store an encoded image. Made from `baseCaptureId` (e.g. annotated), it keeps that capture's
+ * time, monitor and window for auto-save names and embedded metadata.
+ */
+export async function storeCapture(imageData: Uint8Array, baseCaptureId?: string): Promise<string> {
+  const headers: Record<string, string> = baseCaptureId
+    ? { 'x-justsnap-base-capture': encodeURIComponent(baseCaptureId) }
+    : {};
+  return await invoke<string>('store_capture', imageData, { headers });
+}
+
+export async function cropCapture(captureId: string, region: Region): Promise<string> {
+  const { x, y, width, height } = region;
+  return await invoke<string>('crop_capture', { captureId, x, y, width, height });
+}
+
+export async function saveCapture(
+  captureId: string,
+  path: string,
+  includeMetadata = true
+): Promise<void> {
+  await invoke('save_capture', { captureId, path, includeMetadata });
+}
+
+/**
+ * Save several captures to one file (PDF: one page per capture)
+ */
+export async function exportCaptures(
+  captureIds: string[],
+  path: string,
+  includeMetadata = true
+): Promise<void> {
+  await invoke('export_captures', { captureIds, path, includeMetadata });
+}
+
+/**
+ * Save a capture into the save folder under a templated name, returning the final path.
+ * Placeholders: {date}, {time} (optionally with a chrono format, e.g. {date:%Y%m%d}),
+ * {app_name}, {title}, {monitor}, {n} (collision counter) and {ext}.
+ * Template and folder default to the `auto_save_template` / `save_path` settings.
+ */
+export async function autoSaveCapture(
+  captureId: string,
+  options: {
+    window?: WindowInfo;
+    template?: string;
+    directory?: string;
+    format?: OutputFormat;
+  } = {}
+): Promise<string> {
+  return await invoke<string>('auto_save_capture', { captureId, ...options });
+}
+
+/**
+ * Copy a stored capture, with `extras` adding text, HTML and file-list flavours.
+ * Resolves to the flavours that were set.
+ */
+export async function copyCaptureToClipboard(
+  captureId: string,
+  extras?: ClipboardExtras
+): Promise<ClipboardFlavour[]> {
+  return await invoke<ClipboardFlavour[]>('copy_capture_to_clipboard', { captureId, extras });
+}
+
+export async function releaseCapture(captureId: string): Promise<boolean> {
+  return await invoke<boolean>('release_capture', { captureId });
+}
+
+/**
+ * Capture History Commands
+ * Every capture is recorded with a thumbnail; changes are broadcast as `history-changed`
+ */
+
+/**
+ * Record encoded image bytes in the history (region in virtual desktop pixels)
+ */
+export async function recordCaptureHistory(
+  imageData: Uint8Array,
+  details: {
+    captured_at?: number;
+    region?: HistoryRegion;
+    monitor_name?: string; // Looked up from the region when omitted
+    window?: WindowInfo;
+    window_title?: string;
+    app_name?: string;
+    saved_path?: string;
+    ocr_text?: string;
+  } = {}
+): Promise<HistoryEntry> {
+  const headers = { 'x-justsnap-history': encodeURIComponent(JSON.stringify(details)) };
+  return await invoke<HistoryEntry>('record_capture_history', imageData, { headers });
+}
+
+/**
+ * Record a stored capture in the history with its own capture time, monitor and window
+ */
+export async function recordStoredCaptureHistory(
+  captureId: string,
+  details: {
+    region?: HistoryRegion;
+    saved_path?: string;
+    ocr_text?: string;
+  } = {}
+): Promise<HistoryEntry> {
+  return await invoke<HistoryEntry>('record_stored_capture_history', { captureId, details });
+}
+
+export async function listHistory(query: HistoryQuery = {}): Promise<HistoryEntry[]> {
+  return await invoke<HistoryEntry[]>('list_history', { query });
+}
+
+/**
+ * JPEG thumbnail bytes of a history entry
+ */
+export async function getHistoryThumbnail(id: number): Promise<Uint8Array> {
+  const buffer = await invoke<ArrayBuffer>('get_history_thumbnail', { id });
+  return new Uint8Array(buffer);
+}
+
+export async function updateHistoryEntry(
+  id: number,
+  update: { saved_path?: string; ocr_text?: string }
+): Promise<HistoryEntry> {
+  return await invoke<HistoryEntry>('update_history_entry', { id, update });
+}
+
+export async function deleteHistory(ids: number[]): Promise<number> {
+  return await invoke<number>('delete_history', { ids });
+}
+
+/**
+ * Open a history entry in the editor (the main window receives `open-in-editor`).
+ * Returns the id of the capture holding its image.
+ */
+export async function reopenHistory(id: number): Promise<string> {
+  return await invoke<string>('reopen_history', { id });
+}
+
+/**
+ * Image Input Commands
+ */
+
+/**
+ * Read an image (or a copied image file / path) from the clipboard and open it like a capture:
+ * in the editor, pinned as a sticky in the middle of the screen, or only stored ('none').
+ */
+export async function pasteImageFromClipboard(target: OpenTarget = 'editor'): Promise<OpenedImage> {
+  return await invoke<OpenedImage>('paste_image_from_clipboard', { target });
+}
+
+/**
+ * Open image files (GIFs give their first frame). The first goes to `target`, the others are
+ * pinned as stickies. Without `paths` the open dialog asks for them (empty result = cancelled).
+ */
+export async function openImage(paths?: string[], target: OpenTarget = 'editor'): Promise<OpenedImage[]> {
+  return await invoke<OpenedImage[]>('open_image', { paths, target });
+}
+
+/**
+ * Open the image files JustSnap was started with (call once the `open-in-editor` listener is set up)
+ */
+export async function openStartupFiles(): Promise<OpenedImage[]> {
+  return await invoke<OpenedImage[]>('open_startup_files');
+}
+
 /**
  * Multi-Window Commands
  */
@@ -126,11 +494,13 @@ export async function createStickyWindow(
   width: number,
   height: number,
   nativeWidth: number,
-  nativeHeight: number
+  nativeHeight: number,
+  captureId?: string
 ): Promise<void> {
   console.log('[IPC] createStickyWindow called', { x, y, width, height, nativeWidth, nativeHeight });
   await invoke('create_sticky_window', {
     imageSrc,
+    captureId,
     annotationsJson: JSON.stringify(annotations || []),
     x,
     y,
@@ -171,21 +541,103 @@ export async function closeWindow(): Promise<void> {
  */
 
 export interface WindowInfo {
+  id: number;
   title: string;
+  app_name: string;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  z_order: number; // Lower number = closer to user (top-most)
+  monitor_id?: number | null; // Monitor showing most of the window
+  pid?: number | null;
+  is_minimized?: boolean;
+  is_visible?: boolean; // Not minimized and not empty
+  visible_regions?: Region[]; // Parts not covered by windows above it
+  visible_ratio?: number; // Share of the window that is visible (0-1)
+}
+
+// A part of a window: child window or accessibility element (mirrors Rust `UiElement`)
+export interface UiElement {
   x: number;
   y: number;
   width: number;
   height: number;
+  role: string; // Window class on Windows, accessibility role on Linux
+  name: string;
+}
+
+// Window under a point and its elements holding the point, outermost first
+export interface ElementStack {
+  window: WindowInfo;
+  elements: UiElement[];
+}
+
+// Why `getWindowAtPosition` / `getElementsAtPosition` failed (mirrors Rust `WindowLookupError`)
+export type WindowLookupError =
+  | { kind: 'unsupported'; message: string } // Wayland: asking again will not help
+  | { kind: 'failed'; message: string };
+
+// Which windows `listWindows` returns (mirrors Rust `WindowFilter`)
+export interface WindowFilter {
+  app_name?: string; // Regex, e.g. "(?i)^code$"
+  title?: string; // Regex
+  include_minimized?: boolean;
+}
+
+/**
+ * Capture one window's own pixels (windows covering it do not show up).
+ * Resolves to a PNG with a transparent background.
+ */
+export async function captureWindow(
+  id: number,
+  options?: WindowCaptureOptions
+): Promise<Uint8Array> {
+  const buffer = await invoke<ArrayBuffer>('capture_window', { id, options });
+  return new Uint8Array(buffer);
 }
 
-export async function getWindowAtPosition(x: number, y: number): Promise<WindowInfo | null> {
-  const window = await invoke<WindowInfo | null>('get_window_at_position', { x, y });
+/**
+ * Window under a point (physical pixels), with the parts of it that are not covered.
+ * Windows showing less than `minVisibleRatio` (0-1) of themselves resolve to null.
+ */
+export async function getWindowAtPosition(
+  x: number,
+  y: number,
+  minVisibleRatio?: number
+): Promise<WindowInfo | null> {
+  const window = await invoke<WindowInfo | null>('get_window_at_point', {
+    x,
+    y,
+    minVisibleRatio,
+  });
   return window;
 }
 
-export async function getAllWindows(): Promise<WindowInfo[]> {
-  const windows = await invoke<WindowInfo[]>('get_all_windows');
-  return windows;
+/**
+ * Window under a point (physical pixels) with the nested elements inside it that hold the
+ * point, so a picker can snap to part of the window. Pass the `window` already returned by
+ * `getWindowAtPosition` to look up only its elements.
+ */
+export async function getElementsAtPosition(
+  x: number,
+  y: number,
+  minVisibleRatio?: number,
+  window?: WindowInfo
+): Promise<ElementStack | null> {
+  return await invoke<ElementStack | null>('get_elements_at_point', {
+    x,
+    y,
+    minVisibleRatio,
+    window,
+  });
+}
+
+/**
+ * Top-level windows front to back, without JustSnap's own and shell windows
+ */
+export async function listWindows(filter?: WindowFilter): Promise<WindowInfo[]> {
+  return await invoke<WindowInfo[]>('list_windows', { filter });
 }
 
 /**
@@ -210,15 +662,26 @@ export async function getAllMonitors(): Promise<MonitorInfo[]> {
 
 /**
  * App Settings Commands
+ * Settings live in a versioned file owned by Rust; changes are broadcast as `settings-changed`
  */
 
-export async function getSettings(): Promise<Record<string, unknown>> {
-  const settings = await invoke<Record<string, unknown>>('get_settings');
-  return settings;
+export async function getSettings(): Promise<Settings> {
+  return await invoke<Settings>('get_settings');
 }
 
-export async function saveSettings(settings: Record<string, unknown>): Promise<void> {
-  await invoke('save_settings', { settings });
+/**
+ * Save the given fields (snake_case keys) and apply them (hotkeys, start at login)
+ */
+export async function updateSettings(patch: Partial<Settings>): Promise<Settings> {
+  return await invoke<Settings>('update_settings', { patch });
+}
+
+/**
+ * Hand the webview's previously persisted settings to Rust.
+ * Only used when no settings file exists yet; returns the current settings.
+ */
+export async function importLegacySettings(state: Record<string, unknown>): Promise<Settings> {
+  return await invoke<Settings>('import_legacy_settings', { state });
 }
 
 /**
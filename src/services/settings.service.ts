@@ -0,0 +1,96 @@
+// JustSnap - Settings Sync Service
+// Keeps the store's settings in sync with the settings file owned by Rust
+
+import { listen } from '@tauri-apps/api/event';
+import { useAppStore } from '../store/appStore';
+import type { AppState } from '../store/types';
+import type { Settings } from '../types';
+import { getSettings, importLegacySettings, updateSettings } from './ipc.service';
+
+// Store field -> settings file field
+const SETTINGS_FIELDS = {
+  theme: 'theme',
+  savePath: 'save_path',
+  autoSave: 'auto_save',
+  autoSaveTemplate: 'auto_save_template',
+  hotkeys: 'hotkeys',
+  toolbarConfig: 'toolbar_config',
+  defaultTool: 'default_tool',
+  colorPalette: 'color_palette',
+  defaultStrokeWidth: 'default_stroke_width',
+  autoCloseAfterCopy: 'auto_close_after_copy',
+  autoCloseAfterSave: 'auto_close_after_save',
+  startAtLogin: 'start_at_login',
+  historyMaxEntries: 'history_max_entries',
+  historyMaxAgeDays: 'history_max_age_days',
+  controlServer: 'control_server',
+} as const satisfies Partial<Record<keyof AppState, keyof Settings>>;
+
+type StoreField = keyof typeof SETTINGS_FIELDS;
+
+const STORE_FIELDS = Object.keys(SETTINGS_FIELDS) as StoreField[];
+
+// Fields where null is a real value (not "keep the built-in default")
+const NULLABLE_FIELDS: ReadonlySet<StoreField> = new Set([
+  'savePath',
+  'historyMaxEntries',
+  'historyMaxAgeDays',
+]);
+
+// True while settings received from Rust are written to the store (so they are not sent back)
+let applyingRemote = false;
+let started = false;
+
+function applySettings(settings: Settings) {
+  const update: Partial<Record<StoreField, unknown>> = {};
+  for (const field of STORE_FIELDS) {
+    const value = settings[SETTINGS_FIELDS[field]];
+    // No saved toolbar layout yet: keep the built-in default
+    if (value !== null || NULLABLE_FIELDS.has(field)) {
+      update[field] = value;
+    }
+  }
+
+  applyingRemote = true;
+  try {
+    useAppStore.setState(update as Partial<AppState>);
+  } finally {
+    applyingRemote = false;
+  }
+}
+
+/**
+ * Load settings from Rust, follow changes made in other windows and save local changes.
+ * Call once per window; the previous localStorage copy is imported on the first run.
+ */
+export async function startSettingsSync(): Promise<void> {
+  if (started) return;
+  started = true;
+
+  try {
+    // Settings persisted by the webview before Rust owned them (ignored once a file exists)
+    const stored = localStorage.getItem('app-storage');
+    const legacyState = stored ? JSON.parse(stored).state : undefined;
+    applySettings(legacyState ? await importLegacySettings(legacyState) : await getSettings());
+  } catch (err) {
+    console.error('[Settings] Failed to load settings:', err);
+  }
+
+  await listen<Settings>('settings-changed', (e) => applySettings(e.payload));
+
+  useAppStore.subscribe((state, prev) => {
+    if (applyingRemote) return;
+
+    const patch: Partial<Record<keyof Settings, unknown>> = {};
+    for (const field of STORE_FIELDS) {
+      if (state[field] !== prev[field]) {
+        patch[SETTINGS_FIELDS[field]] = state[field];
+      }
+    }
+    if (Object.keys(patch).length === 0) return;
+
+    updateSettings(patch as Partial<Settings>).catch((err) => {
+      console.error('[Settings] Failed to save settings:', err);
+    });
+  });
+}
@@ -12,6 +12,7 @@ import { createSelectionSlice } from './slices/createSelectionSlice';
 import { createScreenshotSlice } from './slices/createScreenshotSlice';
 import { createAnnotationSlice } from './slices/createAnnotationSlice';
 import { createAISlice } from './slices/createAISlice';
+import { releaseCapture } from '../services/ipc.service';
 
 export const useAppStore = create<AppState>()(
   devtools(
@@ -30,6 +31,7 @@ export const useAppStore = create<AppState>()(
           theme: state.theme,
           savePath: state.savePath,
           autoSave: state.autoSave,
+          autoSaveTemplate: state.autoSaveTemplate,
           hotkeys: state.hotkeys,
           toolbarConfig: state.toolbarConfig,
           defaultTool: state.defaultTool,
@@ -37,6 +39,9 @@ export const useAppStore = create<AppState>()(
           defaultStrokeWidth: state.defaultStrokeWidth,
           autoCloseAfterCopy: state.autoCloseAfterCopy,
           autoCloseAfterSave: state.autoCloseAfterSave,
+          historyMaxEntries: state.historyMaxEntries,
+          historyMaxAgeDays: state.historyMaxAgeDays,
+          controlServer: state.controlServer,
           // We can persist other things if we want, like annotationStyle
         }),
       }
@@ -44,6 +49,16 @@ export const useAppStore = create<AppState>()(
   )
 );
 
+// The capture store holds the image of the screenshot being edited until it is replaced or closed
+useAppStore.subscribe((state, previous) => {
+  const captureId = previous.currentScreenshot?.captureId;
+  if (captureId && captureId !== state.currentScreenshot?.captureId) {
+    releaseCapture(captureId).catch((error) => {
+      console.error('Failed to release capture:', error);
+    });
+  }
+});
+
 // Selectors for common state combinations (Backward Compatibility)
 
 export const useOverlayState = () =>
@@ -36,4 +36,11 @@ describe('Settings Slice', () => {
         store.getState().setHotkey('capture', 'Ctrl+A');
         expect(store.getState().hotkeys['capture']).toBe('Ctrl+A');
     });
+
+    it('should update history retention', () => {
+        const store = useTestStore;
+        store.getState().setHistoryRetention(100, null);
+        expect(store.getState().historyMaxEntries).toBe(100);
+        expect(store.getState().historyMaxAgeDays).toBeNull();
+    });
 });
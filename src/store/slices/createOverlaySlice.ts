@@ -1,5 +1,5 @@
 import type { StateCreator } from 'zustand';
-import type { CaptureMode } from '../../types';
+import type { CaptureMode, SelectionTarget } from '../../types';
 
 export interface MonitorOffset {
     x: number;
@@ -12,12 +12,13 @@ export interface MonitorOffset {
 export interface OverlayState {
     isOverlayActive: boolean;
     currentMode: CaptureMode;
+    selectionTarget: SelectionTarget;
     showToolbar: boolean; // Part of overlay logic? Yes.
     isProcessing: boolean;
     monitorOffset: MonitorOffset; // Current monitor's position for coordinate translation
 
     // Actions
-    showOverlay: (mode?: CaptureMode) => void;
+    showOverlay: (mode?: CaptureMode, target?: SelectionTarget) => void;
     hideOverlay: () => Promise<void>;
     setMode: (mode: CaptureMode) => void;
     toggleToolbar: (show: boolean) => void;
@@ -30,11 +31,12 @@ export interface OverlayState {
 export const createOverlaySlice: StateCreator<OverlayState, [], [], OverlayState> = (set, get) => ({
     isOverlayActive: false,
     currentMode: 'capture',
+    selectionTarget: 'region',
     showToolbar: false,
     isProcessing: false,
     monitorOffset: { x: 0, y: 0, width: 1920, height: 1080, scaleFactor: 1 },
 
-    showOverlay: (mode = 'capture') => {
+    showOverlay: (mode = 'capture', target = 'region') => {
         // Call resetAIState() from AI slice
         const state = get() as any;
         if (state.resetAIState) state.resetAIState();
@@ -42,6 +44,7 @@ export const createOverlaySlice: StateCreator<OverlayState, [], [], OverlayState
         set({
             isOverlayActive: true,
             currentMode: mode,
+            selectionTarget: target,
             showToolbar: false,
             currentScreenshot: null
         } as any);
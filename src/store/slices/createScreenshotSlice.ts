@@ -6,6 +6,8 @@ export interface ScreenshotState {
 
     // Actions
     setScreenshot: (screenshot: Screenshot) => void;
+    setScreenshotHistoryId: (screenshotId: string, historyId: number) => void;
+    setScreenshotSavedPath: (screenshotId: string, savedPath: string) => void;
     clearScreenshot: () => void;
 }
 
@@ -18,6 +20,21 @@ export const createScreenshotSlice: StateCreator<ScreenshotState, [], [], Screen
             isOverlayActive: false, // Close overlay when we have a screenshot
         })),
 
+    // History entries are recorded in the background; ignore ones for a screenshot already closed
+    setScreenshotHistoryId: (screenshotId, historyId) =>
+        set((state) =>
+            state.currentScreenshot?.id === screenshotId
+                ? { currentScreenshot: { ...state.currentScreenshot, historyId } }
+                : {}
+        ),
+
+    setScreenshotSavedPath: (screenshotId, savedPath) =>
+        set((state) =>
+            state.currentScreenshot?.id === screenshotId
+                ? { currentScreenshot: { ...state.currentScreenshot, savedPath } }
+                : {}
+        ),
+
     clearScreenshot: () => {
         // Call resetAIState() and resetEditorState()
         const state = get() as any;
@@ -11,6 +11,7 @@ export interface SettingsState {
     theme: 'light' | 'dark' | 'system';
     savePath: string | null;
     autoSave: boolean;
+    autoSaveTemplate: string;
     hotkeys: Record<string, string>;
     toolbarConfig: ToolbarConfig;
     defaultTool: AnnotationTool;
@@ -19,11 +20,15 @@ export interface SettingsState {
     autoCloseAfterCopy: boolean;
     autoCloseAfterSave: boolean;
     startAtLogin: boolean;
+    historyMaxEntries: number | null;
+    historyMaxAgeDays: number | null;
+    controlServer: boolean;
 
     // Actions
     setTheme: (theme: 'light' | 'dark' | 'system') => void;
     setSavePath: (path: string) => void;
     setAutoSave: (enabled: boolean) => void;
+    setAutoSaveTemplate: (template: string) => void;
     setHotkey: (action: string, hotkey: string) => void;
     setDefaultTool: (tool: AnnotationTool) => void;
     setColorPalette: (colors: string[]) => void;
@@ -31,6 +36,8 @@ export interface SettingsState {
     setAutoCloseAfterCopy: (enabled: boolean) => void;
     setAutoCloseAfterSave: (enabled: boolean) => void;
     setStartAtLogin: (enabled: boolean) => void;
+    setHistoryRetention: (maxEntries: number | null, maxAgeDays: number | null) => void;
+    setControlServer: (enabled: boolean) => void;
 
     // Toolbar customization
     setToolbarItemEnabled: (groupId: ToolbarGroupId, itemId: ToolbarItemId, enabled: boolean) => void;
@@ -42,6 +49,7 @@ export const createSettingsSlice: StateCreator<SettingsState> = (set) => ({
     theme: 'system',
     savePath: null,
     autoSave: false,
+    autoSaveTemplate: 'JustSnap_{date}_{time}.{ext}',
     hotkeys: {
         'capture': 'Ctrl+Shift+S',
     },
@@ -52,10 +60,14 @@ export const createSettingsSlice: StateCreator<SettingsState> = (set) => ({
     autoCloseAfterCopy: true,
     autoCloseAfterSave: true,
     startAtLogin: false,
+    historyMaxEntries: 500,
+    historyMaxAgeDays: 90,
+    controlServer: false,
 
     setTheme: (theme) => set({ theme }),
     setSavePath: (savePath) => set({ savePath }),
     setAutoSave: (autoSave) => set({ autoSave }),
+    setAutoSaveTemplate: (autoSaveTemplate) => set({ autoSaveTemplate }),
     setHotkey: (action, hotkey) =>
         set((state) => ({
             hotkeys: { ...state.hotkeys, [action]: hotkey }
@@ -66,6 +78,9 @@ export const createSettingsSlice: StateCreator<SettingsState> = (set) => ({
     setAutoCloseAfterCopy: (enabled) => set({ autoCloseAfterCopy: enabled }),
     setAutoCloseAfterSave: (enabled) => set({ autoCloseAfterSave: enabled }),
     setStartAtLogin: (enabled) => set({ startAtLogin: enabled }),
+    setHistoryRetention: (historyMaxEntries, historyMaxAgeDays) =>
+        set({ historyMaxEntries, historyMaxAgeDays }),
+    setControlServer: (enabled) => set({ controlServer: enabled }),
 
     setToolbarItemEnabled: (groupId, itemId, enabled) =>
         set((state) => ({
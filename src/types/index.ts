@@ -6,6 +6,10 @@
 
 export type CaptureMode = 'capture' | 'scrolling' | 'record' | 'live';
 
+// What a capture-mode overlay lets the user pick: any region, a whole window (window capture
+// hotkey) or a region whose text is recognised and copied (OCR hotkey)
+export type SelectionTarget = 'region' | 'window' | 'text';
+
 export interface Region {
   x: number;
   y: number;
@@ -19,6 +23,9 @@ export interface Screenshot {
   region: Region;
   timestamp: number;
   mode: CaptureMode;
+  historyId?: number; // Capture history entry, once recorded
+  captureId?: string; // Capture store id of the unannotated image, when it lives there
+  savedPath?: string; // Where the capture was last saved
 }
 
 // ============================================
@@ -177,9 +184,178 @@ export interface TauriCaptureResponse {
   height: number;
 }
 
+// Actions a global hotkey can be bound to (mirrors Rust `HotkeyAction`)
+export type HotkeyAction =
+  | 'capture'
+  | 'full_screen'
+  | 'window_capture'
+  | 'repeat_region'
+  | 'open_last_sticky'
+  | 'ocr';
+
+// Why a hotkey was refused (mirrors Rust `HotkeyError`)
+export type HotkeyError =
+  | { kind: 'missing_key' }
+  | { kind: 'modifier_only' }
+  | { kind: 'unknown_modifier'; modifier: string }
+  | { kind: 'unknown_key'; key: string }
+  | { kind: 'no_modifier'; key: string }
+  | { kind: 'reserved'; shortcut: string }
+  | { kind: 'duplicate'; shortcut: string; action: HotkeyAction }
+  | { kind: 'registration_failed'; shortcut: string; message: string };
+
+// Canonical form returned by `validate_hotkey`
+export interface NormalizedHotkey {
+  shortcut: string; // e.g. "Ctrl+Shift+S"
+  modifiers: string[];
+  key: string;
+}
+
+export interface HotkeyRegistration {
+  action: HotkeyAction;
+  shortcut: string;
+  error: HotkeyError | null; // Why the shortcut could not be bound
+}
+
+export interface HotkeyBinding {
+  action: HotkeyAction;
+  shortcut: string;
+  delay_secs: number | null;
+}
+
+// Payload of the `hotkey-action` event
+export interface HotkeyActionEvent {
+  action: HotkeyAction;
+  capture_id: string | null;
+}
+
 export interface HotkeyConfig {
   key: string;
   modifiers: string[];
+  delay_secs?: number; // Timed capture: count down before capturing
+}
+
+// Payload of the `capture-countdown` event (mirrors Rust `CountdownTick`)
+export interface CaptureCountdown {
+  remaining: number; // Reaches 0 right before the capture runs
+  total: number;
+  cancelled: boolean;
+}
+
+// Provenance embedded in exported files (mirrors Rust `ExportMetadata`)
+export interface ExportMetadata {
+  captured_at?: string; // RFC 3339
+  monitor_name?: string;
+  window_title?: string;
+  scale_factor?: number;
+}
+
+// What `captureWindow` keeps around the window's content (mirrors Rust `WindowCaptureOptions`)
+export interface WindowCaptureOptions {
+  // Title bar and borders (default true). Leaving them out fails on macOS and for windows
+  // with client-side decorations, whose title bar is part of the content.
+  include_frame?: boolean;
+  include_shadow?: boolean; // Drop shadow where the platform draws one (default false)
+}
+
+// Clipboard representations set by `copyToClipboard` (mirrors Rust `ClipboardFlavour`)
+export type ClipboardFlavour = 'image' | 'text' | 'html' | 'file_list';
+
+// Extra flavours copied along with an image (mirrors Rust `ClipboardExtras`)
+export interface ClipboardExtras {
+  text?: string; // Plain text (e.g. OCR text), also the alt text of the HTML <img>
+  html?: boolean; // Offer the image as HTML with a data URI (default true)
+  files?: string[]; // File-list flavour, e.g. where the capture was saved
+}
+
+// Where an opened image goes ('none' only stores it)
+export type OpenTarget = 'editor' | 'sticky' | 'none';
+
+// Image stored like a capture (clipboard, files) (mirrors Rust `OpenedImage`)
+export interface OpenedImage {
+  capture_id: string;
+  width: number;
+  height: number;
+  path: string | null; // File the image was read from
+}
+
+// Payload of the `open-in-editor` event
+export interface OpenInEditorEvent {
+  capture_id: string;
+  width: number; // Image pixels
+  height: number;
+  captured_at: number; // Unix time in ms
+  history_id: number | null;
+  monitor: {
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+    scale_factor: number;
+  };
+}
+
+// Region remembered for "repeat last region" (mirrors Rust `RecentRegion`)
+export interface RecentRegion {
+  region: Region; // Virtual desktop pixels
+  monitor_id: number | null;
+  monitor_name: string | null;
+  monitor_origin: [number, number] | null;
+  captured_at: number; // Unix time in ms
+}
+
+export interface RepeatedCapture {
+  region: Region;
+  saved_path: string | null; // null = copied to the clipboard
+}
+
+// Settings file owned by the Rust backend (mirrors Rust `Settings`)
+export interface Settings {
+  version: number;
+  theme: 'light' | 'dark' | 'system';
+  save_path: string | null;
+  auto_save: boolean;
+  auto_save_template: string; // e.g. "{date}/{app_name}_{time}_{n}.{ext}"
+  hotkeys: Partial<Record<HotkeyAction, string>>;
+  toolbar_config: unknown | null; // Editor toolbar layout, owned by the frontend
+  default_tool: string;
+  color_palette: string[];
+  default_stroke_width: number;
+  auto_close_after_copy: boolean;
+  auto_close_after_save: boolean;
+  start_at_login: boolean;
+  history_max_entries: number | null; // null = no limit
+  history_max_age_days: number | null; // null = no limit
+  control_server: boolean; // JSON-RPC socket for scripts (endpoint and token in control.json)
+}
+
+// Capture history (mirrors Rust `history` module)
+export interface HistoryRegion {
+  x: number; // Virtual desktop pixels
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface HistoryEntry {
+  id: number;
+  captured_at: number; // Unix time in ms
+  width: number;
+  height: number;
+  region: HistoryRegion | null;
+  monitor_name: string | null;
+  window_title: string | null;
+  app_name: string | null;
+  saved_path: string | null;
+  ocr_text: string | null;
+}
+
+export interface HistoryQuery {
+  text?: string; // Matches OCR text, window title, app, monitor and saved path
+  since?: number; // Unix time in ms (inclusive)
+  until?: number; // Unix time in ms (exclusive)
+  limit?: number;
+  offset?: number;
 }
 
 // ============================================